@@ -83,6 +83,7 @@ pub fn devnet_config() -> Result<ChainSpec, String> {
 				],
 				vec![],
 				vec![],
+				vec![],
 				0,
 			)
 		},
@@ -127,6 +128,7 @@ pub fn testnet_config() -> Result<ChainSpec, String> {
 				],
 				vec![],
 				vec![],
+				vec![],
 				0,
 			)
 		},
@@ -145,45 +147,51 @@ pub fn testnet_config() -> Result<ChainSpec, String> {
 }
 
 // Includes for nakamoto genesis
-use std::{fs::File, path::PathBuf};
-use serde::{Deserialize};
-use serde_json as json;
-
+use std::path::Path;
+use crate::snapshot::{load_snapshot, parse_account, SubspaceJSONState};
 
 
 
-// Configure storage from nakamoto data
-#[derive(Deserialize, Debug)]
-struct SubspaceJSONState {
-	balances: std::collections::HashMap<String, u64>,
-	// subnet -> (name, tempo, immunity_period, min_allowed_weights, max_allowed_uids, founder)
-	subnets: Vec<(String, u16, u16, u16, u16,  String )>,
-	// module -> (key, name, address, stake, weights)
-	modules : Vec<Vec<(String, String, String, u64, Vec<(u16, u16)>)>>,
+pub fn mainnet_config(path: &Path) -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
+	let state: SubspaceJSONState = load_snapshot(path)?;
 
-	block: u64,
+	// Give front-ends necessary data to present to users
+	let mut properties = sc_service::Properties::new();
+	properties.insert("tokenSymbol".into(), "C".into());
+	properties.insert("tokenDecimals".into(), 9.into());
+	properties.insert("ss58Format".into(), 13116.into());
+	Ok(ChainSpec::from_genesis(
+		// Name
+		"Commune",
+		// ID
+		"commune",
+		ChainType::Development,
+		move || snapshot_genesis(wasm_binary, &state),
+		// Bootnodes
+		vec![
+		],
+		// Telemetry
+		None,
+		// Protocol ID
+		Some("commune"),
+		None,
+		// Properties
+		Some(properties),
+		// Extensions
+		None,
+	))
 
 }
 
-pub fn mainnet_config() -> Result<ChainSpec, String> {
-	let path: PathBuf = std::path::PathBuf::from("./snapshots/main.json");
-	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
-
-	// We mmap the file into memory first, as this is *a lot* faster than using
-	// `serde_json::from_reader`. See https://github.com/serde-rs/json/issues/160
-	let file = File::open(&path)
-		.map_err(|e| format!("Error opening genesis file `{}`: {}", path.display(), e))?;
 
-	// SAFETY: `mmap` is fundamentally unsafe since technically the file can change
-	//         underneath us while it is mapped; in practice it's unlikely to be a problem
-	let bytes = unsafe {
-		memmap2::Mmap::map(&file)
-			.map_err(|e| format!("Error mmaping genesis file `{}`: {}", path.display(), e))?
-	};
-
-	let state: SubspaceJSONState =
-		json::from_slice(&bytes).map_err(|e| format!("Error parsing genesis file: {}", e))?;
+// Keys of a snapshot are checked when it is loaded.
+fn snapshot_account(key: &str) -> AccountId {
+	parse_account(key).expect("snapshot keys are validated when it is loaded")
+}
 
+// Genesis of a chain relaunched from a snapshot.
+pub fn snapshot_genesis(wasm_binary: &[u8], state: &SubspaceJSONState) -> GenesisConfig {
 	let block : u64 = state.block;
 	let mut subnets: Vec<( Vec<u8>, u16, u16, u16 , u16, sp_runtime::AccountId32)> = Vec::new();
 	let mut modules: Vec<Vec<(sp_runtime::AccountId32, Vec<u8>, Vec<u8>, u64, Vec<(u16,u16)>)>> = Vec::new();
@@ -195,14 +203,14 @@ pub fn mainnet_config() -> Result<ChainSpec, String> {
 					 subnet.2, 
 					 subnet.3, 
 					 subnet.4, 
-					 sp_runtime::AccountId32::from(<sr25519::Public as Ss58Codec>::from_ss58check(&subnet.5).unwrap()),
+					 snapshot_account(&subnet.5),
 					));
 
 		// Add empty modules
 		modules.push(Vec::new());
-		for (uid, module) in state.modules[netuid].iter().enumerate() {
+		for module in state.modules[netuid].iter() {
 			modules[netuid].push((
-				sp_runtime::AccountId32::from(<sr25519::Public as Ss58Codec>::from_ss58check(&module.0).unwrap()),
+				snapshot_account(&module.0),
 				module.1.as_bytes().to_vec(), // key
 				module.2.as_bytes().to_vec(), // name
 				module.3, // stake 
@@ -212,60 +220,33 @@ pub fn mainnet_config() -> Result<ChainSpec, String> {
 
 	}
 
-	let mut balances_issuance: u64 = 0;
 	let mut processed_balances: Vec<(sp_runtime::AccountId32, u64)> = Vec::new();
 	for (key_str, amount) in state.balances.iter() {
-		let key = <sr25519::Public as Ss58Codec>::from_ss58check(&key_str).unwrap();
-		let key_account = sp_runtime::AccountId32::from(key);
+		processed_balances.push((snapshot_account(key_str), *amount));
+	}
 
-		processed_balances.push((key_account, *amount));
-		balances_issuance += *amount;
+	let mut vesting: Vec<(sp_runtime::AccountId32, u64, u64, u64)> = Vec::new();
+	for (key_str, locked, cliff, duration) in state.vesting.iter() {
+		vesting.push((snapshot_account(key_str), *locked, *cliff, *duration));
 	}
 
-	// Give front-ends necessary data to present to users
-	let mut properties = sc_service::Properties::new();
-	properties.insert("tokenSymbol".into(), "C".into());
-	properties.insert("tokenDecimals".into(), 9.into());
-	properties.insert("ss58Format".into(), 13116.into());
-	Ok(ChainSpec::from_genesis(
-		// Name
-		"Commune",
-		// ID
-		"commune",
-		ChainType::Development,
-		move || {
-			network_genesis(
-				wasm_binary,
-				// Initial PoA authorities (Validators)
-				// aura | grandpa
-				vec![
-					// Keys for debug
-					authority_keys_from_seed("Alice"), authority_keys_from_seed("Bob"),
-					], 
-				// Sudo account
-				Ss58Codec::from_ss58check("5GYs4kBRGo3VH1wgzYEs8UeP2ABSotNNmvaeXs9vJUiGEThJ").unwrap(), 
-				// Pre-funded a
-				processed_balances.clone(), // balances
-				modules.clone(), // modules,
-				subnets.clone(), // subnets,
-				block,
-				
-			)
-		},
-		// Bootnodes
+	network_genesis(
+		wasm_binary,
+		// Initial PoA authorities (Validators)
+		// aura | grandpa
 		vec![
-		],
-		// Telemetry
-		None,
-		// Protocol ID
-		Some("commune"),
-		None,
-		// Properties
-		Some(properties),
-		// Extensions
-		None,
-	))
-
+			// Keys for debug
+			authority_keys_from_seed("Alice"), authority_keys_from_seed("Bob"),
+			], 
+		// Sudo account
+		Ss58Codec::from_ss58check("5GYs4kBRGo3VH1wgzYEs8UeP2ABSotNNmvaeXs9vJUiGEThJ").unwrap(), 
+		// Pre-funded a
+		processed_balances, // balances
+		modules, // modules,
+		subnets, // subnets,
+		vesting, // vesting,
+		block,
+	)
 }
 
 
@@ -277,6 +258,7 @@ fn network_genesis(
 	balances: Vec<(AccountId, u64)>,
 	modules: Vec<Vec<(AccountId,Vec<u8>, Vec<u8>, u64, Vec<(u16, u16)>)>>,
 	subnets: Vec<(Vec<u8>, u16, u16, u16, u16 ,AccountId)>,
+	vesting: Vec<(AccountId, u64, u64, u64)>,
 	block: u64,
 
 
@@ -307,6 +289,7 @@ fn network_genesis(
 			// Add names to storage.
 			modules: modules,
 			subnets: subnets,
+			vesting: vesting,
 			block: block,
 		},
 	}
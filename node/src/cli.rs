@@ -7,6 +7,10 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	// Snapshot the `main` and `dev` chains are launched from.
+	#[arg(long, global = true, value_name = "PATH", default_value = crate::snapshot::DEFAULT_SNAPSHOT_PATH)]
+	pub snapshot: std::path::PathBuf,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -27,6 +31,12 @@ pub enum Subcommand {
 	// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	// Export the state of a given block into the snapshot JSON format.
+	ExportSnapshot(crate::snapshot::ExportSnapshotCmd),
+
+	// Check a snapshot file without starting the node.
+	ValidateSnapshot(crate::snapshot::ValidateSnapshotCmd),
+
 	// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
@@ -38,8 +38,8 @@ impl SubstrateCli for Cli {
 	fn load_spec(&self, id: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
 		Ok(match id {
 			 "test" => Box::new(chain_spec::testnet_config()?),
-			  "main" =>Box::new(chain_spec::mainnet_config()?),
-			  "dev" => Box::new(chain_spec::mainnet_config()?),
+			  "main" =>Box::new(chain_spec::mainnet_config(&self.snapshot)?),
+			  "dev" => Box::new(chain_spec::mainnet_config(&self.snapshot)?),
 			path =>
 				Box::new(chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(path))?),
 		})
@@ -82,6 +82,14 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
+		Some(Subcommand::ExportSnapshot(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(&*client)
+			})
+		},
+		Some(Subcommand::ValidateSnapshot(cmd)) => cmd.run(),
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -0,0 +1,16 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+
+sp_api::decl_runtime_apis! {
+	pub trait SubspaceRuntimeApi<Balance> where Balance: Codec {
+		// Emission per block at the current block, capped by the remaining supply.
+		fn get_total_emission_per_block() -> Balance;
+		// Projected emission per block at a future block, assuming the current total stake.
+		fn get_emission_per_block_at(block: u64) -> Balance;
+		// Projected total emission over the next blocks, assuming the current total stake.
+		fn get_projected_emission(blocks: u64) -> Balance;
+		// Tokens left to emit before the max supply is reached.
+		fn get_remaining_supply() -> Balance;
+	}
+}
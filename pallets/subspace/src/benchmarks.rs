@@ -1,191 +1,73 @@
 //! Subspace pallet benchmarking.
 
 #![cfg(feature = "runtime-benchmarks")]
-//mod benchmarking;
-
 
 use crate::*;
 use crate::Pallet as Subspace;
-use frame_benchmarking::{benchmarks, whitelisted_caller, account};
+use frame_benchmarking::{benchmarks, account};
 use frame_system::RawOrigin;
 use frame_support::sp_std::vec;
-use frame_support::inherent::Vec;
-pub use pallet::*;
-use frame_support::assert_ok;
-//use mock::{Test, new_test_ext};
-
-benchmarks! {
-   
-  // Add individual benchmarks here
-  benchmark_epoch_without_weights { 
-
-    // This is a whitelisted caller who can make transaction without weights.
-    let caller: T::AccountId = whitelisted_caller::<AccountIdOf<T>>(); 
-    let caller_origin = <T as frame_system::Config>::RuntimeOrigin::from(RawOrigin::Signed(caller.clone()));
-
-    // Lets create a single network.
-    let n: u16 = 4096;
-    let netuid: u16 = 0; //11 is the benchmark network.
-    let epoch: u16 = 1;
-    let name: Vec<u8> = "DefaultModule".as_bytes().to_vec();
-    assert_ok!( Subspace::<T>::do_add_network( RawOrigin::Root.into(), netuid.try_into().unwrap(), name.into(),  epoch.into()));
-    Subspace::<T>::set_max_allowed_uids( netuid, n ); 
-
-    // Lets fill the network with 100 UIDS and no weights.
-    let mut seed : u32 = 1;
-    for uid in 0..n as u16 {
-        let block_number: u64 = Subspace::<T>::get_current_block_as_u64();
-        let key: T::AccountId = account("Alice", 0, seed);
-        Subspace::<T>::append_module( netuid, &key, block_number );
-        seed = seed + 1;
+use sp_std::vec::Vec;
+
+const SEED: u32 = 1;
+
+// Creates a network named "DefaultModule" and fills it with n modules, each staking stake.
+fn add_network_with_modules<T: Config>( n: u16, stake: BalanceOf<T> ) -> ( u16, Vec<T::AccountId> ) {
+    let founder: T::AccountId = account( "Founder", 0, SEED );
+    let netuid: u16 = Subspace::<T>::add_network_from_name( "DefaultModule".as_bytes().to_vec().try_into().unwrap(), &founder ).unwrap();
+    Subspace::<T>::set_max_allowed_uids( netuid, n.max( 1 ) );
+    Subspace::<T>::set_max_registrations_per_block( netuid, n.max( 1 ) );
+
+    let address: BoundedAddress<T> = "0.0.0.0:30333".as_bytes().to_vec().try_into().unwrap();
+    let mut keys: Vec<T::AccountId> = vec![];
+    for i in 0..n {
+        let key: T::AccountId = account( "Alice", i as u32, SEED );
+        let mut name: Vec<u8> = "module".as_bytes().to_vec();
+        name.extend( i.to_le_bytes().to_vec() );
+        Subspace::<T>::add_balance_to_account( &key, stake.saturating_add( stake ) ).unwrap();
+        Subspace::<T>::append_module( netuid, &key, name.try_into().unwrap(), address.clone(), stake ).unwrap();
+        keys.push( key );
     }
+    ( netuid, keys )
+}
 
-  }: _( RawOrigin::Signed( caller.clone() ) )
-
-  // Add individual benchmarks here
-  /*benchmark_drain_emission { 
-
-    // This is a whitelisted caller who can make transaction without weights.
-    let caller: T::AccountId = whitelisted_caller::<AccountIdOf<T>>(); 
-    let caller_origin = <T as frame_system::Config>::RuntimeOrigin::from(RawOrigin::Signed(caller.clone()));
-
-    // Lets create a single network.
-    let n: u16 = 4096;
-    let netuid: u16 = 0; //11 is the benchmark network.
-    let epoch: u16 = 1;
-        let name: Vec<u8> = "DefaultModule".as_bytes().to_vec();
-    Subspace::<T>::do_add_network( caller_origin.clone(), netuid.try_into().unwrap(), name.into(), epoch.into());
-    Subspace::<T>::set_max_allowed_uids( netuid, n ); 
-    Subspace::<T>::set_epoch( netuid, epoch );
-
-    // Lets fill the network with 100 UIDS and no weights.
-    let mut seed : u32 = 1;
-    let mut emission: Vec<(T::AccountId, u64)> = vec![];
-    for uid in 0..n as u16 {
-        let block_number: u64 = Subspace::<T>::get_current_block_as_u64();
-        let key: T::AccountId = account("Alice", 0, SEED);
-        Subspace::<T>::append_module( netuid, &key, block_number );
-        SEED = SEED + 1;
-        emission.push( ( key, 1 ) );
-    }
-    Subspace::<T>::sink_emission( netuid, emission );
- 
-  }: _( RawOrigin::Signed( caller.clone() ) )  */
-
-
-  benchmark_register { 
-
-    // This is a whitelisted caller who can make transaction without weights.
-    let caller: T::AccountId = whitelisted_caller::<AccountIdOf<T>>(); 
-
-    // Lets create a single network.
-    let n: u16 = 10;
-    let netuid: u16 = 0; //11 is the benchmark network.
-    let epoch: u16 = 1;
-    let name: Vec<u8> = "DefaultModule".as_bytes().to_vec();
-
-
-    assert_ok!(Subspace::<T>::do_add_network( RawOrigin::Root.into(), netuid.try_into().unwrap(), name.into(), epoch.into()));
-    
-    let block_number: u64 = Subspace::<T>::get_current_block_as_u64();
-    let key: T::AccountId = account("Alice", 0, seed);
-        
-  }: register( RawOrigin::Signed( caller.clone() ), netuid  )
-
-  benchmark_set_weights {
-    
-    // This is a whitelisted caller who can make transaction without weights.
-    let caller: T::AccountId = whitelisted_caller::<AccountIdOf<T>>(); 
-    let caller_origin = <T as frame_system::Config>::RuntimeOrigin::from(RawOrigin::Signed(caller.clone()));
-    let netuid: u16 = 0;
-    let epoch: u16 = 1;
-    let name: Vec<u8> = "DefaultModule".as_bytes().to_vec();
-   
-    assert_ok!( Subspace::<T>::do_add_network( RawOrigin::Root.into(), name.into(),  epoch.into(), n.into()));
-    Subspace::<T>::set_max_allowed_uids( netuid, 4096 ); 
-
-   assert_ok!(Subspace::<T>::do_sudo_set_max_registrations_per_block(RawOrigin::Root.into(), netuid.try_into().unwrap(), 4096 ));
-    
-    let mut seed : u32 = 1; 
-    let mut dests: Vec<u16> = vec![];
-    let mut weights: Vec<u16> = vec![];
-    let signer : T::AccountId = account("Alice", 0, seed);
-    let name : Vec<u8> = "Alice".as_bytes().to_vec();
-    let ip: Vec<u8> = "0.0.0.0".as_bytes().to_vec();
-    let port: u16 = 30333;
-
-    for id in 0..4096 as u16 {
-      let block_number: u64 = Subspace::<T>::get_current_block_as_u64();
-      let start_nonce: u64 = (39420842u64 + 100u64*id as u64).into();
-      
-      let key: T::AccountId = account("Alice", 0, seed);
-      seed = seed +1;
-    
-      
-      let block_number: u64 = Subspace::<T>::get_current_block_as_u64();
-      
-      assert_ok!( Subspace::<T>::do_registration(caller_origin.clone(), netuid.try_into().unwrap(), name.clone(), ip.clone(), port )); 
-
-      let uid = Subspace::<T>::get_uid_for_key(netuid, &key.clone()).unwrap();
-      dests.push(id.clone());
-      weights.push(id.clone());
-    }
-
-  }: set_weights(RawOrigin::Signed( signer.clone() ), netuid, dests, weights)
-
-
-  benchmark_add_stake {
-    let caller: T::AccountId = whitelisted_caller::<AccountIdOf<T>>(); 
-    let caller_origin = <T as frame_system::Config>::RuntimeOrigin::from(RawOrigin::Signed(caller.clone()));
-    let epoch: u16 = 1;
-    let n : u32 = 4096;
-    let name: Vec<u8> = "DefaultModule".as_bytes().to_vec();
-
-    assert_ok!( Subspace::<T>::do_add_network( RawOrigin::Root.into(), name.into(),  epoch, n ));
-    Subspace::<T>::set_max_allowed_uids( netuid, 4096 ); 
-    assert_eq!(Subspace::<T>::get_max_allowed_uids(netuid), 4096);
-
-    let block_number: u64 = Subspace::<T>::get_current_block_as_u64();
-    let start_nonce: u64 = (39420842u64 + 100u64*netuid as u64).into();
-    let mut seed : u32 = 1;
-    let key: T::AccountId = account("Alice", 0, seed);
-
-    assert_ok!( Subspace::<T>::do_registration(caller_origin.clone(), netuid.try_into().unwrap() , name.clone(), ip.clone(), port ));
-
-    let amount: u64 = 1;
-    let amoun_to_be_staked = Subspace::<T>::u64_to_balance( 1000000000);
-
-    Subspace::<T>::add_balance_to_account(&key.clone(), amoun_to_be_staked.unwrap());
-
-  }: add_stake(RawOrigin::Signed( key.clone() ), amount)
-
-  benchmark_remove_stake{
-    let caller: T::AccountId = whitelisted_caller::<AccountIdOf<T>>(); 
-    let caller_origin = <T as frame_system::Config>::RuntimeOrigin::from(RawOrigin::Signed(caller.clone()));
-    let netuid: u16 = 0;
-    let epoch: u16 = 1;
-    let name: Vec<u8> = "DefaultModule".as_bytes().to_vec();
-    let network: Vec<u8> = "commune".as_bytes().to_vec();
-    let netuid = Subspace::<T>::get_netuid_for_name(name.clone()).unwrap();
-
-    assert_ok!( Subspace::<T>::do_add_network( RawOrigin::Root.into(), name.into(),  epoch.into(), n.into()));
-    Subspace::<T>::set_max_allowed_uids( netuid, 4096 ); 
-    assert_eq!(Subspace::<T>::get_max_allowed_uids(netuid), 4096);
-
-    let block_number: u64 = Subspace::<T>::get_current_block_as_u64();
-    let start_nonce: u64 = (39420842u64 + 100u64*netuid as u64).into();
-    let mut seed : u32 = 1;
-    let key: T::AccountId = caller; 
-
-    assert_ok!( Subspace::<T>::do_registration(caller_origin.clone(), netuid.try_into().unwrap() , name.clone(), ip.clone(), port));
-
-    let amoun_to_be_staked = Subspace::<T>::u64_to_balance( 1000000000);
-    Subspace::<T>::add_balance_to_account(&key.clone(), amoun_to_be_staked.unwrap());
-
-    assert_ok!( Subspace::<T>::add_stake(RawOrigin::Signed( key.clone() ).into(), 1000));
-
-    let amount_unstaked: u64 = 1;
-
-  }: remove_stake(RawOrigin::Signed( key.clone() ), key.clone(), amount_unstaked)
-
+benchmarks! {
 
+  // set_weights only touches the weights and last update of the caller's uid, so
+  // its cost grows with the number of weights rather than with the size of the network.
+  set_weights {
+    let w in 1 .. 4095;
+    let ( netuid, keys ) = add_network_with_modules::<T>( w as u16 + 1, 1_000_000_000u64.saturated_into() );
+    Subspace::<T>::set_min_allowed_weights( netuid, 1 );
+    Subspace::<T>::set_max_allowed_weights( netuid, w as u16 );
+
+    let uids: Vec<u16> = ( 1..=w as u16 ).collect();
+    let weights: Vec<u16> = uids.clone();
+  }: set_weights( RawOrigin::Signed( keys[0].clone() ), netuid, uids, weights )
+  verify {
+    assert_eq!( Subspace::<T>::get_last_update_for_uid( netuid, 0 ), Subspace::<T>::get_current_block_as_u64() );
+  }
+
+  register {
+    let ( _, _ ) = add_network_with_modules::<T>( 10, 1_000_000_000u64.saturated_into() );
+    let key: T::AccountId = account( "Bob", 0, SEED );
+    let stake: BalanceOf<T> = 1_000_000_000u64.saturated_into();
+    Subspace::<T>::add_balance_to_account( &key, stake.saturating_add( stake ) ).unwrap();
+  }: register( RawOrigin::Signed( key.clone() ), "DefaultModule".as_bytes().to_vec(), "Bob".as_bytes().to_vec(), "0.0.0.0:30333".as_bytes().to_vec(), stake )
+
+  add_stake {
+    let ( netuid, keys ) = add_network_with_modules::<T>( 1, 1_000_000_000u64.saturated_into() );
+  }: add_stake( RawOrigin::Signed( keys[0].clone() ), netuid, 1_000u64.saturated_into() )
+
+  remove_stake {
+    let ( netuid, keys ) = add_network_with_modules::<T>( 1, 1_000_000_000u64.saturated_into() );
+  }: remove_stake( RawOrigin::Signed( keys[0].clone() ), netuid, 1_000u64.saturated_into() )
+
+  // The epoch writes one incentive, dividends and emission entry per uid.
+  epoch {
+    let n in 1 .. 4096;
+    let ( netuid, _ ) = add_network_with_modules::<T>( n as u16, 1_000_000_000u64.saturated_into() );
+  }: {
+    Subspace::<T>::epoch( netuid, 1_000_000_000u64.saturated_into() );
+  }
+}
@@ -0,0 +1,138 @@
+use super::*;
+use frame_system::ensure_root;
+use frame_support::pallet_prelude::DispatchResult;
+use frame_support::storage::IterableStorageMap;
+use sp_std::vec::Vec;
+use sp_std::mem::size_of;
+
+impl<T: Config> Pallet<T> {
+
+
+    pub fn do_set_emission_schedule(
+        origin: T::RuntimeOrigin,
+        schedule: EmissionSchedule<BalanceOf<T>>,
+        max_supply: BalanceOf<T>,
+    ) -> DispatchResult {
+        ensure_root( origin )?;
+        ensure!( Self::check_emission_schedule( &schedule ), Error::<T>::InvalidEmissionSchedule );
+        Self::set_emission_schedule_and_max_supply( schedule, max_supply );
+        Ok(())
+    }
+
+    pub fn set_emission_schedule_and_max_supply( schedule: EmissionSchedule<BalanceOf<T>>, max_supply: BalanceOf<T> ) {
+        Schedule::<T>::put( schedule.clone() );
+        MaxSupply::<T>::put( max_supply );
+        EmissionHalvings::<T>::put( 0 );
+        Self::update_emission_halvings();
+        log::info!("EmissionScheduleSet( schedule:{:?}, max_supply:{:?} )", schedule, max_supply );
+        Self::deposit_event( Event::EmissionScheduleSet( schedule, max_supply ) );
+    }
+
+    // Stake checkpoints must be strictly increasing and block halvings need a non zero interval.
+    pub fn check_emission_schedule( schedule: &EmissionSchedule<BalanceOf<T>> ) -> bool {
+        match schedule {
+            EmissionSchedule::StakeHalving { checkpoints } => {
+                checkpoints.windows( 2 ).all( |pair| pair[0] < pair[1] )
+            },
+            EmissionSchedule::BlockHalving { interval } => *interval > 0,
+            EmissionSchedule::Constant => true,
+        }
+    }
+
+
+    // Tokens in existence: the total issuance, which includes the stake locked on balances,
+    // emission that is queued on subnets and treasury and founder shares, both not yet minted.
+    pub fn get_total_supply() -> BalanceOf<T> {
+        let mut pending_emission: BalanceOf<T> = Zero::zero();
+        for ( _, pending ) in <PendingEmission<T> as IterableStorageMap<u16, BalanceOf<T>>>::iter() {
+            pending_emission = pending_emission.saturating_add( pending );
+        }
+        return T::Currency::total_issuance()
+            .saturating_add( pending_emission )
+            .saturating_add( TotalPendingShares::<T>::get() );
+    }
+
+    pub fn get_remaining_supply() -> BalanceOf<T> {
+        return MaxSupply::<T>::get().saturating_sub( Self::get_total_supply() );
+    }
+
+
+    // Returns the number of halvings the stake halving schedule applies at the current total stake.
+    pub fn get_stake_halvings( checkpoints: &[BalanceOf<T>] ) -> u32 {
+        let total_stake: BalanceOf<T> = Self::get_total_stake();
+        return checkpoints.iter().filter( |checkpoint| total_stake >= **checkpoint ).count() as u32;
+    }
+
+    // Halvings under the stake schedule only ever go up, so unstaking can not raise the emission.
+    pub fn update_emission_halvings() {
+        if let EmissionSchedule::StakeHalving { checkpoints } = Schedule::<T>::get() {
+            let halvings: u32 = Self::get_stake_halvings( &checkpoints );
+            if halvings > EmissionHalvings::<T>::get() {
+                EmissionHalvings::<T>::put( halvings );
+            }
+        }
+    }
+
+
+    // Emission per block dictated by the schedule at the given block, ignoring the max supply.
+    pub fn get_scheduled_emission_at( block_number: u64 ) -> BalanceOf<T> {
+        let unit_emission: BalanceOf<T> = UnitEmission::<T>::get();
+        let halvings: u64 = match Schedule::<T>::get() {
+            EmissionSchedule::StakeHalving { checkpoints } => {
+                Self::get_stake_halvings( &checkpoints ).max( EmissionHalvings::<T>::get() ) as u64
+            },
+            EmissionSchedule::BlockHalving { interval } => block_number / interval.max( 1 ),
+            EmissionSchedule::Constant => 0,
+        };
+        if halvings >= ( size_of::<BalanceOf<T>>() * 8 ) as u64 {
+            return Zero::zero();
+        }
+        return unit_emission >> halvings as u32;
+    }
+
+    // First block after block_number at which the scheduled emission can change on its own.
+    pub fn get_next_emission_change( block_number: u64 ) -> u64 {
+        match Schedule::<T>::get() {
+            EmissionSchedule::BlockHalving { interval } => {
+                let interval: u64 = interval.max( 1 );
+                ( block_number / interval ).saturating_add( 1 ).saturating_mul( interval )
+            },
+            _ => u64::MAX,
+        }
+    }
+
+
+    // Returns the total emission for this block, capped by the remaining supply.
+    pub fn get_total_emission_per_block() -> BalanceOf<T> {
+        let scheduled_emission: BalanceOf<T> = Self::get_scheduled_emission_at( Self::get_current_block_as_u64() );
+        return scheduled_emission.min( Self::get_remaining_supply() );
+    }
+
+    // Projects the emission per block at a future block, assuming the current total stake.
+    pub fn get_emission_per_block_at( block_number: u64 ) -> BalanceOf<T> {
+        return Self::get_scheduled_emission_at( block_number ).min( Self::get_remaining_supply() );
+    }
+
+    // Projects the total emission over the next blocks, assuming the current total stake.
+    pub fn get_projected_emission( blocks: u64 ) -> BalanceOf<T> {
+        let mut remaining_supply: BalanceOf<T> = Self::get_remaining_supply();
+        let mut block_number: u64 = Self::get_current_block_as_u64();
+        let end_block: u64 = block_number.saturating_add( blocks );
+        let mut total_emission: BalanceOf<T> = Zero::zero();
+
+        while block_number < end_block && !remaining_supply.is_zero() {
+            let emission_per_block: BalanceOf<T> = Self::get_scheduled_emission_at( block_number );
+            if emission_per_block.is_zero() {
+                break;
+            }
+            let segment_end: u64 = Self::get_next_emission_change( block_number ).min( end_block );
+            let segment_blocks: BalanceOf<T> = ( segment_end - block_number ).saturated_into();
+            let segment_emission: BalanceOf<T> = segment_blocks.saturating_mul( emission_per_block ).min( remaining_supply );
+            total_emission = total_emission.saturating_add( segment_emission );
+            remaining_supply = remaining_supply.saturating_sub( segment_emission );
+            block_number = segment_end;
+        }
+        return total_emission;
+    }
+
+}
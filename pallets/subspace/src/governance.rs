@@ -0,0 +1,241 @@
+use super::*;
+use frame_support::pallet_prelude::{DispatchResult, DispatchError};
+use frame_support::weights::Weight;
+use sp_std::vec::Vec;
+
+// Most votes of tallied proposals removed in one block, the rest are removed in the next blocks.
+const MAX_VOTE_REMOVALS_PER_BLOCK: u32 = 1_024;
+
+impl<T: Config> Pallet<T> {
+
+
+    pub fn do_add_global_proposal(
+        origin: T::RuntimeOrigin,
+        params: GlobalParams<BalanceOf<T>>,
+    ) -> DispatchResult {
+        // --- 1. Check the caller's signature.
+        let key = ensure_signed( origin )?;
+
+        // --- 2. Only keys with stake on some subnet may propose.
+        ensure!( !Self::get_total_stake_for_key( &key ).is_zero(), Error::<T>::NotEnoughStakeToPropose );
+
+        // --- 3. Reject parameters that would leave the chain in an unusable state.
+        ensure!( Self::check_global_params( &params ), Error::<T>::InvalidGlobalParams );
+
+        // --- 4. Store the proposal.
+        Self::add_proposal( key, ProposalData::GlobalParams( params ) )?;
+        Ok(())
+    }
+
+
+    pub fn do_add_emission_proposal(
+        origin: T::RuntimeOrigin,
+        schedule: EmissionSchedule<BalanceOf<T>>,
+        max_supply: BalanceOf<T>,
+    ) -> DispatchResult {
+        let key = ensure_signed( origin )?;
+        ensure!( !Self::get_total_stake_for_key( &key ).is_zero(), Error::<T>::NotEnoughStakeToPropose );
+        ensure!( Self::check_emission_schedule( &schedule ), Error::<T>::InvalidEmissionSchedule );
+        Self::add_proposal( key, ProposalData::Emission( schedule, max_supply ) )?;
+        Ok(())
+    }
+
+
+    pub fn do_add_treasury_proposal(
+        origin: T::RuntimeOrigin,
+        dest: T::AccountId,
+        amount: BalanceOf<T>,
+    ) -> DispatchResult {
+        let key = ensure_signed( origin )?;
+        ensure!( !Self::get_total_stake_for_key( &key ).is_zero(), Error::<T>::NotEnoughStakeToPropose );
+        Self::add_proposal( key, ProposalData::TreasurySpend( dest, amount ) )?;
+        Ok(())
+    }
+
+
+    // Stores a new proposal, voting closes after the voting period. Proposing is free of transaction fees,
+    // so the open proposals are bounded and the proposer burns the proposal cost from its free balance.
+    // Proposals of this block were already tallied, so voting closes at the next block at the earliest.
+    pub fn add_proposal( key: T::AccountId, data: ProposalData<T::AccountId, BalanceOf<T>> ) -> Result<u64, DispatchError> {
+        ensure!( Proposals::<T>::count() < MaxProposals::<T>::get(), Error::<T>::TooManyProposals );
+        ensure!( ProposalsPerKey::<T>::get( &key ) < MaxProposalsPerKey::<T>::get(), Error::<T>::TooManyProposalsForKey );
+        let cost: BalanceOf<T> = ProposalCost::<T>::get();
+        let burned = T::Currency::withdraw( &key, cost, WithdrawReasons::FEE, ExistenceRequirement::KeepAlive )
+            .map_err( |_| Error::<T>::NotEnoughBalanceToPropose )?;
+        drop( burned );
+
+        let proposal_id: u64 = NextProposalId::<T>::get();
+        let expiration_block: u64 = Self::get_current_block_as_u64().saturating_add( ProposalVotingPeriod::<T>::get().max( 1 ) );
+        Proposals::<T>::insert( proposal_id, Proposal {
+            id: proposal_id,
+            proposer: key.clone(),
+            data: data,
+            expiration_block: expiration_block,
+        });
+        ProposalsAt::<T>::insert( expiration_block, proposal_id, () );
+        ProposalsPerKey::<T>::mutate( &key, |count| *count = count.saturating_add( 1 ) );
+        NextProposalId::<T>::put( proposal_id + 1 );
+
+        log::info!("ProposalCreated( proposal_id:{:?}, key:{:?} )", proposal_id, key );
+        Self::deposit_event( Event::ProposalCreated( proposal_id, key ) );
+        return Ok( proposal_id );
+    }
+
+
+    pub fn do_vote_proposal(
+        origin: T::RuntimeOrigin,
+        proposal_id: u64,
+        approve: bool,
+    ) -> DispatchResult {
+        // --- 1. Check the caller's signature.
+        let key = ensure_signed( origin )?;
+
+        // --- 2. The proposal must still be open.
+        let proposal = Proposals::<T>::get( proposal_id ).ok_or( Error::<T>::ProposalDoesNotExist )?;
+        ensure!( Self::get_current_block_as_u64() < proposal.expiration_block, Error::<T>::ProposalExpired );
+
+        // --- 3. Votes are weighted by the stake of the key when it votes, a key without stake cannot vote.
+        let stake: BalanceOf<T> = Self::get_total_stake_for_key( &key );
+        ensure!( !stake.is_zero(), Error::<T>::NotEnoughStakeToVote );
+
+        // --- 4. Record (or overwrite) the vote and move its stake in the running tally.
+        let previous_vote: Option<(bool, BalanceOf<T>)> = ProposalVotes::<T>::get( proposal_id, &key );
+        ProposalTallies::<T>::mutate( proposal_id, |( stake_for, stake_against )| {
+            match previous_vote {
+                Some(( true, previous_stake )) => *stake_for = stake_for.saturating_sub( previous_stake ),
+                Some(( false, previous_stake )) => *stake_against = stake_against.saturating_sub( previous_stake ),
+                None => {},
+            }
+            if approve {
+                *stake_for = stake_for.saturating_add( stake );
+            } else {
+                *stake_against = stake_against.saturating_add( stake );
+            }
+        });
+        ProposalVotes::<T>::insert( proposal_id, &key, ( approve, stake ) );
+
+        log::info!("ProposalVoted( proposal_id:{:?}, key:{:?}, approve:{:?} )", proposal_id, key, approve );
+        Self::deposit_event( Event::ProposalVoted( proposal_id, key, approve ) );
+        Ok(())
+    }
+
+
+    // Resolves the proposals whose voting period ends at this block from their running tallies and returns their weight.
+    // A proposal is accepted when the stake that voted reaches the quorum and the
+    // approving stake is larger than the rejecting stake.
+    pub fn resolve_proposals( block_number: u64 ) -> Weight {
+        let due: Vec<u64> = ProposalsAt::<T>::drain_prefix( block_number ).map( |( proposal_id, _ )| proposal_id ).collect();
+        let mut weight: Weight = T::DbWeight::get().reads_writes( 1 + due.len() as u64, due.len() as u64 );
+
+        for proposal_id in due.iter() {
+            let proposal = match Proposals::<T>::take( proposal_id ) {
+                Some( proposal ) => proposal,
+                None => continue,
+            };
+            let ( stake_for, stake_against ) = ProposalTallies::<T>::take( proposal.id );
+            let voted_stake: u128 = stake_for.saturating_add( stake_against ).saturated_into();
+            let total_stake: u128 = Self::get_total_stake().saturated_into();
+            let quorum_stake: u128 = total_stake.saturating_mul( ProposalQuorum::<T>::get() as u128 ) / 100;
+
+            let accepted: bool = voted_stake > 0 && voted_stake >= quorum_stake && stake_for > stake_against;
+            if accepted && Self::enact_proposal( &proposal.data ).is_ok() {
+                log::info!("ProposalAccepted( proposal_id:{:?} )", proposal.id );
+                Self::deposit_event( Event::ProposalAccepted( proposal.id ) );
+            } else {
+                log::info!("ProposalRejected( proposal_id:{:?} )", proposal.id );
+                Self::deposit_event( Event::ProposalRejected( proposal.id ) );
+            }
+
+            // --- The votes are removed in the next blocks, see remove_tallied_votes.
+            TalliedProposals::<T>::insert( proposal.id, () );
+            ProposalsPerKey::<T>::mutate_exists( &proposal.proposer, |count| {
+                *count = count.map( |count| count.saturating_sub( 1 ) ).filter( |count| *count > 0 );
+            });
+
+            // --- Enacting writes a few parameters.
+            weight = weight.saturating_add( Weight::from_ref_time( 30_000_000 ) )
+                .saturating_add( T::DbWeight::get().reads_writes( 8, 8 ) );
+        }
+        return weight;
+    }
+
+
+    // Removes the votes of tallied proposals, at most MAX_VOTE_REMOVALS_PER_BLOCK in a block, and returns their weight.
+    pub fn remove_tallied_votes() -> Weight {
+        let mut removals: u32 = MAX_VOTE_REMOVALS_PER_BLOCK;
+        let mut weight: Weight = T::DbWeight::get().reads( 1 );
+        while removals > 0 {
+            let proposal_id: u64 = match TalliedProposals::<T>::iter_keys().next() {
+                Some( proposal_id ) => proposal_id,
+                None => break,
+            };
+            let result = ProposalVotes::<T>::clear_prefix( proposal_id, removals, None );
+            removals = removals.saturating_sub( result.unique );
+            weight = weight.saturating_add( T::DbWeight::get().reads_writes( 1 + result.loops as u64, result.unique as u64 ) );
+
+            // --- A prefix is only cleared once per block, the rest of its votes wait for the next block.
+            if result.maybe_cursor.is_some() {
+                break;
+            }
+            TalliedProposals::<T>::remove( proposal_id );
+            weight = weight.saturating_add( T::DbWeight::get().writes( 1 ) );
+        }
+        return weight;
+    }
+
+
+    // Returns the ( approving, rejecting ) stake of the votes cast so far on this proposal.
+    pub fn tally_proposal( proposal_id: u64 ) -> ( BalanceOf<T>, BalanceOf<T> ) {
+        return ProposalTallies::<T>::get( proposal_id );
+    }
+
+
+    // Applies an accepted proposal. A treasury spend fails when the treasury can not cover it.
+    pub fn enact_proposal( data: &ProposalData<T::AccountId, BalanceOf<T>> ) -> DispatchResult {
+        match data {
+            ProposalData::GlobalParams( params ) => Self::set_global_params( params.clone() ),
+            ProposalData::Emission( schedule, max_supply ) => Self::set_emission_schedule_and_max_supply( schedule.clone(), *max_supply ),
+            ProposalData::TreasurySpend( dest, amount ) => return Self::spend_treasury( dest, *amount ),
+        }
+        Ok(())
+    }
+
+
+    pub fn check_global_params( params: &GlobalParams<BalanceOf<T>> ) -> bool {
+        let defaults: &SubnetParams = &params.subnet_defaults;
+        if params.max_allowed_subnets == 0 { return false; }
+        if defaults.tempo == 0 { return false; }
+        if defaults.max_allowed_uids == 0 { return false; }
+        if defaults.min_allowed_weights > defaults.max_allowed_weights { return false; }
+        if params.treasury_share > 100 { return false; }
+        return true;
+    }
+
+
+    pub fn get_global_params() -> GlobalParams<BalanceOf<T>> {
+        return GlobalParams {
+            max_allowed_subnets: MaxAllowedSubnets::<T>::get(),
+            unit_emission: UnitEmission::<T>::get(),
+            subnet_defaults: SubnetDefaults::<T>::get(),
+            treasury_share: TreasuryShare::<T>::get(),
+        };
+    }
+
+    pub fn set_global_params( params: GlobalParams<BalanceOf<T>> ) {
+        MaxAllowedSubnets::<T>::put( params.max_allowed_subnets );
+        UnitEmission::<T>::put( params.unit_emission );
+        SubnetDefaults::<T>::put( params.subnet_defaults.clone() );
+        TreasuryShare::<T>::put( params.treasury_share );
+        Self::deposit_event( Event::GlobalParamsUpdated( params ) );
+    }
+
+    pub fn get_proposal_voting_period() -> u64 { ProposalVotingPeriod::<T>::get() }
+    pub fn set_proposal_voting_period( voting_period: u64 ) { ProposalVotingPeriod::<T>::put( voting_period ) }
+    pub fn get_proposal_quorum() -> u16 { ProposalQuorum::<T>::get() }
+    pub fn set_proposal_quorum( quorum: u16 ) { ProposalQuorum::<T>::put( quorum ) }
+    pub fn get_proposal_cost() -> BalanceOf<T> { ProposalCost::<T>::get() }
+    pub fn set_proposal_cost( cost: BalanceOf<T> ) { ProposalCost::<T>::put( cost ) }
+    pub fn set_max_proposals( max_proposals: u32 ) { MaxProposals::<T>::put( max_proposals ) }
+    pub fn set_max_proposals_per_key( max_proposals_per_key: u16 ) { MaxProposalsPerKey::<T>::put( max_proposals_per_key ) }
+
+}
@@ -19,6 +19,9 @@ use frame_support::{
 	}, ensure, 
 	traits::{
 		Currency, 
+		LockableCurrency,
+		LockIdentifier,
+		Imbalance,
 		ExistenceRequirement,
 		tokens::{
 			WithdrawReasons
@@ -34,7 +37,14 @@ use sp_runtime::{
 		Dispatchable,
 		DispatchInfoOf,
 		SignedExtension,
-		PostDispatchInfoOf
+		PostDispatchInfoOf,
+		Zero,
+		Saturating,
+		CheckedAdd,
+		CheckedSub,
+		Bounded,
+		SaturatedConversion,
+		Verify
 	},
 	transaction_validity::{
 		TransactionValidity,
@@ -59,23 +69,41 @@ mod network;
 mod registration;
 mod staking;
 mod weights;
+mod governance;
+mod slashing;
+mod vesting;
+mod emission;
+mod treasury;
+mod voting;
+mod migration;
 pub mod module;
+pub mod weight_info;
+pub use weight_info::WeightInfo;
+pub use staking::STAKE_LOCK_ID;
+pub use vesting::VESTING_LOCK_ID;
 
 #[frame_support::pallet]
 pub mod pallet {
 	use frame_support::pallet_prelude::*;
 	use frame_system::pallet_prelude::*;
 	use frame_support::traits::Currency;
+	use frame_support::traits::LockableCurrency;
+	use sp_runtime::traits::{Zero, Bounded, SaturatedConversion, Verify, IdentifyAccount};
 	use frame_support::sp_std::vec;
 	use serde::{Serialize, Deserialize};
 	use serde_with::{serde_as, DisplayFromStr};
 	use frame_support::inherent::Vec;
 	use scale_info::prelude::string::String;
+	use frame_support::PalletId;
+	use crate::weight_info::WeightInfo;
 
 
+	// --- Current version of the pallet storage, bumped by every migration in migration.rs.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
-	#[pallet::without_storage_info]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	// Configure the pallet by specifying the parameters and types on which it depends.
@@ -84,13 +112,48 @@ pub mod pallet {
 		// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
-		// --- Currency type that will be used to place deposits on modules
-		type Currency: Currency<Self::AccountId> + Send + Sync;
+		// --- Currency type that will be used to place deposits on modules, stake is held as a lock on it.
+		type Currency: LockableCurrency<Self::AccountId, Moment = Self::BlockNumber> + Send + Sync;
+
+		// --- Id used to derive the treasury account that receives the treasury share of emission.
+		#[pallet::constant]
+		type TreasuryPalletId: Get<PalletId>;
+
+		// --- Longest name a module or network can have.
+		#[pallet::constant]
+		type MaxNameLength: Get<u32>;
+
+		// --- Longest address a module can serve on.
+		#[pallet::constant]
+		type MaxAddressLength: Get<u32>;
+
+		// --- Most weights a module can set, which also bounds the subnet votes of an account.
+		#[pallet::constant]
+		type MaxWeightsPerModule: Get<u32>;
+
+		// --- Most modules a subnet can hold, whatever its max_allowed_uids.
+		#[pallet::constant]
+		type MaxModulesPerSubnet: Get<u32>;
+
+		// --- Signature founders sign registration tickets of invite only subnets with.
+		type Signature: Verify<Signer = Self::Signer> + Parameter;
+
+		// --- Public key of a signature, identifying the account that signed it.
+		type Signer: IdentifyAccount<AccountId = Self::AccountId>;
+
+		// --- Weights of the benchmarked calls, see weight_info.rs.
+		type WeightInfo: WeightInfo;
 
 	}
 	
 
 	pub type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
+	pub type BoundedName<T> = BoundedVec<u8, <T as Config>::MaxNameLength>;
+	pub type BoundedAddress<T> = BoundedVec<u8, <T as Config>::MaxAddressLength>;
+	pub type BoundedWeights<T> = BoundedVec<(u16, u16), <T as Config>::MaxWeightsPerModule>;
+	pub type MaxHalvingCheckpoints = ConstU32<64>;
+	// Stake and emission are denominated in the balance of the currency.
+	pub type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 
 	// =======================================
@@ -112,6 +175,7 @@ pub mod pallet {
 	// ==============================
 
 	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
 	pub struct SubnetInfo<T: Config> {
 
 		// --- parameters
@@ -121,13 +185,64 @@ pub mod pallet {
 		pub min_allowed_weights: u16, // min number of weights allowed to be registered in this subnet
 		pub max_allowed_weights: u16, // max number of weights allowed to be registered in this subnet
 		pub max_allowed_uids: u16, // max number of uids allowed to be registered in this subnet
-		// pub mode: u8, // --- 0 for open, 1 for closed.
+		pub mode: RegistrationMode, // --- who can register modules on the subnet.
 		// state variables
 		pub netuid: u16, // --- unique id of the network
 		pub n : u16,
-		pub stake: u64,
-		pub emission: u64,
+		pub stake: BalanceOf<T>,
+		pub emission: BalanceOf<T>,
 		pub founder: T::AccountId,
+		pub founder_share: u16, // --- percentage of the subnet emission paid to the founder.
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub struct SubnetParams {
+		pub tempo: u16,
+		pub immunity_period: u16,
+		pub min_allowed_weights: u16,
+		pub max_allowed_weights: u16,
+		pub max_allowed_uids: u16,
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub enum EmissionSchedule<Balance> {
+		StakeHalving { checkpoints: BoundedVec<Balance, MaxHalvingCheckpoints> }, // --- Halve the unit emission once for every total stake checkpoint reached.
+		BlockHalving { interval: u64 }, // --- Halve the unit emission every interval blocks.
+		Constant, // --- Emit the unit emission every block until the max supply is reached.
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub struct GlobalParams<Balance> {
+		pub max_allowed_subnets: u16,
+		pub unit_emission: Balance, // emission per block before any halving is applied
+		pub subnet_defaults: SubnetParams, // hyperparameters given to newly created subnets
+		pub treasury_share: u16, // percentage of every subnet emission paid to the treasury
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub enum RegistrationMode {
+		Open, // --- Any key can register.
+		Allowlist, // --- Only keys on the allowlist of the founder can register.
+		Invite, // --- Only keys holding a registration ticket signed by the founder can register.
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo)]
+	pub struct RegistrationTicket<Signature> {
+		pub expires_at: u64, // last block in which the ticket can be used
+		pub signature: Signature, // signature of the founder over the registration ticket payload
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub struct WeightCopyDetection {
+		pub block: u64, // block of the epoch in which the weights were found copied
+		pub source_uid: u16, // uid whose previous weights were the closest match
+		pub similarity: u16, // cosine similarity with those weights, as a proportion of u16::MAX
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub enum SubnetEmissionMode {
+		Stake, // --- Each subnet receives its share of the total stake.
+		Vote, // --- Each subnet receives its share of the stake weighted subnet votes.
 	}
 
 
@@ -138,7 +253,7 @@ pub mod pallet {
 	#[pallet::type_value] 
 	pub fn DefaultN<T:Config>() -> u16 { 0 }
 	#[pallet::type_value] 
-	pub fn DefaultEmission<T:Config>() -> u64 { 0 }
+	pub fn DefaultEmission<T:Config>() -> BalanceOf<T> { Zero::zero() }
 	#[pallet::type_value] 
 	pub fn DefaultBlockAtRegistration<T: Config>() -> u64 { 0 }
 	#[pallet::type_value]
@@ -149,8 +264,6 @@ pub mod pallet {
 	pub fn DefaultMinAllowedWeights<T: Config>() -> u16 { 1 }
 	#[pallet::type_value] 
 	pub fn DefaultMaxAllowedWeights<T: Config>() -> u16 { 420 }
-	#[pallet::type_value] 
-	pub fn DefaultMaxNameLength<T: Config>() -> u16 { 32 }
 	#[pallet::type_value]
 	pub fn DefaultRegistrationsThisBlock<T: Config>() ->  u16 { 0}
 	#[pallet::type_value] 
@@ -158,14 +271,40 @@ pub mod pallet {
 	#[pallet::type_value] 
 	pub fn DefaultMaxAllowedSubnets<T: Config>() -> u16 { 100 }
 	#[pallet::type_value]
-	pub fn DefaultPendingEmission<T: Config>() ->  u64 { 0 }
+	pub fn DefaultPendingEmission<T: Config>() ->  BalanceOf<T> { Zero::zero() }
 	#[pallet::type_value]
 	pub fn DefaultTempo<T: Config>() -> u16 { 1 }
+	#[pallet::type_value]
+	pub fn DefaultSubnetImmunityPeriod<T: Config>() -> u64 { 10_800 } // one day of 8 second blocks
+	#[pallet::type_value]
+	pub fn DefaultUnitEmission<T: Config>() -> BalanceOf<T> { 4_000_000_000u64.saturated_into() }
+	#[pallet::type_value]
+	pub fn DefaultEmissionSchedule<T: Config>() -> EmissionSchedule<BalanceOf<T>> {
+		EmissionSchedule::StakeHalving {
+			checkpoints: BoundedVec::truncate_from( vec![2_000_000u64, 12_000_000, 22_000_000, 32_000_000].iter().map(|x| ( x * 1_000_000_000 ).saturated_into()).collect() )
+		}
+	}
+	#[pallet::type_value]
+	pub fn DefaultMaxSupply<T: Config>() -> BalanceOf<T> { BalanceOf::<T>::max_value() }
+	#[pallet::type_value]
+	pub fn DefaultSubnetParams<T: Config>() -> SubnetParams {
+		SubnetParams {
+			tempo: DefaultTempo::<T>::get(),
+			immunity_period: DefaultImmunityPeriod::<T>::get(),
+			min_allowed_weights: DefaultMinAllowedWeights::<T>::get(),
+			max_allowed_weights: DefaultMaxAllowedWeights::<T>::get(),
+			max_allowed_uids: DefaultMaxAllowedUids::<T>::get(),
+		}
+	}
 
 	#[pallet::storage] // --- ITEM( tota_number_of_existing_networks )
 	pub type TotalSubnets<T> = StorageValue<_, u16, ValueQuery>;
+	#[pallet::storage] // --- ITEM( next_netuid ) lowest netuid that has never been used.
+	pub type NextNetuid<T> = StorageValue<_, u16, ValueQuery>;
+	#[pallet::storage] // --- MAP ( netuid ) --> () netuids of removed networks, free to be reused.
+	pub type RemovedSubnets<T> = StorageMap<_, Identity, u16, (), OptionQuery>;
 	#[pallet::storage] // --- ITEM( tota_number_of_existing_networks )
-	pub type SubnetEmission<T> = StorageMap< _, Identity, u16, u64, ValueQuery, DefaultEmission<T> >;
+	pub type SubnetEmission<T> = StorageMap< _, Identity, u16, BalanceOf<T>, ValueQuery, DefaultEmission<T> >;
 	#[pallet::storage] // --- MAP ( netuid ) --> subnetwork_n (Number of UIDs in the network).
 	pub type N<T:Config> = StorageMap< _, Identity, u16, u16, ValueQuery, DefaultN<T> >;
 	#[pallet::storage] // --- DMAP ( key, netuid ) --> bool
@@ -173,13 +312,21 @@ pub mod pallet {
 
 	#[pallet::storage] // --- MAP ( netuid ) --> epoch
 	pub type Tempo<T> = StorageMap<_, Identity, u16, u16, ValueQuery, DefaultTempo<T> >;
+	#[pallet::storage] // --- MAP ( netuid ) --> founder_share
+	pub type FounderShare<T> = StorageMap<_, Identity, u16, u16, ValueQuery>;
+	#[pallet::storage] // --- ITEM ( treasury_share )
+	pub type TreasuryShare<T> = StorageValue<_, u16, ValueQuery>;
+	#[pallet::storage] // --- MAP ( key ) --> treasury and founder shares below the existential deposit, minted once they reach it.
+	pub type PendingShares<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+	#[pallet::storage] // --- ITEM ( total_pending_shares ) sum of the pending shares, counted in the total supply.
+	pub type TotalPendingShares<T> = StorageValue<_, BalanceOf<T>, ValueQuery>;
 	#[pallet::storage] // --- MAP ( netuid ) --> pending_emission
-	pub type PendingEmission<T> = StorageMap<_, Identity, u16, u64, ValueQuery, DefaultPendingEmission<T>>;
-	#[pallet::storage] // --- DMAP ( netuid ) --> bonds
-	pub(super) type MaxNameLength<T:Config> = StorageValue< _, u16, ValueQuery, DefaultMaxNameLength<T> >;
+	pub type PendingEmission<T> = StorageMap<_, Identity, u16, BalanceOf<T>, ValueQuery, DefaultPendingEmission<T>>;
 	
-	#[pallet::storage] // --- MAP ( netuid ) --> weights_set_rate_limit
-	pub type SubnetNamespace<T: Config> = StorageMap<_, Twox64Concat, Vec<u8>,  u16 , ValueQuery>;
+	#[pallet::storage] // --- MAP ( name ) --> netuid
+	pub type SubnetNamespace<T: Config> = StorageMap<_, Twox64Concat, BoundedName<T>,  u16 , ValueQuery>;
+	#[pallet::storage] // --- MAP ( netuid ) --> name, kept in sync with SubnetNamespace.
+	pub type SubnetNames<T: Config> = StorageMap<_, Identity, u16, BoundedName<T>, ValueQuery>;
 	#[pallet::storage] // --- MAP ( netuid ) --> max_allowed_uids
 	pub type MaxAllowedUids<T> = StorageMap<_, Identity, u16, u16, ValueQuery, DefaultMaxAllowedUids<T> >;
 	#[pallet::storage] // --- MAP ( netuid ) --> immunity_period
@@ -190,6 +337,43 @@ pub mod pallet {
 	pub type MaxAllowedWeights<T> = StorageMap< _, Identity, u16, u16, ValueQuery, DefaultMaxAllowedWeights<T> >;
 	#[pallet::storage] // --- MAP ( netuid ) --> weights_set_rate_limit
 	pub type BlockAtRegistration<T:Config> = StorageDoubleMap<_, Identity, u16, Identity, u16, u64, ValueQuery, DefaultBlockAtRegistration<T> >;
+	#[pallet::storage] // --- ITEM ( unit_emission )
+	pub type UnitEmission<T> = StorageValue<_, BalanceOf<T>, ValueQuery, DefaultUnitEmission<T>>;
+	#[pallet::storage] // --- ITEM ( emission_schedule )
+	pub type Schedule<T> = StorageValue<_, EmissionSchedule<BalanceOf<T>>, ValueQuery, DefaultEmissionSchedule<T>>;
+	#[pallet::storage] // --- ITEM ( max_supply ) cap on issuance plus pending emission, emission stops once reached.
+	pub type MaxSupply<T> = StorageValue<_, BalanceOf<T>, ValueQuery, DefaultMaxSupply<T>>;
+	#[pallet::storage] // --- ITEM ( emission_halvings ) halvings applied so far under the stake halving schedule.
+	pub type EmissionHalvings<T> = StorageValue<_, u32, ValueQuery>;
+	#[pallet::storage] // --- ITEM ( subnet_defaults )
+	pub type SubnetDefaults<T> = StorageValue<_, SubnetParams, ValueQuery, DefaultSubnetParams<T>>;
+	#[pallet::storage] // --- MAP ( netuid ) --> block_at_registration
+	pub type SubnetRegisteredAt<T> = StorageMap<_, Identity, u16, u64, ValueQuery>;
+	#[pallet::storage] // --- ITEM ( subnet_immunity_period ) blocks after registration during which a subnet can not be replaced.
+	pub type SubnetImmunityPeriod<T> = StorageValue<_, u64, ValueQuery, DefaultSubnetImmunityPeriod<T>>;
+
+	// ========================
+	// ==== Subnet Voting =====
+	// ========================
+	#[pallet::type_value]
+	pub fn DefaultSubnetEmissionMode<T: Config>() -> SubnetEmissionMode { SubnetEmissionMode::Stake }
+	#[pallet::type_value]
+	pub fn DefaultSubnetVoteInterval<T: Config>() -> u64 { 100 }
+	#[pallet::type_value]
+	pub fn DefaultMaxSubnetVoters<T: Config>() -> u32 { 512 }
+
+	#[pallet::storage] // --- ITEM ( emission_mode ) how the block emission is split between subnets.
+	pub type EmissionMode<T> = StorageValue<_, SubnetEmissionMode, ValueQuery, DefaultSubnetEmissionMode<T>>;
+	#[pallet::storage] // --- ITEM ( subnet_vote_majority ) percentage of stake for the consensus clip, 0 disables clipping.
+	pub type SubnetVoteMajority<T> = StorageValue<_, u16, ValueQuery>;
+	#[pallet::storage] // --- MAP ( key ) --> [ ( netuid, weight ) ]
+	pub type SubnetVotes<T: Config> = CountedStorageMap<_, Blake2_128Concat, T::AccountId, BoundedWeights<T>, ValueQuery>;
+	#[pallet::storage] // --- ITEM ( max_subnet_voters ) keys with a subnet vote, bounds the recomputation of the shares.
+	pub type MaxSubnetVoters<T> = StorageValue<_, u32, ValueQuery, DefaultMaxSubnetVoters<T>>;
+	#[pallet::storage] // --- ITEM ( subnet_vote_interval ) blocks between recomputations of the shares in vote mode.
+	pub type SubnetVoteInterval<T> = StorageValue<_, u64, ValueQuery, DefaultSubnetVoteInterval<T>>;
+	#[pallet::storage] // --- MAP ( netuid ) --> vote_share, recomputed every SubnetVoteInterval blocks in vote mode.
+	pub type SubnetVoteShare<T> = StorageMap<_, Identity, u16, u16, ValueQuery>;
 
 	// =======================================
 	// ==== Subnetwork Storage  ====
@@ -201,44 +385,81 @@ pub mod pallet {
 	#[pallet::type_value] 
 	pub fn EmptyBoolVec<T:Config>() -> Vec<bool> { vec![] }
 	#[pallet::type_value] 
-	pub fn DefaultWeights<T:Config>() -> Vec<(u16, u16)> { vec![] }
+	pub fn DefaultWeights<T:Config>() -> BoundedWeights<T> { BoundedVec::default() }
 	#[pallet::type_value] 
 	pub fn DefaultKey<T:Config>() -> T::AccountId { T::AccountId::decode(&mut sp_runtime::traits::TrailingZeroInput::zeroes()).unwrap() }
 
 	
-	#[pallet::storage] // --- DMAP ( netuid ) --> incentive
-	pub(super) type Incentive<T:Config> = StorageMap< _, Identity, u16, Vec<u16>, ValueQuery, EmptyU16Vec<T>>;
-	#[pallet::storage] // --- DMAP ( netuid ) --> dividends
-	pub(super) type Dividends<T:Config> = StorageMap< _, Identity, u16, Vec<u16>, ValueQuery, EmptyU16Vec<T>>;
-	#[pallet::storage] // --- DMAP ( netuid ) --> dividends
-	pub(super) type Emission<T:Config> = StorageMap< _, Identity, u16, Vec<u64>, ValueQuery, EmptyU64Vec<T>>;
-	#[pallet::storage] // --- DMAP ( netuid ) --> last_update
-	pub(super) type LastUpdate<T:Config> = StorageMap< _, Identity, u16, Vec<u64>, ValueQuery, EmptyU64Vec<T>>;
+	#[pallet::storage] // --- DMAP ( netuid, uid ) --> incentive
+	pub(super) type Incentive<T:Config> = StorageDoubleMap< _, Identity, u16, Identity, u16, u16, ValueQuery>;
+	#[pallet::storage] // --- DMAP ( netuid, uid ) --> dividends
+	pub(super) type Dividends<T:Config> = StorageDoubleMap< _, Identity, u16, Identity, u16, u16, ValueQuery>;
+	#[pallet::storage] // --- DMAP ( netuid, uid ) --> emission
+	pub(super) type Emission<T:Config> = StorageDoubleMap< _, Identity, u16, Identity, u16, BalanceOf<T>, ValueQuery>;
+	#[pallet::storage] // --- DMAP ( netuid, uid ) --> last_update
+	pub(super) type LastUpdate<T:Config> = StorageDoubleMap< _, Identity, u16, Identity, u16, u64, ValueQuery>;
+	#[pallet::storage] // --- MAP ( netuid ) --> blocks without activity after which a module is deregistered, 0 disables it.
+	pub type MaxInactiveBlocks<T> = StorageMap<_, Identity, u16, u64, ValueQuery>;
 	#[pallet::storage] // --- DMAP ( netuid, uid ) --> weights
-    pub(super) type Weights<T:Config> = StorageDoubleMap<_, Identity, u16, Identity, u16, Vec<(u16, u16)>, ValueQuery, DefaultWeights<T> >;
+    pub(super) type Weights<T:Config> = StorageDoubleMap<_, Identity, u16, Identity, u16, BoundedWeights<T>, ValueQuery, DefaultWeights<T> >;
+	#[pallet::storage] // --- MAP ( netuid ) --> weights have to be committed and revealed instead of set directly.
+	pub type CommitRevealWeights<T> = StorageMap<_, Identity, u16, bool, ValueQuery>;
+	#[pallet::storage] // --- DMAP ( netuid, uid ) --> ( hash of the committed weights, block of the commit )
+	pub type WeightCommits<T:Config> = StorageDoubleMap<_, Identity, u16, Identity, u16, (T::Hash, u64), OptionQuery>;
+	#[pallet::storage] // --- MAP ( netuid ) --> percentage of cosine similarity above which weights count as copied, 0 disables the detection.
+	pub type WeightCopyThreshold<T> = StorageMap<_, Identity, u16, u16, ValueQuery>;
+	#[pallet::storage] // --- MAP ( netuid ) --> percentage of the dividends taken from a module that copied weights.
+	pub type WeightCopyPenalty<T> = StorageMap<_, Identity, u16, u16, ValueQuery>;
+	#[pallet::storage] // --- DMAP ( netuid, uid ) --> weights of the uid at the previous epoch, kept while copy detection is on.
+	pub(super) type PreviousWeights<T:Config> = StorageDoubleMap<_, Identity, u16, Identity, u16, BoundedWeights<T>, ValueQuery, DefaultWeights<T> >;
+	#[pallet::storage] // --- DMAP ( netuid, uid ) --> last epoch in which the uid was penalised for copying weights.
+	pub type WeightCopyDetections<T:Config> = StorageDoubleMap<_, Identity, u16, Identity, u16, WeightCopyDetection, OptionQuery>;
 	#[pallet::storage] // --- DMAP ( netuid, key ) --> uid
 	pub(super) type Uids<T:Config> = StorageDoubleMap<_, Identity, u16, Blake2_128Concat, T::AccountId, u16, OptionQuery>;
 	#[pallet::storage] // --- DMAP ( netuid, uid ) --> key
 	pub(super) type Keys<T:Config> = StorageDoubleMap<_, Identity, u16, Identity, u16, T::AccountId, ValueQuery, DefaultKey<T> >;
 	#[pallet::storage]
-	pub type Namespace<T: Config> = StorageDoubleMap<_, Twox64Concat, u16, Twox64Concat, Vec<u8>, u16, ValueQuery>;
+	pub type Namespace<T: Config> = StorageDoubleMap<_, Twox64Concat, u16, Twox64Concat, BoundedName<T>, u16, ValueQuery>;
 	#[pallet::storage]
-	pub type Names<T: Config> = StorageDoubleMap<_, Twox64Concat, u16, Twox64Concat, u16, Vec<u8>, ValueQuery>;
+	pub type Names<T: Config> = StorageDoubleMap<_, Twox64Concat, u16, Twox64Concat, u16, BoundedName<T>, ValueQuery>;
 	#[pallet::storage]
-	pub type Address<T: Config> = StorageDoubleMap<_, Twox64Concat, u16, Twox64Concat, u16, Vec<u8>, ValueQuery>;
+	pub type Address<T: Config> = StorageDoubleMap<_, Twox64Concat, u16, Twox64Concat, u16, BoundedAddress<T>, ValueQuery>;
 
 	// ============================
 	// ==== Staking + Accounts ====
 	// ============================
 	#[pallet::type_value] 
-	pub fn DefaultStake<T: Config>() -> u64 { 0 }
+	pub fn DefaultStake<T: Config>() -> BalanceOf<T> { Zero::zero() }
 	#[pallet::type_value] 
 	pub fn DefaultAccount<T: Config>() -> T::AccountId { T::AccountId::decode(&mut sp_runtime::traits::TrailingZeroInput::zeroes()).unwrap()}
+	#[pallet::type_value] 
+	pub fn DefaultPayoutPreference<T: Config>() -> PayoutPreference<T::AccountId> { PayoutPreference::Compound }
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub enum PayoutPreference<AccountId> {
+		Compound, // --- Emission is minted into the key and locked as stake on the subnet.
+		Free, // --- Emission is minted into the free balance of the key.
+		Account(AccountId), // --- Emission is minted into the free balance of another account.
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub struct VestingSchedule<Balance> {
+		pub locked: Balance, // --- Balance of the key locked when the schedule starts.
+		pub start: u64, // --- Block the schedule starts at.
+		pub cliff: u64, // --- Blocks after the start before anything unlocks.
+		pub duration: u64, // --- Blocks after the cliff over which the locked balance unlocks linearly.
+	}
 
 	#[pallet::storage] // --- ITEM ( total_stake )
-	pub type TotalStake<T> = StorageValue<_, u64, ValueQuery>;
+	pub type TotalStake<T> = StorageValue<_, BalanceOf<T>, ValueQuery>;
 	#[pallet::storage] // --- DMAP ( hot, cold ) --> stake | Returns the stake under a key prefixed by key.
-	pub type Stake<T:Config> = StorageDoubleMap<_,Identity, u16,  Identity, T::AccountId, u64, ValueQuery, DefaultStake<T>>;
+	pub type Stake<T:Config> = StorageDoubleMap<_,Identity, u16,  Identity, T::AccountId, BalanceOf<T>, ValueQuery, DefaultStake<T>>;
+	#[pallet::storage] // --- MAP ( key ) --> stake of the key over every subnet, locked on its balance.
+	pub type LockedStake<T:Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery, DefaultStake<T>>;
+	#[pallet::storage] // --- MAP ( key ) --> where the emission of the key is paid to.
+	pub type PayoutPreferences<T:Config> = StorageMap<_, Blake2_128Concat, T::AccountId, PayoutPreference<T::AccountId>, ValueQuery, DefaultPayoutPreference<T>>;
+	#[pallet::storage] // --- MAP ( key ) --> vesting schedule locking part of the balance and stake of the key.
+	pub type Vesting<T:Config> = StorageMap<_, Blake2_128Concat, T::AccountId, VestingSchedule<BalanceOf<T>>, OptionQuery>;
 	#[pallet::storage] // --- MAP ( netuid ) --> Registration this Block.
 	pub type RegistrationsThisBlock<T> = StorageMap<_, Identity, u16, u16, ValueQuery, DefaultRegistrationsThisBlock<T>>;
 	#[pallet::storage] // --- ITEM( global_max_registrations_per_block ) 
@@ -246,7 +467,118 @@ pub mod pallet {
 	#[pallet::storage] // --- ITEM( global_max_registrations_per_block ) 
 	pub type MaxAllowedSubnets<T> = StorageValue<_, u16, ValueQuery, DefaultMaxAllowedSubnets<T>>;
 	#[pallet::storage] // --- ITEM ( total_stake )
-	pub type SubnetTotalStake<T> = StorageMap<_, Identity,u16, u64, ValueQuery>;
+	pub type SubnetTotalStake<T> = StorageMap<_, Identity,u16, BalanceOf<T>, ValueQuery>;
+	#[pallet::type_value] 
+	pub fn DefaultRegistrationMode<T: Config>() -> RegistrationMode { RegistrationMode::Open }
+	#[pallet::storage] // --- MAP ( netuid ) --> who can register modules on the subnet.
+	pub type RegistrationModes<T> = StorageMap<_, Identity, u16, RegistrationMode, ValueQuery, DefaultRegistrationMode<T>>;
+	#[pallet::storage] // --- DMAP ( netuid, key ) --> key may register on an allowlist subnet.
+	pub type RegistrationAllowlist<T:Config> = StorageDoubleMap<_, Identity, u16, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	// ====================
+	// ==== Governance ====
+	// ====================
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub enum ProposalData<AccountId, Balance> {
+		GlobalParams(GlobalParams<Balance>), // --- Replace the global parameters once accepted.
+		Emission(EmissionSchedule<Balance>, Balance), // --- Replace the emission schedule and max supply once accepted.
+		TreasurySpend(AccountId, Balance), // --- Transfer from the treasury account once accepted.
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub struct Proposal<AccountId, Balance> {
+		pub id: u64,
+		pub proposer: AccountId,
+		pub data: ProposalData<AccountId, Balance>,
+		pub expiration_block: u64, // --- Block at which the votes are tallied.
+	}
+
+	#[pallet::type_value]
+	pub fn DefaultProposalVotingPeriod<T: Config>() -> u64 { 75_600 } // one week of 8 second blocks
+	#[pallet::type_value]
+	pub fn DefaultProposalQuorum<T: Config>() -> u16 { 50 }
+	#[pallet::type_value]
+	pub fn DefaultProposalCost<T: Config>() -> BalanceOf<T> { 10_000_000_000u64.saturated_into() }
+	#[pallet::type_value]
+	pub fn DefaultMaxProposals<T: Config>() -> u32 { 128 }
+	#[pallet::type_value]
+	pub fn DefaultMaxProposalsPerKey<T: Config>() -> u16 { 4 }
+
+	#[pallet::storage] // --- ITEM ( next_proposal_id )
+	pub type NextProposalId<T> = StorageValue<_, u64, ValueQuery>;
+	#[pallet::storage] // --- MAP ( proposal_id ) --> proposal
+	pub type Proposals<T: Config> = CountedStorageMap<_, Identity, u64, Proposal<T::AccountId, BalanceOf<T>>, OptionQuery>;
+	#[pallet::storage] // --- DMAP ( expiration_block, proposal_id ) --> proposal tallied at the block
+	pub type ProposalsAt<T> = StorageDoubleMap<_, Identity, u64, Identity, u64, (), OptionQuery>;
+	#[pallet::storage] // --- MAP ( key ) --> open proposals of the key
+	pub type ProposalsPerKey<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u16, ValueQuery>;
+	#[pallet::storage] // --- DMAP ( proposal_id, key ) --> ( approve, stake counted for the vote )
+	pub type ProposalVotes<T: Config> = StorageDoubleMap<_, Identity, u64, Blake2_128Concat, T::AccountId, (bool, BalanceOf<T>), OptionQuery>;
+	#[pallet::storage] // --- MAP ( proposal_id ) --> ( approving stake, rejecting stake ) of the votes cast so far
+	pub type ProposalTallies<T: Config> = StorageMap<_, Identity, u64, (BalanceOf<T>, BalanceOf<T>), ValueQuery>;
+	#[pallet::storage] // --- MAP ( proposal_id ) --> tallied proposal whose votes are still being removed
+	pub type TalliedProposals<T> = StorageMap<_, Identity, u64, (), OptionQuery>;
+	#[pallet::storage] // --- ITEM ( proposal_voting_period )
+	pub type ProposalVotingPeriod<T> = StorageValue<_, u64, ValueQuery, DefaultProposalVotingPeriod<T>>;
+	#[pallet::storage] // --- ITEM ( proposal_quorum ) percentage of the total stake that has to vote.
+	pub type ProposalQuorum<T> = StorageValue<_, u16, ValueQuery, DefaultProposalQuorum<T>>;
+	#[pallet::storage] // --- ITEM ( proposal_cost ) burned from the free balance of the proposer.
+	pub type ProposalCost<T> = StorageValue<_, BalanceOf<T>, ValueQuery, DefaultProposalCost<T>>;
+	#[pallet::storage] // --- ITEM ( max_proposals ) open proposals over all keys.
+	pub type MaxProposals<T> = StorageValue<_, u32, ValueQuery, DefaultMaxProposals<T>>;
+	#[pallet::storage] // --- ITEM ( max_proposals_per_key ) open proposals of a single key.
+	pub type MaxProposalsPerKey<T> = StorageValue<_, u16, ValueQuery, DefaultMaxProposalsPerKey<T>>;
+
+	// ==================
+	// ==== Slashing ====
+	// ==================
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub enum SlashDestination {
+		Burn, // --- The slashed balance is removed from the issuance.
+		Treasury, // --- The slashed balance is paid into the treasury account.
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub struct PendingSlash<AccountId, Balance> {
+		pub netuid: u16,
+		pub key: AccountId,
+		pub percent: u16, // --- Percentage of the stake of the key on the subnet when the slash was scheduled.
+		pub amount: Balance, // --- Stake taken once applied, fixed when the slash is scheduled.
+		pub destination: SlashDestination,
+		pub apply_at: u64, // --- Block at which the slash is applied unless cancelled before.
+		pub scheduled_by: Option<AccountId>, // --- Key which scheduled the slash, None for root and the pallet itself.
+	}
+
+	#[derive(Decode, Encode, PartialEq, Eq, Clone, Debug, TypeInfo, MaxEncodedLen)]
+	pub struct SlashRecord<Balance> {
+		pub netuid: u16,
+		pub percent: u16,
+		pub amount: Balance, // --- Stake actually taken when the slash was applied.
+		pub destination: SlashDestination,
+		pub block: u64,
+	}
+
+	#[pallet::type_value]
+	pub fn DefaultSlashDeferPeriod<T: Config>() -> u64 { 10_800 } // one day of 8 second blocks
+	#[pallet::type_value]
+	pub fn DefaultMaxPendingSlashes<T: Config>() -> u32 { 256 }
+
+	#[pallet::storage] // --- ITEM ( next_slash_id )
+	pub type NextSlashId<T> = StorageValue<_, u64, ValueQuery>;
+	#[pallet::storage] // --- ITEM ( slash_defer_period ) blocks during which a scheduled slash can still be cancelled.
+	pub type SlashDeferPeriod<T> = StorageValue<_, u64, ValueQuery, DefaultSlashDeferPeriod<T>>;
+	#[pallet::storage] // --- ITEM ( max_pending_slashes ) bounds the slashes applied in a single block.
+	pub type MaxPendingSlashes<T> = StorageValue<_, u32, ValueQuery, DefaultMaxPendingSlashes<T>>;
+	#[pallet::storage] // --- MAP ( slash_id ) --> slash waiting for its deferral period to end
+	pub type PendingSlashes<T: Config> = CountedStorageMap<_, Identity, u64, PendingSlash<T::AccountId, BalanceOf<T>>, OptionQuery>;
+	#[pallet::storage] // --- MAP ( key ) --> number of pending slashes of the key, its stake can not leave it while any is pending.
+	pub type PendingSlashCount<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+	#[pallet::storage] // --- DMAP ( apply_at, slash_id ) --> pending slash applied at the block
+	pub type PendingSlashesAt<T> = StorageDoubleMap<_, Identity, u64, Identity, u64, (), OptionQuery>;
+	#[pallet::storage] // --- DMAP ( key, slash_id ) --> applied slash
+	pub type SlashHistory<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Identity, u64, SlashRecord<BalanceOf<T>>, OptionQuery>;
 
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/main-docs/build/events-errors/
@@ -257,8 +589,9 @@ pub mod pallet {
 		// parameters. [something, who]
 		NetworkAdded( u16, Vec<u8> ),	// --- Event created when a new network is added.
 		NetworkRemoved( u16 ), // --- Event created when a network is removed.
-		StakeAdded( T::AccountId, u64 ), // --- Event created when stake has been transfered from the a coldkey account onto the key staking account.
-		StakeRemoved( T::AccountId, u64 ), // --- Event created when stake has been removed from the key staking account onto the coldkey account.
+		NetworkReplaced( u16, Vec<u8> ), // --- Event created when the least staked network is replaced by a new network under the same netuid.
+		StakeAdded( T::AccountId, BalanceOf<T> ), // --- Event created when stake has been transfered from the a coldkey account onto the key staking account.
+		StakeRemoved( T::AccountId, BalanceOf<T> ), // --- Event created when stake has been removed from the key staking account onto the coldkey account.
 		WeightsSet( u16, u16 ), // ---- Event created when a caller successfully set's their weights on a subnetwork.
 		ModuleRegistered( u16, u16, T::AccountId ), // --- Event created when a new module account has been registered to the chain.
 		BulkModulesRegistered( u16, u16 ), // --- Event created when multiple uids have been concurrently registered.
@@ -270,6 +603,37 @@ pub mod pallet {
 		ModuleUpdated( u16, T::AccountId ), // --- Event created when the module server information is added to the network.
 		DelegateAdded( T::AccountId, T::AccountId, u16 ), // --- Event created to signal a key has become a delegate.
 		TxRateLimitSet( u64 ), // --- Event created when setting the transaction rate limit.
+		ProposalCreated( u64, T::AccountId ), // --- Event created when a governance proposal is submitted.
+		ProposalVoted( u64, T::AccountId, bool ), // --- Event created when a key votes on a proposal.
+		ProposalAccepted( u64 ), // --- Event created when a proposal reaches quorum and majority and is enacted.
+		ProposalRejected( u64 ), // --- Event created when a proposal expires without quorum or majority.
+		GlobalParamsUpdated( GlobalParams<BalanceOf<T>> ), // --- Event created when the global parameters are replaced.
+		EmissionScheduleSet( EmissionSchedule<BalanceOf<T>>, BalanceOf<T> ), // --- Event created when the emission schedule and max supply are replaced.
+		TreasuryEmission( u16, BalanceOf<T> ), // --- Event created when the treasury share of a subnet emission is paid.
+		FounderEmission( u16, T::AccountId, BalanceOf<T> ), // --- Event created when the founder share of a subnet emission is paid.
+		TreasurySpent( T::AccountId, BalanceOf<T> ), // --- Event created when an accepted spend proposal is paid out of the treasury.
+		FounderShareSet( u16, u16 ), // --- Event created when a founder sets the founder share of a subnet.
+		SubnetWeightsVoted( T::AccountId, u16 ), // --- Event created when a key votes on the weights of subnets.
+		SubnetEmissionModeSet( SubnetEmissionMode, u16 ), // --- Event created when the subnet emission mode and vote majority are set.
+		EpochEmission( u16, BalanceOf<T>, BalanceOf<T> ), // --- Event created every epoch with the pending emission of the subnet and the amount distributed to its modules.
+		EmissionMinted( u16, BalanceOf<T>, BalanceOf<T>, BalanceOf<T> ), // --- Event created when emission rewards of a subnet are minted, with the totals compounded into stake, paid to free balances and paid to other accounts.
+		StakeTransferred( T::AccountId, u16, u16, BalanceOf<T> ), // --- Event created when a key moves stake from one subnet to another.
+		StakeTransferredTo( T::AccountId, T::AccountId, u16, BalanceOf<T> ), // --- Event created when a key hands stake on a subnet over to another key.
+		PayoutPreferenceSet( T::AccountId, PayoutPreference<T::AccountId> ), // --- Event created when a key changes where its emission is paid to.
+		CommitRevealWeightsSet( u16, bool ), // --- Event created when the founder turns commit-reveal weights on or off for a subnet.
+		WeightCopyDetectionSet( u16, u16, u16 ), // --- Event created when the founder sets the similarity threshold and dividend penalty of weight copy detection.
+		RegistrationModeSet( u16, RegistrationMode ), // --- Event created when the founder changes who can register on a subnet.
+		AllowlistSet( u16, T::AccountId, bool ), // --- Event created when the founder adds a key to or removes it from the allowlist of a subnet.
+		Vested( T::AccountId, BalanceOf<T> ), // --- Event created when a key unlocks vested balance, with the balance still locked.
+		SlashScheduled( u64, u16, T::AccountId, u16, u64 ), // --- Event created when a slash of a percentage of the stake of a key is scheduled, with the block it applies at.
+		SlashCancelled( u64 ), // --- Event created when a pending slash is cancelled during its deferral period.
+		SlashApplied( u64, u16, T::AccountId, BalanceOf<T>, SlashDestination ), // --- Event created when a slash takes stake from a key.
+		SlashDeferPeriodSet( u64 ), // --- Event created when root sets the deferral period of new slashes.
+		MaxInactiveBlocksSet( u16, u64 ), // --- Event created when the founder sets after how many inactive blocks modules are deregistered.
+		ModuleDeregistered( u16, u16, T::AccountId ), // --- Event created when an inactive module is deregistered and its stake unlocked.
+		WeightsCommitted( u16, u16 ), // --- Event created when a module commits to the hash of its weights.
+		WeightsRevealed( u16, u16 ), // --- Event created when a module reveals the weights it committed to.
+		WeightCommitExpired( u16, u16 ), // --- Event created when a commit is dropped without being revealed in time.
 	}
 
 	// Errors inform users that something went wrong.
@@ -313,7 +677,40 @@ pub mod pallet {
 		NotEnoughStakeToStartNetwork,
 		NetworkRegistrationFailed,
 		NetworkAlreadyRegistered,
-		NoSelfWeight
+		NoSelfWeight,
+		InvalidGlobalParams, // --- Thrown when the proposed global parameters are out of range.
+		NotEnoughStakeToPropose, // --- Thrown when a key without stake submits a proposal.
+		NotEnoughStakeToVote, // --- Thrown when a key without stake votes on a proposal.
+		ProposalDoesNotExist, // --- Thrown when voting on a proposal that does not exist or was already tallied.
+		ProposalExpired, // --- Thrown when voting on a proposal after its voting period.
+		TooManyProposals, // --- Thrown when proposing while MaxProposals proposals are open.
+		TooManySubnetVoters, // --- Thrown when a key votes on subnets for the first time while MaxSubnetVoters keys have a vote.
+		TooManyProposalsForKey, // --- Thrown when the key proposing already has MaxProposalsPerKey open proposals.
+		NotEnoughBalanceToPropose, // --- Thrown when the free balance of the key does not cover the proposal cost.
+		InvalidEmissionSchedule, // --- Thrown when an emission schedule has a zero interval or unordered checkpoints.
+		InvalidShare, // --- Thrown when an emission share is above 100 percent.
+		SubnetNameTooLong, // --- Thrown when a network name is longer than MaxNameLength.
+		AddressTooLong, // --- Thrown when a module address is longer than MaxAddressLength.
+		BalanceMintError, // --- Thrown when emission could not be minted into an account.
+		ArithmeticOverflow, // --- Thrown when stake or emission arithmetic overflows the balance type.
+		SameSubnetTransfer, // --- Thrown when stake is transferred to the subnet it is taken from.
+		SameKeyTransfer, // --- Thrown when a key transfers stake to itself.
+		CommitRevealEnabled, // --- Thrown when weights are set directly on a subnet which requires commit-reveal.
+		CommitRevealDisabled, // --- Thrown when weights are committed on a subnet which does not use commit-reveal.
+		NoWeightCommit, // --- Thrown when weights are revealed without a commit.
+		WeightRevealTooEarly, // --- Thrown when weights are revealed before the epoch following their commit.
+		WeightCommitExpired, // --- Thrown when weights are revealed after the reveal window of the commit.
+		InvalidWeightReveal, // --- Thrown when the revealed weights and salt do not match the commit.
+		DuplicateSubnets, // --- Thrown when a batch of weights names the same subnet more than once.
+		RegistrationNotAllowed, // --- Thrown when a key registers on a subnet whose registration mode does not let it in.
+		InvalidRegistrationTicket, // --- Thrown when a registration ticket is expired or not signed by the founder for the key.
+		SlashDoesNotExist, // --- Thrown when cancelling a slash that is not pending.
+		NotSlashScheduler, // --- Thrown when a slash is cancelled by neither root nor the key which scheduled it.
+		TooManyPendingSlashes, // --- Thrown when scheduling a slash while MaxPendingSlashes are already pending.
+		StakeHasPendingSlash, // --- Thrown when removing or transferring stake of a key with a pending slash.
+		NotVesting, // --- Thrown when a key without a vesting schedule tries to vest.
+		NoReplaceableSubnet, // --- Thrown when a network is added at MaxAllowedSubnets while every network is immune.
+		RootCanNotPayStake, // --- Thrown when root bulk registers modules with stake, it has no balance to pay the stake from.
 	}
 
 	// ==================
@@ -324,9 +721,11 @@ pub mod pallet {
 	#[cfg(feature = "std")]
 	pub struct GenesisConfig<T: Config> {
 		// key, name, address, stake, weights 
-		pub modules: Vec<Vec<(T::AccountId, Vec<u8>, Vec<u8>, u64, Vec<(u16, u16)>)>>,
+		pub modules: Vec<Vec<(T::AccountId, Vec<u8>, Vec<u8>, BalanceOf<T>, Vec<(u16, u16)>)>>,
 		// name, tempo, immunity_period, max_allowed_uids, min_allowed_weight, max_registrations_per_block, max_allowed_weights
 		pub subnets: Vec<(Vec<u8>, u16, u16, u16, u16, T::AccountId)>,
+		// key, locked, cliff, duration
+		pub vesting: Vec<(T::AccountId, BalanceOf<T>, u64, u64)>,
 
 		pub block: u64,
 	}
@@ -337,6 +736,7 @@ pub mod pallet {
 			Self { 
 				modules: Default::default(),
 				subnets: Default::default(),
+				vesting: Default::default(),
 				block: Default::default(),
 			}
 		}
@@ -354,54 +754,56 @@ pub mod pallet {
 			
 			for (i, subnet) in self.subnets.iter().enumerate() {
 				let netuid: u16 = i as u16;
-				SubnetNamespace::<T>::insert( subnet.0.clone(), netuid );
+				let subnet_name: BoundedName<T> = subnet.0.clone().try_into().expect("subnet name longer than MaxNameLength");
+				SubnetNamespace::<T>::insert( subnet_name.clone(), netuid );
+				SubnetNames::<T>::insert( netuid, subnet_name );
 				Tempo::<T>::insert(netuid, subnet.1);
 				ImmunityPeriod::<T>::insert(netuid, subnet.2);
 				MinAllowedWeights::<T>::insert(netuid, subnet.3);
 				MaxAllowedUids::<T>::insert(netuid, subnet.4);
 				Founder::<T>::insert(netuid, subnet.5.clone());
 				TotalSubnets::<T>::mutate( |n| *n += 1 );
+				NextNetuid::<T>::put( netuid + 1 );
 				N::<T>::insert( netuid, 0 );
 
-				let mut incentive : Vec<u16> = Incentive::<T>::get(netuid );
-				let mut dividends : Vec<u16> = Dividends::<T>::get(netuid );
-				let mut  emission : Vec<u64> = Emission::<T>::get(netuid);
-				let mut last_update : Vec<u64> = LastUpdate::<T>::get(netuid);
-				
-				
+
 
 				for (uid_usize, (key, name, address, stake, weights)) in self.modules[i].iter().enumerate() {
 					let uid = uid_usize as u16;
+					let name: BoundedName<T> = name.clone().try_into().expect("module name longer than MaxNameLength");
+					let address: BoundedAddress<T> = address.clone().try_into().expect("module address longer than MaxAddressLength");
+					let weights: BoundedWeights<T> = weights.clone().try_into().expect("more weights than MaxWeightsPerModule");
 					Keys::<T>::insert(netuid, uid , key.clone());
 					Uids::<T>::insert(netuid, key.clone(), uid );
-					Names::<T>::insert(netuid, uid , name);
+					Names::<T>::insert(netuid, uid , name.clone());
 					Namespace::<T>::insert(netuid, name, uid);
 					Address::<T>::insert(netuid, uid , address);
 					Weights::<T>::insert(netuid, uid , weights);
 
-					incentive.push(0);
-					dividends.push(0);
-					emission.push(0);
-					last_update.push(0);
+					Incentive::<T>::insert(netuid, uid, 0);
+					Dividends::<T>::insert(netuid, uid, 0);
+					Emission::<T>::insert(netuid, uid, 0);
+					LastUpdate::<T>::insert(netuid, uid, 0);
 
 					
 
-					// increase  stake variables
-					Stake::<T>::insert(netuid,key.clone(), stake);
-					TotalStake::<T>::mutate( |n| *n += stake );
-					SubnetTotalStake::<T>::insert(netuid , SubnetTotalStake::<T>::get(netuid).saturating_add( *stake ) );
+					// genesis stake is not part of the balances, mint it and lock it on the key.
+					if let Err( error ) = Pallet::<T>::mint_to_account( key, *stake ) {
+						log::warn!("could not mint the genesis stake of {:?}: {:?}", key, error );
+					}
+					Pallet::<T>::increase_stake_on_account( netuid, key, *stake ).expect("genesis stake overflows the balance type");
 
 					N::<T>::insert( netuid, N::<T>::get(netuid) + 1 );
 					BlockAtRegistration::<T>::insert(netuid, uid , 0);
 					
 				}
 
-				Incentive::<T>::insert(netuid, incentive);
-				Dividends::<T>::insert(netuid, dividends);
-				Emission::<T>::insert(netuid, emission);
-				LastUpdate::<T>::insert(netuid, last_update);
 			}
-			
+
+			// vesting starts at the genesis block and locks balance and stake alike.
+			for (key, locked, cliff, duration) in self.vesting.iter() {
+				Pallet::<T>::add_vesting_schedule( key, VestingSchedule { locked: *locked, start: block, cliff: *cliff, duration: *duration } );
+			}
 
 		}
 	}
@@ -418,11 +820,17 @@ pub mod pallet {
 		// 	* 'n': (T::BlockNumber):
 		// 		- The number of the block we are initializing.
 		fn on_initialize( _block_number: BlockNumberFor<T> ) -> Weight {
-			Self::block_step();
+			let step_weight: Weight = Self::block_step();
 			
 			return Weight::from_ref_time(110_634_229_000 as u64)
 						.saturating_add(T::DbWeight::get().reads(8304 as u64))
-						.saturating_add(T::DbWeight::get().writes(110 as u64));
+						.saturating_add(T::DbWeight::get().writes(110 as u64))
+						.saturating_add(step_weight);
+		}
+
+		// ---- Runs the pending storage migrations, see migration.rs.
+		fn on_runtime_upgrade() -> Weight {
+			migration::migrate_to_v1::<T>()
 		}
 	}
 
@@ -432,9 +840,8 @@ pub mod pallet {
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 
-        #[pallet::weight((Weight::from_ref_time(10_151_000_000)
-		.saturating_add(T::DbWeight::get().reads(4104))
-		.saturating_add(T::DbWeight::get().writes(2)), DispatchClass::Normal, Pays::No))]
+        #[pallet::call_index(0)]
+        #[pallet::weight((T::WeightInfo::set_weights(uids.len() as u32), DispatchClass::Normal, Pays::No))]
 		pub fn set_weights(
 			origin:OriginFor<T>, 
 			netuid: u16,
@@ -445,18 +852,84 @@ pub mod pallet {
 		}
 
 
-		#[pallet::weight((Weight::from_ref_time(65_000_000)
-		.saturating_add(T::DbWeight::get().reads(8))
-		.saturating_add(T::DbWeight::get().writes(6)), DispatchClass::Normal, Pays::No))]
+        #[pallet::call_index(7)]
+        #[pallet::weight((subnet_weights.iter().fold( Weight::zero(), |weight, ( _, uids, _ )| weight.saturating_add( T::WeightInfo::set_weights(uids.len() as u32) ) ),
+			DispatchClass::Normal, Pays::No))]
+		pub fn set_weights_multi(
+			origin:OriginFor<T>, 
+			subnet_weights: Vec<(u16, Vec<u16>, Vec<u16>)>,
+		) -> DispatchResult {
+			Self::do_set_weights_multi( origin, subnet_weights )
+		}
+
+
+		#[pallet::call_index(8)]
+		#[pallet::weight((Weight::from_ref_time(15_000_000)
+		.saturating_add(T::DbWeight::get().reads(2))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn set_commit_reveal_weights(
+			origin: OriginFor<T>,
+			netuid: u16,
+			enabled: bool,
+		) -> DispatchResult {
+			Self::do_set_commit_reveal_weights( origin, netuid, enabled )
+		}
+
+
+		#[pallet::call_index(9)]
+		#[pallet::weight((Weight::from_ref_time(15_000_000)
+		.saturating_add(T::DbWeight::get().reads(2))
+		.saturating_add(T::DbWeight::get().writes(2)), DispatchClass::Normal, Pays::No))]
+		pub fn set_weight_copy_detection(
+			origin: OriginFor<T>,
+			netuid: u16,
+			threshold: u16,
+			penalty: u16,
+		) -> DispatchResult {
+			Self::do_set_weight_copy_detection( origin, netuid, threshold, penalty )
+		}
+
+
+		#[pallet::call_index(10)]
+		#[pallet::weight((Weight::from_ref_time(20_000_000)
+		.saturating_add(T::DbWeight::get().reads(5))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn commit_weights(
+			origin: OriginFor<T>,
+			netuid: u16,
+			commit_hash: T::Hash,
+		) -> DispatchResult {
+			Self::do_commit_weights( origin, netuid, commit_hash )
+		}
+
+
+        #[pallet::call_index(11)]
+        #[pallet::weight((Weight::from_ref_time(25_000_000 + 200_000 * uids.len() as u64)
+		.saturating_add(T::DbWeight::get().reads(10))
+		.saturating_add(T::DbWeight::get().writes(3)), DispatchClass::Normal, Pays::No))]
+		pub fn reveal_weights(
+			origin: OriginFor<T>,
+			netuid: u16,
+			uids: Vec<u16>,
+			weights: Vec<u16>,
+			salt: Vec<u8>,
+		) -> DispatchResult {
+			Self::do_reveal_weights( origin, netuid, uids, weights, salt )
+		}
+
+
+		#[pallet::call_index(1)]
+		#[pallet::weight((T::WeightInfo::add_stake(), DispatchClass::Normal, Pays::No))]
 		pub fn add_stake(
 			origin: OriginFor<T>, 
 			netuid: u16,
-			amount_staked: u64
+			amount_staked: BalanceOf<T>
 		) -> DispatchResult {
 			Self::do_add_stake(origin,netuid, amount_staked)
 		}
 
 
+		#[pallet::call_index(2)]
 		#[pallet::weight((Weight::from_ref_time(65_000_000)
 		.saturating_add(T::DbWeight::get().reads(8))
 		.saturating_add(T::DbWeight::get().writes(6)), DispatchClass::Normal, Pays::No))]
@@ -483,6 +956,7 @@ pub mod pallet {
 
 
 
+		#[pallet::call_index(3)]
 		#[pallet::weight((Weight::from_ref_time(65_000_000)
 		.saturating_add(T::DbWeight::get().reads(8))
 		.saturating_add(T::DbWeight::get().writes(6)), DispatchClass::Normal, Pays::No))]
@@ -494,18 +968,69 @@ pub mod pallet {
 		}
 
 
-		#[pallet::weight((Weight::from_ref_time(66_000_000)
-		.saturating_add(T::DbWeight::get().reads(8))
-		.saturating_add(T::DbWeight::get().writes(6)), DispatchClass::Normal, Pays::No))]
+		#[pallet::call_index(4)]
+		#[pallet::weight((T::WeightInfo::remove_stake(), DispatchClass::Normal, Pays::No))]
 		pub fn remove_stake(
 			origin: OriginFor<T>, 
 			netuid: u16,
-			amount_unstaked: u64
+			amount_unstaked: BalanceOf<T>
 		) -> DispatchResult {
 			Self::do_remove_stake(origin, netuid, amount_unstaked)
 		}
 
 
+		#[pallet::call_index(12)]
+		#[pallet::weight((Weight::from_ref_time(66_000_000)
+		.saturating_add(T::DbWeight::get().reads(10))
+		.saturating_add(T::DbWeight::get().writes(7)), DispatchClass::Normal, Pays::No))]
+		pub fn transfer_stake(
+			origin: OriginFor<T>, 
+			from_netuid: u16,
+			to_netuid: u16,
+			amount: BalanceOf<T>
+		) -> DispatchResult {
+			Self::do_transfer_stake(origin, from_netuid, to_netuid, amount)
+		}
+
+
+		#[pallet::call_index(13)]
+		#[pallet::weight((Weight::from_ref_time(80_000_000)
+		.saturating_add(T::DbWeight::get().reads(12))
+		.saturating_add(T::DbWeight::get().writes(9)), DispatchClass::Normal, Pays::No))]
+		pub fn transfer_stake_to(
+			origin: OriginFor<T>, 
+			netuid: u16,
+			dest: T::AccountId,
+			amount: BalanceOf<T>
+		) -> DispatchResult {
+			Self::do_transfer_stake_to(origin, netuid, dest, amount)
+		}
+
+
+
+		#[pallet::call_index(14)]
+		#[pallet::weight((Weight::from_ref_time(15_000_000)
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn set_payout_preference(
+			origin: OriginFor<T>, 
+			preference: PayoutPreference<T::AccountId>,
+		) -> DispatchResult {
+			Self::do_set_payout_preference(origin, preference)
+		}
+
+
+		#[pallet::call_index(15)]
+		#[pallet::weight((Weight::from_ref_time(15_000_000)
+		.saturating_add(T::DbWeight::get().reads(2))
+		.saturating_add(T::DbWeight::get().writes(2)), DispatchClass::Normal, Pays::No))]
+		pub fn vest(
+			origin: OriginFor<T>,
+		) -> DispatchResult {
+			Self::do_vest(origin)
+		}
+
+
+		#[pallet::call_index(5)]
 		#[pallet::weight((Weight::from_ref_time(19_000_000)
 		.saturating_add(T::DbWeight::get().reads(2))
 		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
@@ -520,17 +1045,236 @@ pub mod pallet {
 		}
 
 
-		#[pallet::weight((Weight::from_ref_time(91_000_000)
-		.saturating_add(T::DbWeight::get().reads(27))
-		.saturating_add(T::DbWeight::get().writes(22)), DispatchClass::Normal, Pays::No))]
+		#[pallet::call_index(6)]
+		#[pallet::weight((T::WeightInfo::register(), DispatchClass::Normal, Pays::No))]
 		pub fn register( 
 				origin:OriginFor<T>, 
 				network: Vec<u8>,
 				name: Vec<u8>,
 				address: Vec<u8>,
-				stake: u64, 
+				stake: BalanceOf<T>, 
 		) -> DispatchResult { 
-			Self::do_registration(origin, network , name, address, stake,)
+			Self::do_registration(origin, network , name, address, stake, None)
+		}
+
+
+		#[pallet::call_index(16)]
+		#[pallet::weight((T::WeightInfo::register()
+		.saturating_add(Weight::from_ref_time(29_000_000))
+		.saturating_add(T::DbWeight::get().reads(1)), DispatchClass::Normal, Pays::No))]
+		pub fn register_with_ticket( 
+				origin:OriginFor<T>, 
+				network: Vec<u8>,
+				name: Vec<u8>,
+				address: Vec<u8>,
+				stake: BalanceOf<T>, 
+				ticket: RegistrationTicket<T::Signature>,
+		) -> DispatchResult { 
+			Self::do_registration(origin, network , name, address, stake, Some(ticket))
+		}
+
+
+		#[pallet::call_index(17)]
+		#[pallet::weight((Weight::from_ref_time(15_000_000)
+		.saturating_add(T::DbWeight::get().reads(2))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn set_registration_mode(
+			origin: OriginFor<T>,
+			netuid: u16,
+			mode: RegistrationMode,
+		) -> DispatchResult {
+			Self::do_set_registration_mode(origin, netuid, mode)
+		}
+
+
+		#[pallet::call_index(18)]
+		#[pallet::weight((Weight::from_ref_time(15_000_000)
+		.saturating_add(T::DbWeight::get().reads(2))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn set_allowlisted(
+			origin: OriginFor<T>,
+			netuid: u16,
+			key: T::AccountId,
+			allowed: bool,
+		) -> DispatchResult {
+			Self::do_set_allowlisted(origin, netuid, key, allowed)
+		}
+
+
+		#[pallet::call_index(19)]
+		#[pallet::weight((Weight::from_ref_time(10_000_000)
+		.saturating_add(T::DbWeight::get().reads(2))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn heartbeat(
+			origin: OriginFor<T>,
+			netuid: u16,
+		) -> DispatchResult {
+			Self::do_heartbeat(origin, netuid)
+		}
+
+
+		#[pallet::call_index(20)]
+		#[pallet::weight((Weight::from_ref_time(15_000_000)
+		.saturating_add(T::DbWeight::get().reads(2))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn set_max_inactive_blocks(
+			origin: OriginFor<T>,
+			netuid: u16,
+			max_inactive_blocks: u64,
+		) -> DispatchResult {
+			Self::do_set_max_inactive_blocks(origin, netuid, max_inactive_blocks)
+		}
+
+
+		#[pallet::call_index(21)]
+		#[pallet::weight((Weight::from_ref_time(111_000_000 * modules.len() as u64)
+		.saturating_add(T::DbWeight::get().reads(5 + 14 * modules.len() as u64))
+		.saturating_add(T::DbWeight::get().writes(1 + 20 * modules.len() as u64)), DispatchClass::Normal, Pays::No))]
+		pub fn bulk_register( 
+				origin:OriginFor<T>, 
+				netuid: u16,
+				modules: Vec<(T::AccountId, Vec<u8>, Vec<u8>, BalanceOf<T>)>,
+		) -> DispatchResult { 
+			Self::do_bulk_register(origin, netuid, modules)
+		}
+
+
+		#[pallet::call_index(22)]
+		#[pallet::weight((Weight::from_ref_time(20_000_000)
+		.saturating_add(T::DbWeight::get().reads(4))
+		.saturating_add(T::DbWeight::get().writes(2)), DispatchClass::Normal, Pays::No))]
+		pub fn slash_module(
+			origin: OriginFor<T>,
+			netuid: u16,
+			key: T::AccountId,
+			percent: u16,
+			destination: SlashDestination,
+		) -> DispatchResult {
+			Self::do_slash_module(origin, netuid, key, percent, destination)
+		}
+
+
+		#[pallet::call_index(23)]
+		#[pallet::weight((Weight::from_ref_time(15_000_000)
+		.saturating_add(T::DbWeight::get().reads(2))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn cancel_slash(
+			origin: OriginFor<T>,
+			slash_id: u64,
+		) -> DispatchResult {
+			Self::do_cancel_slash(origin, slash_id)
+		}
+
+
+		#[pallet::call_index(24)]
+		#[pallet::weight((Weight::from_ref_time(10_000_000)
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Operational, Pays::No))]
+		pub fn set_slash_defer_period(
+			origin: OriginFor<T>,
+			defer_period: u64,
+		) -> DispatchResult {
+			Self::do_set_slash_defer_period(origin, defer_period)
+		}
+
+
+		#[pallet::call_index(25)]
+		#[pallet::weight((Weight::from_ref_time(45_000_000)
+		.saturating_add(T::DbWeight::get().reads(104))
+		.saturating_add(T::DbWeight::get().writes(2)), DispatchClass::Normal, Pays::No))]
+		pub fn add_global_proposal(
+			origin: OriginFor<T>,
+			params: GlobalParams<BalanceOf<T>>,
+		) -> DispatchResult {
+			Self::do_add_global_proposal(origin, params)
+		}
+
+
+		#[pallet::call_index(26)]
+		#[pallet::weight((Weight::from_ref_time(45_000_000)
+		.saturating_add(T::DbWeight::get().reads(104))
+		.saturating_add(T::DbWeight::get().writes(2)), DispatchClass::Normal, Pays::No))]
+		pub fn add_emission_proposal(
+			origin: OriginFor<T>,
+			schedule: EmissionSchedule<BalanceOf<T>>,
+			max_supply: BalanceOf<T>,
+		) -> DispatchResult {
+			Self::do_add_emission_proposal(origin, schedule, max_supply)
+		}
+
+
+		#[pallet::call_index(27)]
+		#[pallet::weight((Weight::from_ref_time(45_000_000)
+		.saturating_add(T::DbWeight::get().reads(104))
+		.saturating_add(T::DbWeight::get().writes(2)), DispatchClass::Normal, Pays::No))]
+		pub fn add_treasury_proposal(
+			origin: OriginFor<T>,
+			dest: T::AccountId,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			Self::do_add_treasury_proposal(origin, dest, amount)
+		}
+
+
+		#[pallet::call_index(28)]
+		#[pallet::weight((Weight::from_ref_time(19_000_000)
+		.saturating_add(T::DbWeight::get().reads(2))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn set_founder_share(
+			origin: OriginFor<T>,
+			netuid: u16,
+			founder_share: u16,
+		) -> DispatchResult {
+			Self::do_set_founder_share(origin, netuid, founder_share)
+		}
+
+
+		#[pallet::call_index(29)]
+		#[pallet::weight((Weight::from_ref_time(10_000_000)
+		.saturating_add(T::DbWeight::get().writes(2)), DispatchClass::Operational, Pays::No))]
+		pub fn set_emission_schedule(
+			origin: OriginFor<T>,
+			schedule: EmissionSchedule<BalanceOf<T>>,
+			max_supply: BalanceOf<T>,
+		) -> DispatchResult {
+			Self::do_set_emission_schedule(origin, schedule, max_supply)
+		}
+
+
+		#[pallet::call_index(30)]
+		#[pallet::weight((Weight::from_ref_time(30_000_000)
+		.saturating_add(T::DbWeight::get().reads(103))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn vote_proposal(
+			origin: OriginFor<T>,
+			proposal_id: u64,
+			approve: bool,
+		) -> DispatchResult {
+			Self::do_vote_proposal(origin, proposal_id, approve)
+		}
+
+
+		#[pallet::call_index(31)]
+		#[pallet::weight((Weight::from_ref_time(30_000_000)
+		.saturating_add(T::DbWeight::get().reads(204))
+		.saturating_add(T::DbWeight::get().writes(1)), DispatchClass::Normal, Pays::No))]
+		pub fn vote_subnet_weights(
+			origin: OriginFor<T>,
+			netuids: Vec<u16>,
+			weights: Vec<u16>,
+		) -> DispatchResult {
+			Self::do_vote_subnet_weights(origin, netuids, weights)
+		}
+
+
+		#[pallet::call_index(32)]
+		#[pallet::weight((Weight::from_ref_time(10_000_000)
+		.saturating_add(T::DbWeight::get().writes(2)), DispatchClass::Operational, Pays::No))]
+		pub fn set_subnet_emission_mode(
+			origin: OriginFor<T>,
+			mode: SubnetEmissionMode,
+			majority: u16,
+		) -> DispatchResult {
+			Self::do_set_subnet_emission_mode(origin, mode, majority)
 		}
 		
 	}	
@@ -593,8 +1337,6 @@ impl<T: Config + Send + Sync + TypeInfo> SubspaceSignedExtension<T> where
 		return Pallet::<T>::get_priority_set_weights( who, netuid );
 	}
 
-	pub fn u64_to_balance( input: u64 ) -> Option<<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance> { input.try_into().ok() }
-
 }
 
 impl <T:Config + Send + Sync + TypeInfo> sp_std::fmt::Debug for SubspaceSignedExtension<T> {
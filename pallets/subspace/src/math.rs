@@ -2,6 +2,9 @@ use frame_support::sp_std::vec;
 use frame_support::inherent::Vec;
 use substrate_fixed::transcendental::exp;
 use substrate_fixed::types::{I32F32, I64F64};
+use sp_runtime::traits::{UniqueSaturatedFrom, UniqueSaturatedInto};
+use sp_runtime::helpers_128bit::multiply_by_rational_with_rounding;
+use sp_runtime::Rounding;
 
 #[allow(dead_code)]
 pub fn fixed(val: f32) -> I32F32 { I32F32::from_num(val) }
@@ -36,6 +39,29 @@ pub fn vec_u16_proportions_to_fixed( vec: Vec<u16> ) -> Vec<I32F32> { vec.into_i
 #[allow(dead_code)]
 pub fn vec_fixed_proportions_to_u16( vec: Vec<I32F32> ) -> Vec<u16> { vec.into_iter().map(|e| fixed_proportion_to_u16(e) ).collect() }
 
+// Returns part / total as a proportion in [0, 1], computed in u128 so balances wider than u64 do not overflow.
+#[allow(dead_code)]
+pub fn balance_proportion<B: UniqueSaturatedInto<u128>>( part: B, total: B ) -> I64F64 {
+    let part: u128 = part.unique_saturated_into();
+    let total: u128 = total.unique_saturated_into();
+    if total == 0 {
+        return I64F64::from_num( 0 );
+    }
+    match multiply_by_rational_with_rounding( part.min( total ), 1u128 << 64, total, Rounding::Down ) {
+        Some( bits ) => I64F64::from_bits( bits as i128 ),
+        None => I64F64::from_num( 0 ),
+    }
+}
+
+// Returns the proportion of the amount, the proportion is clamped to [0, 1] so the share never exceeds the amount.
+#[allow(dead_code)]
+pub fn balance_share<B: UniqueSaturatedInto<u128> + UniqueSaturatedFrom<u128>>( amount: B, proportion: I64F64 ) -> B {
+    let amount: u128 = amount.unique_saturated_into();
+    let proportion: I64F64 = proportion.max( I64F64::from_num( 0 ) ).min( I64F64::from_num( 1 ) );
+    let share: u128 = multiply_by_rational_with_rounding( amount, proportion.to_bits() as u128, 1u128 << 64, Rounding::Down ).unwrap_or( 0 );
+    return B::unique_saturated_from( share );
+}
+
 #[allow(dead_code)]
 pub fn sum( x: &Vec<I32F32> ) -> I32F32 { x.iter().sum() }
 
@@ -751,6 +777,47 @@ pub fn sparse_threshold( w: &Vec<Vec<(u16, I32F32)>>, threshold: I32F32 ) -> Vec
     sparse_threshold_result
 }
 
+// Returns the dot product of two sparse vectors, both sorted by index.
+#[allow(dead_code)]
+pub fn sparse_dot( x: &Vec<(u16, I32F32)>, y: &Vec<(u16, I32F32)> ) -> I64F64 {
+    let mut result: I64F64 = I64F64::from_num( 0 );
+    let mut i: usize = 0;
+    let mut j: usize = 0;
+    while i < x.len() && j < y.len() {
+        if x[i].0 < y[j].0 {
+            i += 1;
+        } else if x[i].0 > y[j].0 {
+            j += 1;
+        } else {
+            result = result.saturating_add( I64F64::from_num( x[i].1 ).saturating_mul( I64F64::from_num( y[j].1 ) ) );
+            i += 1;
+            j += 1;
+        }
+    }
+    result
+}
+
+// Square root by Newton's method starting above the root, zero for non positive input.
+#[allow(dead_code)]
+pub fn sqrt_safe( x: I64F64 ) -> I64F64 {
+    if x <= 0 { return I64F64::from_num( 0 ); }
+    let mut root: I64F64 = x.max( I64F64::from_num( 1 ) );
+    for _ in 0..128 {
+        let next: I64F64 = ( root + x / root ) / I64F64::from_num( 2 );
+        if next >= root { break; }
+        root = next;
+    }
+    root
+}
+
+// Returns the cosine similarity of two sparse vectors sorted by index, zero if either vector is zero.
+#[allow(dead_code)]
+pub fn sparse_cosine_similarity( x: &Vec<(u16, I32F32)>, y: &Vec<(u16, I32F32)> ) -> I32F32 {
+    let norm: I64F64 = sqrt_safe( sparse_dot( x, x ) ).saturating_mul( sqrt_safe( sparse_dot( y, y ) ) );
+    if norm == 0 { return I32F32::from_num( 0 ); }
+    I32F32::from_num( ( sparse_dot( x, y ) / norm ).min( I64F64::from_num( 1 ) ) )
+}
+
 #[cfg(test)]
 mod tests {
     use crate::math::*;
@@ -758,6 +825,38 @@ mod tests {
     use substrate_fixed::types::{I32F32, I64F64, I96F32, I110F18};
     use rand::{Rng, thread_rng, seq::SliceRandom};
 
+    #[test]
+    fn test_math_balance_proportion() {
+        assert_eq!( balance_proportion( 0u128, 0u128 ), I64F64::from_num( 0 ) );
+        assert_eq!( balance_proportion( 5u64, 10u64 ), I64F64::from_num( 0.5 ) );
+        assert_eq!( balance_proportion( 20u64, 10u64 ), I64F64::from_num( 1 ) );
+        // 18 decimal balances are far above the integer range of I64F64.
+        let total: u128 = 1_000_000_000 * 10u128.pow( 18 );
+        assert_eq!( balance_proportion( total / 4, total ), I64F64::from_num( 0.25 ) );
+    }
+
+    #[test]
+    fn test_math_balance_share() {
+        assert_eq!( balance_share( 100u64, I64F64::from_num( 0.25 ) ), 25 );
+        assert_eq!( balance_share( 100u64, I64F64::from_num( 2 ) ), 100 );
+        assert_eq!( balance_share( 100u64, I64F64::from_num( -1 ) ), 0 );
+        let amount: u128 = 1_000_000_000 * 10u128.pow( 18 );
+        assert_eq!( balance_share( amount, I64F64::from_num( 0.5 ) ), amount / 2 );
+    }
+
+    #[test]
+    fn test_math_sparse_cosine_similarity() {
+        let x: Vec<(u16, I32F32)> = vec![ (0, fixed( 1. )), (1, fixed( 1. )) ];
+        let epsilon: I32F32 = I32F32::from_num( 0.0001 );
+        assert_float_compare( sqrt_safe( I64F64::from_num( 16 ) ).to_num::<I32F32>(), fixed( 4. ), epsilon );
+        assert_float_compare( sqrt_safe( I64F64::from_num( 0.25 ) ).to_num::<I32F32>(), fixed( 0.5 ), epsilon );
+        assert_float_compare( sparse_cosine_similarity( &x, &x ), fixed( 1. ), epsilon );
+        assert_float_compare( sparse_cosine_similarity( &x, &vec![ (0, fixed( 0.2 )), (1, fixed( 0.2 )) ] ), fixed( 1. ), epsilon );
+        assert_float_compare( sparse_cosine_similarity( &x, &vec![ (0, fixed( 1. )) ] ), fixed( 0.70710678 ), epsilon );
+        assert_eq!( sparse_cosine_similarity( &x, &vec![ (2, fixed( 1. )) ] ), fixed( 0. ) );
+        assert_eq!( sparse_cosine_similarity( &x, &vec![] ), fixed( 0. ) );
+    }
+
     fn assert_float_compare(a: I32F32, b: I32F32, epsilon: I32F32 ) {
         assert!( I32F32::abs( a - b ) <= epsilon, "a({:?}) != b({:?})", a, b);
     }
@@ -0,0 +1,218 @@
+use super::*;
+use frame_support::traits::{Get, GetStorageVersion, StorageVersion};
+use frame_support::weights::Weight;
+use frame_support::storage::IterableStorageMap;
+use sp_std::vec::Vec;
+use sp_std::collections::btree_map::BTreeMap;
+use frame_support::storage_alias;
+
+// The migration checks the on chain storage version, so running it twice is a no-op.
+
+
+// Layout of the items which changed since the chain was launched without a storage version.
+pub mod v0 {
+    use super::*;
+
+    #[storage_alias]
+    pub type SubnetNamespace<T: Config> = StorageMap<Pallet<T>, Twox64Concat, Vec<u8>, u16, ValueQuery>;
+    #[storage_alias]
+    pub type Namespace<T: Config> = StorageDoubleMap<Pallet<T>, Twox64Concat, u16, Twox64Concat, Vec<u8>, u16, ValueQuery>;
+    #[storage_alias]
+    pub type Names<T: Config> = StorageDoubleMap<Pallet<T>, Twox64Concat, u16, Twox64Concat, u16, Vec<u8>, ValueQuery>;
+    #[storage_alias]
+    pub type Address<T: Config> = StorageDoubleMap<Pallet<T>, Twox64Concat, u16, Twox64Concat, u16, Vec<u8>, ValueQuery>;
+    #[storage_alias]
+    pub type Weights<T: Config> = StorageDoubleMap<Pallet<T>, Identity, u16, Identity, u16, Vec<(u16, u16)>, ValueQuery>;
+    #[storage_alias]
+    pub type Incentive<T: Config> = StorageMap<Pallet<T>, Identity, u16, Vec<u16>, ValueQuery>;
+    #[storage_alias]
+    pub type Dividends<T: Config> = StorageMap<Pallet<T>, Identity, u16, Vec<u16>, ValueQuery>;
+    #[storage_alias]
+    pub type Emission<T: Config> = StorageMap<Pallet<T>, Identity, u16, Vec<u64>, ValueQuery>;
+    #[storage_alias]
+    pub type LastUpdate<T: Config> = StorageMap<Pallet<T>, Identity, u16, Vec<u64>, ValueQuery>;
+    #[storage_alias]
+    pub type MaxNameLength<T: Config> = StorageValue<Pallet<T>, u16, ValueQuery>;
+}
+
+
+// v1: migrates the storage of the launched chain to the current layout.
+//  - netuids are allocated from NextNetuid and the RemovedSubnets free-list, gaps left by removed
+//    networks become free netuids and TotalSubnets is recounted.
+//  - stake is a lock on the balance of the key instead of being withdrawn from it, the withdrawn
+//    stake is minted back into the keys and locked.
+//  - Incentive, Dividends, Emission and LastUpdate move from one vector per subnet to one entry per
+//    ( netuid, uid ). The vectors share the storage prefix with the new maps, so they are drained
+//    completely before anything is written in the new layout.
+//  - subnet names get the SubnetNames reverse index. Names of networks that no longer exist and extra
+//    names left behind by renames are dropped, the first name found is kept.
+//  - names, addresses and weights are bounded by the Config constants. Entries within the bounds encode
+//    the same, so only longer ones are rewritten, truncated. A truncated name that collides with an
+//    existing one is rejected together with its network or module, refunding the stake, and subnets
+//    holding more than MaxModulesPerSubnet modules lose their lowest scoring ones.
+pub fn migrate_to_v1<T: Config>() -> Weight {
+    let mut weight: Weight = T::DbWeight::get().reads(1);
+    if Pallet::<T>::on_chain_storage_version() >= 1 {
+        return weight;
+    }
+    let max_name_length: usize = T::MaxNameLength::get() as usize;
+    let max_address_length: usize = T::MaxAddressLength::get() as usize;
+    let max_weights: usize = T::MaxWeightsPerModule::get() as usize;
+    // removing a network or module touches every per uid item, this is a rough upper bound of it.
+    let removal_weight: Weight = T::DbWeight::get().reads_writes(40, 40);
+
+    // --- 1. Netuid allocation.
+    let netuids: Vec<u16> = <N<T> as IterableStorageMap<u16, u16>>::iter_keys().collect();
+    weight = weight.saturating_add( T::DbWeight::get().reads( netuids.len() as u64 ) );
+    let next_netuid: u16 = netuids.iter().max().map( |netuid| netuid + 1 ).unwrap_or( 0 );
+    for netuid in 0..next_netuid {
+        if !netuids.contains( &netuid ) {
+            RemovedSubnets::<T>::insert( netuid, () );
+            weight = weight.saturating_add( T::DbWeight::get().writes(1) );
+        }
+    }
+    NextNetuid::<T>::put( next_netuid );
+    TotalSubnets::<T>::put( netuids.len() as u16 );
+    weight = weight.saturating_add( T::DbWeight::get().writes(2) );
+
+    // --- 2. Stake locks, removals below unlock the stake of the removed keys, so it has to be backed first.
+    weight = weight.saturating_add( lock_withdrawn_stake::<T>() );
+
+    // --- 3. Module metrics per uid.
+    let incentive: Vec<(u16, Vec<u16>)> = v0::Incentive::<T>::drain().collect();
+    let dividends: Vec<(u16, Vec<u16>)> = v0::Dividends::<T>::drain().collect();
+    let emission: Vec<(u16, Vec<u64>)> = v0::Emission::<T>::drain().collect();
+    let last_update: Vec<(u16, Vec<u64>)> = v0::LastUpdate::<T>::drain().collect();
+    for ( netuid, values ) in incentive.iter() {
+        weight = weight.saturating_add( T::DbWeight::get().reads_writes(1, 1 + values.len() as u64) );
+        for ( uid, value ) in values.iter().enumerate() {
+            Incentive::<T>::insert( netuid, uid as u16, value );
+        }
+    }
+    for ( netuid, values ) in dividends.iter() {
+        weight = weight.saturating_add( T::DbWeight::get().reads_writes(1, 1 + values.len() as u64) );
+        for ( uid, value ) in values.iter().enumerate() {
+            Dividends::<T>::insert( netuid, uid as u16, value );
+        }
+    }
+    for ( netuid, values ) in emission.iter() {
+        weight = weight.saturating_add( T::DbWeight::get().reads_writes(1, 1 + values.len() as u64) );
+        for ( uid, value ) in values.iter().enumerate() {
+            Emission::<T>::insert( netuid, uid as u16, BalanceOf::<T>::saturated_from( *value ) );
+        }
+    }
+    for ( netuid, values ) in last_update.iter() {
+        weight = weight.saturating_add( T::DbWeight::get().reads_writes(1, 1 + values.len() as u64) );
+        for ( uid, value ) in values.iter().enumerate() {
+            LastUpdate::<T>::insert( netuid, uid as u16, value );
+        }
+    }
+
+    // --- 4. Subnet names, stale ones are dropped first, then rejected networks are removed.
+    let namespace: Vec<(Vec<u8>, u16)> = v0::SubnetNamespace::<T>::iter().collect();
+    weight = weight.saturating_add( T::DbWeight::get().reads( namespace.len() as u64 ) );
+    let mut subnet_names: Vec<(u16, Vec<u8>)> = Vec::new();
+    for ( name, netuid ) in namespace.into_iter() {
+        weight = weight.saturating_add( T::DbWeight::get().reads(1) );
+        if N::<T>::contains_key( netuid ) && !subnet_names.iter().any( |( named, _ )| *named == netuid ) {
+            subnet_names.push( ( netuid, name ) );
+        } else {
+            log::warn!("dropping stale subnet name {:?} for netuid {:?}", name, netuid );
+            v0::SubnetNamespace::<T>::remove( name );
+            weight = weight.saturating_add( T::DbWeight::get().writes(1) );
+        }
+    }
+    for ( netuid, name ) in subnet_names.into_iter() {
+        weight = weight.saturating_add( T::DbWeight::get().reads_writes(1, 3) );
+        if name.len() <= max_name_length {
+            SubnetNames::<T>::insert( netuid, BoundedName::<T>::truncate_from( name ) );
+            continue;
+        }
+        v0::SubnetNamespace::<T>::remove( &name );
+        let name: BoundedName<T> = BoundedVec::truncate_from( name );
+        if SubnetNamespace::<T>::contains_key( &name ) {
+            log::warn!("rejecting network {:?}, its truncated name {:?} is taken", netuid, name );
+            Pallet::<T>::remove_network_for_netuid( netuid );
+            weight = weight.saturating_add( removal_weight );
+        } else {
+            SubnetNamespace::<T>::insert( &name, netuid );
+            SubnetNames::<T>::insert( netuid, name );
+        }
+    }
+
+    // --- 5. Module names, rejected modules are removed once every name is in place.
+    let names: Vec<(u16, u16, Vec<u8>)> = v0::Names::<T>::iter().collect();
+    weight = weight.saturating_add( T::DbWeight::get().reads( names.len() as u64 ) );
+    let mut rejected_modules: Vec<(u16, T::AccountId)> = Vec::new();
+    for ( netuid, uid, name ) in names.into_iter().filter( |( _, _, name )| name.len() > max_name_length ) {
+        v0::Namespace::<T>::remove( netuid, &name );
+        let name: BoundedName<T> = BoundedVec::truncate_from( name );
+        if Namespace::<T>::contains_key( netuid, &name ) {
+            log::warn!("rejecting module {:?} on network {:?}, its truncated name {:?} is taken", uid, netuid, name );
+            rejected_modules.push( ( netuid, Keys::<T>::get( netuid, uid ) ) );
+        } else {
+            Namespace::<T>::insert( netuid, &name, uid );
+        }
+        Names::<T>::insert( netuid, uid, name );
+        weight = weight.saturating_add( T::DbWeight::get().reads_writes(2, 3) );
+    }
+
+    // --- 6. Addresses and weights are truncated.
+    let addresses: Vec<(u16, u16, Vec<u8>)> = v0::Address::<T>::iter().collect();
+    weight = weight.saturating_add( T::DbWeight::get().reads( addresses.len() as u64 ) );
+    for ( netuid, uid, address ) in addresses.into_iter().filter( |( _, _, address )| address.len() > max_address_length ) {
+        Address::<T>::insert( netuid, uid, BoundedAddress::<T>::truncate_from( address ) );
+        weight = weight.saturating_add( T::DbWeight::get().writes(1) );
+    }
+    let weights: Vec<(u16, u16, Vec<(u16, u16)>)> = v0::Weights::<T>::iter().collect();
+    weight = weight.saturating_add( T::DbWeight::get().reads( weights.len() as u64 ) );
+    for ( netuid, uid, weights_i ) in weights.into_iter().filter( |( _, _, weights_i )| weights_i.len() > max_weights ) {
+        Weights::<T>::insert( netuid, uid, BoundedWeights::<T>::truncate_from( weights_i ) );
+        weight = weight.saturating_add( T::DbWeight::get().writes(1) );
+    }
+
+    // --- 7. Remove the rejected modules, then the lowest scoring modules above the cap.
+    for ( netuid, key ) in rejected_modules.iter() {
+        if let Some( uid ) = Uids::<T>::get( netuid, key ) {
+            Pallet::<T>::remove_module( *netuid, uid );
+            weight = weight.saturating_add( removal_weight );
+        }
+    }
+    let max_modules: u16 = Pallet::<T>::get_max_modules_per_subnet();
+    let netuids: Vec<u16> = <N<T> as IterableStorageMap<u16, u16>>::iter_keys().collect();
+    for netuid in netuids.iter() {
+        while Pallet::<T>::get_subnet_n( *netuid ) > max_modules {
+            Pallet::<T>::remove_module( *netuid, Pallet::<T>::get_lowest_uid( *netuid ) );
+            weight = weight.saturating_add( removal_weight );
+        }
+    }
+
+    // --- 8. The name length is a Config constant now.
+    v0::MaxNameLength::<T>::kill();
+
+    StorageVersion::new(1).put::<Pallet<T>>();
+    log::info!("migrated subspace storage to v1, next_netuid: {:?}, rejected modules: {:?}", next_netuid, rejected_modules.len() );
+    return weight.saturating_add( T::DbWeight::get().writes(2) );
+}
+
+// Mints the stake of every key which is not locked yet back into the key and locks all of its stake.
+// Keys already backed by locks are left as they are, so this can run more than once.
+pub fn lock_withdrawn_stake<T: Config>() -> Weight {
+    let mut stake_per_key: BTreeMap<T::AccountId, BalanceOf<T>> = BTreeMap::new();
+    for ( _, key, stake ) in Stake::<T>::iter() {
+        let total_stake = stake_per_key.entry( key ).or_insert( Zero::zero() );
+        *total_stake = total_stake.saturating_add( stake );
+    }
+    let mut weight: Weight = T::DbWeight::get().reads( stake_per_key.len() as u64 );
+
+    for ( key, stake ) in stake_per_key.iter() {
+        let withdrawn_stake: BalanceOf<T> = stake.saturating_sub( LockedStake::<T>::get( key ) );
+        if let Err( error ) = Pallet::<T>::mint_to_account( key, withdrawn_stake ) {
+            log::warn!("could not mint back the stake of {:?}: {:?}", key, error );
+        }
+        LockedStake::<T>::insert( key, *stake );
+        Pallet::<T>::update_stake_lock( key );
+        weight = weight.saturating_add( T::DbWeight::get().reads_writes(2, 3) );
+    }
+    return weight;
+}
@@ -1,6 +1,6 @@
 use super::*;
 use frame_support::storage::IterableStorageMap;
-use frame_support::pallet_prelude::{Decode, Encode};
+use frame_support::pallet_prelude::{Decode, Encode, DispatchError};
 extern crate alloc;
 use alloc::vec::Vec;
 use frame_support::sp_std::vec;
@@ -15,8 +15,8 @@ pub struct ModuleSubnetInfo<T: Config> {
     last_update: Compact<u64>,
     
     // Subnet Info
-    stake: Vec<(T::AccountId, Compact<u64>)>, // map of key to stake on this module/key (includes delegations)
-    emission: Compact<u64>,
+    stake: Vec<(T::AccountId, BalanceOf<T>)>, // map of key to stake on this module/key (includes delegations)
+    emission: BalanceOf<T>,
     incentive: Compact<u16>,
     dividends: Compact<u16>,
     weights: Vec<(Compact<u16>, Compact<u16>)>, // Vec of (uid, weight)
@@ -27,10 +27,10 @@ impl<T: Config> Pallet<T> {
 
 
         pub fn replace_module_with_uid( netuid: u16, uid: u16, replace_uid: u16 ) {
-            Self::replace_module( netuid, uid, &Keys::<T>::get( netuid, replace_uid ), Names::<T>::get( netuid, replace_uid ), Address::<T>::get( netuid, replace_uid ), Self::get_stake( netuid, &Keys::<T>::get( netuid, replace_uid ) ) );
+            Self::replace_module( netuid, uid, &Keys::<T>::get( netuid, replace_uid ), Names::<T>::get( netuid, replace_uid ), Address::<T>::get( netuid, replace_uid ) );
         }
-        // Replace the module under this uid.
-        pub fn replace_module( netuid: u16, uid: u16, new_key: &T::AccountId, name: Vec<u8>, address: Vec<u8>, stake: u64 ) {
+        // Replace the module under this uid, the stake of the new key is left to the caller.
+        pub fn replace_module( netuid: u16, uid: u16, new_key: &T::AccountId, name: BoundedName<T>, address: BoundedAddress<T> ) {
 
             log::debug!("remove_network_for_netuid( netuid: {:?} | uid : {:?} | new_key: {:?} ) ", netuid, uid, new_key );
             
@@ -41,36 +41,30 @@ impl<T: Config> Pallet<T> {
             Uids::<T>::insert( netuid, new_key.clone(), uid ); // Make uid - key association.
             Keys::<T>::insert( netuid, uid, new_key.clone() ); // Make key - uid association.
             
-            // pop frm incentive vector and push to new key
-            let mut incentive: Vec<u16> = Incentive::<T>::get( netuid ); 
-            let mut dividends: Vec<u16> = Dividends::<T>::get( netuid ); 
-            let mut last_update: Vec<u64> = LastUpdate::<T>::get( netuid );
-            let mut emission: Vec<u64> = Emission::<T>::get( netuid ); 
-
-            
-            incentive[uid as usize] = 0 as u16;
-            dividends[uid as usize] = 0 as u16;
-            emission[uid as usize] = 0 as u64;
-            last_update[uid as usize] = block_number as u64;
-            
-            Incentive::<T>::insert( netuid, incentive ); // Make uid - key association.
-            Emission::<T>::insert( netuid, emission ); // Make uid - key association.
-            Dividends::<T>::insert( netuid, dividends ); // Make uid - key association.
-            LastUpdate::<T>::insert( netuid, last_update ); // Make uid - key association.
+            // reset the metrics of the uid for the new key
+            Incentive::<T>::insert( netuid, uid, 0 );
+            Emission::<T>::insert( netuid, uid, 0 );
+            Dividends::<T>::insert( netuid, uid, 0 );
+            LastUpdate::<T>::insert( netuid, uid, block_number );
             BlockAtRegistration::<T>::insert( netuid, uid, block_number ); // Fill block at registration.
             Address::<T>::insert( netuid, uid, address ); // Fill module info.
 
+            // only drop the old name if it still points at this uid, it may have been taken over by another module.
             let old_name = Names::<T>::get( netuid, uid );
-            Namespace::<T>::remove( netuid, old_name.clone() ); // Fill module namespace.
+            if Namespace::<T>::get( netuid, old_name.clone() ) == uid {
+                Namespace::<T>::remove( netuid, old_name.clone() );
+            }
             Namespace::<T>::insert( netuid, name.clone(), uid ); // Fill module namespace.
             Names::<T>::insert( netuid, uid, name.clone() ); // Fill module namespace.
 
             // 3. Remove the network if it is empty.
             // Weights::<T>::insert( netuid, uid, vec![] as Vec<(u16, u16)> ); // Make uid - key association.
-            Weights::<T>::insert( netuid, uid, vec![] as Vec<(u16, u16)> ); // Make uid - key association.
-            // 3. Remove the stake from the old account and add to the new
+            Weights::<T>::insert( netuid, uid, BoundedWeights::<T>::default() ); // Make uid - key association.
+            WeightCommits::<T>::remove( netuid, uid ); // the commit belongs to the old key.
+            PreviousWeights::<T>::remove( netuid, uid );
+            WeightCopyDetections::<T>::remove( netuid, uid );
+            // 3. Unlock the stake of the old account.
             Self::remove_stake_from_storage( netuid, &old_key );
-            Self::add_stake_on_account( netuid, &new_key, stake );
             
         }
 
@@ -89,25 +83,38 @@ impl<T: Config> Pallet<T> {
             assert!( uid < n, "The uid is out of bounds." );
 
             let replace_uid = Self::get_subnet_n( netuid ) - 1;
+            let replace_key: T::AccountId = Keys::<T>::get( netuid, replace_uid );
+            let replace_name: BoundedName<T> = Names::<T>::get( netuid, replace_uid );
 
-            
-            Self::replace_module_with_uid( netuid, uid, replace_uid );
+            // the last module moves into the removed uid, unless it is the removed module itself.
+            if uid != replace_uid {
+                Self::replace_module_with_uid( netuid, uid, replace_uid );
+            } else {
+                Self::remove_stake_from_storage( netuid, &replace_key );
+            }
 
-            let replace_key: T::AccountId = Keys::<T>::get( netuid, replace_uid );
             // 2. Remove previous set memberships.
-            Uids::<T>::remove( netuid, &replace_key.clone() ); 
+            if Namespace::<T>::get( netuid, replace_name.clone() ) == replace_uid {
+                Namespace::<T>::remove( netuid, replace_name );
+            }
+            if uid == replace_uid {
+                Uids::<T>::remove( netuid, &replace_key.clone() ); // the key keeps its moved uid otherwise.
+            }
             Keys::<T>::remove( netuid, replace_uid ); // Make key - uid association.
             Address::<T>::remove(netuid, replace_uid ); // Make uid - key association.
             BlockAtRegistration::<T>::remove( netuid, replace_uid ); // Fill block at registration.
             Weights::<T>::remove( netuid, replace_uid ); // Make uid - key association.
+            WeightCommits::<T>::remove( netuid, replace_uid );
+            PreviousWeights::<T>::remove( netuid, replace_uid );
+            WeightCopyDetections::<T>::remove( netuid, replace_uid );
             Names::<T>::remove( netuid, replace_uid ); // Make uid - key association.
             N::<T>::mutate( netuid, |v| *v -= 1 ); // Decrease the number of modules in the network.
             
-            // pop frm incentive vector and push to new key
-            Incentive::<T>::mutate( netuid, |v| v.pop() );
-            Dividends::<T>::mutate( netuid, |v| v.pop() );
-            Emission::<T>::mutate( netuid, |v| v.pop() );
-            LastUpdate::<T>::mutate( netuid, |v| v.pop() );
+            // drop the metrics of the last uid, which moved into the removed uid
+            Incentive::<T>::remove( netuid, replace_uid );
+            Dividends::<T>::remove( netuid, replace_uid );
+            Emission::<T>::remove( netuid, replace_uid );
+            LastUpdate::<T>::remove( netuid, replace_uid );
 
             // 3. Remove the network if it is empty.
             if N::<T>::get( netuid ) == 0 {
@@ -119,19 +126,105 @@ impl<T: Config> Pallet<T> {
         }
     
 
+        // Deregisters the module under this uid, keeping the state of the other modules. The last module moves
+        // into the uid with its weights, metrics and registration block, weights on the removed uid are dropped
+        // and weights on the moved uid follow it. The network is kept even when it is left empty.
+        pub fn deregister_module( netuid: u16, uid: u16 ) {
+            let n: u16 = Self::get_subnet_n( netuid );
+            assert!( uid < n, "The uid is out of bounds." );
+            let last_uid: u16 = n - 1;
+
+            // 1. Unlock the stake of the removed key and drop its memberships.
+            let key: T::AccountId = Keys::<T>::get( netuid, uid );
+            let name: BoundedName<T> = Names::<T>::get( netuid, uid );
+            Self::remove_stake_from_storage( netuid, &key );
+            Uids::<T>::remove( netuid, &key );
+            if Namespace::<T>::get( netuid, name.clone() ) == uid {
+                Namespace::<T>::remove( netuid, name );
+            }
+            WeightCommits::<T>::remove( netuid, uid );
+            WeightCopyDetections::<T>::remove( netuid, uid );
+
+            // 2. Move the last module into the uid as it is.
+            if uid != last_uid {
+                let moved_key: T::AccountId = Keys::<T>::get( netuid, last_uid );
+                let moved_name: BoundedName<T> = Names::<T>::get( netuid, last_uid );
+                Keys::<T>::insert( netuid, uid, moved_key.clone() );
+                Uids::<T>::insert( netuid, moved_key, uid );
+                if Namespace::<T>::get( netuid, moved_name.clone() ) == last_uid {
+                    Namespace::<T>::insert( netuid, moved_name.clone(), uid );
+                }
+                Names::<T>::insert( netuid, uid, moved_name );
+                Address::<T>::insert( netuid, uid, Address::<T>::get( netuid, last_uid ) );
+                BlockAtRegistration::<T>::insert( netuid, uid, BlockAtRegistration::<T>::get( netuid, last_uid ) );
+                Weights::<T>::insert( netuid, uid, Weights::<T>::get( netuid, last_uid ) );
+                PreviousWeights::<T>::insert( netuid, uid, PreviousWeights::<T>::get( netuid, last_uid ) );
+                if let Some( commit ) = WeightCommits::<T>::get( netuid, last_uid ) {
+                    WeightCommits::<T>::insert( netuid, uid, commit );
+                }
+                if let Some( detection ) = WeightCopyDetections::<T>::get( netuid, last_uid ) {
+                    WeightCopyDetections::<T>::insert( netuid, uid, detection );
+                }
+                Incentive::<T>::insert( netuid, uid, Incentive::<T>::get( netuid, last_uid ) );
+                Dividends::<T>::insert( netuid, uid, Dividends::<T>::get( netuid, last_uid ) );
+                Emission::<T>::insert( netuid, uid, Emission::<T>::get( netuid, last_uid ) );
+                LastUpdate::<T>::insert( netuid, uid, LastUpdate::<T>::get( netuid, last_uid ) );
+            }
+
+            // 3. Drop the last uid, which is now empty.
+            Keys::<T>::remove( netuid, last_uid );
+            Names::<T>::remove( netuid, last_uid );
+            Address::<T>::remove( netuid, last_uid );
+            BlockAtRegistration::<T>::remove( netuid, last_uid );
+            Weights::<T>::remove( netuid, last_uid );
+            PreviousWeights::<T>::remove( netuid, last_uid );
+            WeightCommits::<T>::remove( netuid, last_uid );
+            WeightCopyDetections::<T>::remove( netuid, last_uid );
+            Incentive::<T>::remove( netuid, last_uid );
+            Dividends::<T>::remove( netuid, last_uid );
+            Emission::<T>::remove( netuid, last_uid );
+            LastUpdate::<T>::remove( netuid, last_uid );
+            N::<T>::insert( netuid, last_uid );
+
+            // 4. Drop the weights on the removed uid and point the weights on the moved uid at its new uid.
+            let remap = |weights: &mut BoundedWeights<T>| {
+                weights.retain( |( target, _ )| *target != uid );
+                for ( target, _ ) in weights.iter_mut() {
+                    if *target == last_uid {
+                        *target = uid;
+                    }
+                }
+            };
+            for row in 0..last_uid {
+                Weights::<T>::mutate( netuid, row, remap );
+                PreviousWeights::<T>::mutate( netuid, row, remap );
+                WeightCopyDetections::<T>::mutate( netuid, row, |detection| {
+                    if let Some( detection ) = detection {
+                        if detection.source_uid == last_uid {
+                            detection.source_uid = uid;
+                        }
+                    }
+                });
+            }
+        }
+
+
         // Appends the uid to the network.
-        pub fn append_module( netuid: u16, key: &T::AccountId , name: Vec<u8>, address: Vec<u8>, stake: u64) -> u16{
+        pub fn append_module( netuid: u16, key: &T::AccountId , name: BoundedName<T>, address: BoundedAddress<T>, stake: BalanceOf<T>) -> Result<u16, DispatchError> {
     
+            // 0. Lock the stake first, so nothing is written if the key cannot afford it.
+            Self::add_stake_on_account( netuid, &key, stake )?;
+
             // 1. Get the next uid. This is always equal to subnetwork_n.
             let uid: u16 = Self::get_subnet_n( netuid );
             let block_number = Self::get_current_block_as_u64();
             log::debug!("append_module( netuid: {:?} | uid: {:?} | new_key: {:?} ) ", netuid, key, uid );
     
             // 3. Expand Yuma with new position.
-            Emission::<T>::mutate(netuid, |v| v.push(0) );
-            Incentive::<T>::mutate(netuid, |v| v.push(0) );
-            Dividends::<T>::mutate(netuid, |v| v.push(0) );
-            LastUpdate::<T>::mutate(netuid, |v| v.push( block_number ) );
+            Emission::<T>::insert( netuid, uid, 0 );
+            Incentive::<T>::insert( netuid, uid, 0 );
+            Dividends::<T>::insert( netuid, uid, 0 );
+            LastUpdate::<T>::insert( netuid, uid, block_number );
         
             // 4. Insert new account information.
             Keys::<T>::insert( netuid, uid, key.clone() ); // Make key - uid association.
@@ -140,12 +233,11 @@ impl<T: Config> Pallet<T> {
             Namespace::<T>::insert( netuid, name.clone(), uid ); // Fill module namespace.
             Names::<T>::insert( netuid, uid, name.clone() ); // Fill module namespace.
             Address::<T>::insert( netuid, uid, address.clone() ); // Fill module info.
-            Self::add_stake_on_account( netuid, &key, stake );
             
             // 3. Get and increase the uid count.
             N::<T>::insert( netuid, uid + 1 );
     
-            return uid;
+            return Ok( uid );
     
         }   
     
@@ -188,9 +280,7 @@ impl<T: Config> Pallet<T> {
             .filter_map(|(i, w)| if *w > 0 { Some((i.into(), w.into())) } else { None })
             .collect::<Vec<(Compact<u16>, Compact<u16>)>>();
         
-        let stake: Vec<(T::AccountId, Compact<u64>)> = Stake::<T>::iter_prefix(netuid)
-            .map(|(key, stake)| (key, stake.into()))
-            .collect();
+        let stake: Vec<(T::AccountId, BalanceOf<T>)> = Stake::<T>::iter_prefix(netuid).collect();
 
         
 
@@ -199,7 +289,7 @@ impl<T: Config> Pallet<T> {
             uid: uid.into(),
             netuid: netuid.into(),
             stake: stake,
-            emission: emission.into(),
+            emission: emission,
             incentive: incentive.into(),
             dividends: dividends.into(),
             last_update: last_update.into(),
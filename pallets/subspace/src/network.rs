@@ -4,10 +4,12 @@ use sp_std::vec::Vec;
 use frame_system::ensure_root;
 use frame_support::IterableStorageDoubleMap;
 use frame_support::storage::IterableStorageMap;
+use frame_support::traits::Get;
 use frame_support::pallet_prelude::{Decode, Encode};
 use codec::Compact;
 use frame_support::pallet_prelude::{DispatchError, DispatchResult};
 use substrate_fixed::types::{I64F64};
+use crate::math::balance_share;
 extern crate alloc;
 
 
@@ -26,19 +28,29 @@ impl<T: Config> Pallet<T> {
         return N::<T>::contains_key( netuid );
     }
 
-    // get the least staked network
-    pub fn least_staked_netuid() -> u16 {
-        let mut min_stake: u64 = u64::MAX;
-        let mut min_stake_netuid: u16 = u16::MAX;
-        for ( netuid, net_stake ) in <SubnetTotalStake<T> as IterableStorageMap<u16, u64> >::iter(){
+    // get the least staked network that is outside of its immunity period
+    pub fn least_staked_netuid() -> Option<u16> {
+        let mut min_stake: BalanceOf<T> = BalanceOf::<T>::max_value();
+        let mut min_stake_netuid: Option<u16> = None;
+        for netuid in <N<T> as IterableStorageMap<u16, u16> >::iter_keys(){
+            if Self::is_subnet_immune( netuid ) {
+                continue;
+            }
+            let net_stake: BalanceOf<T> = Self::get_network_stake( netuid );
             if net_stake <= min_stake {
                 min_stake = net_stake;
-                min_stake_netuid = netuid;
+                min_stake_netuid = Some( netuid );
             }
         }
         return min_stake_netuid;
     }
 
+    // Returns true while a newly registered subnet can not be replaced.
+    pub fn is_subnet_immune( netuid: u16 ) -> bool {
+        let registered_at: u64 = SubnetRegisteredAt::<T>::get( netuid );
+        return Self::get_current_block_as_u64().saturating_sub( registered_at ) < SubnetImmunityPeriod::<T>::get();
+    }
+
     pub fn get_max_allowed_subnets() -> u16 {
         return MaxAllowedSubnets::<T>::get();
     }
@@ -48,7 +60,7 @@ impl<T: Config> Pallet<T> {
 
     
 
-    pub fn enough_stake_to_start_network(stake: u64) -> bool {
+    pub fn enough_stake_to_start_network(stake: BalanceOf<T>) -> bool {
         let num_subnets: u16 = Self::get_number_of_subnets();
         let max_subnets: u16 = MaxAllowedSubnets::<T>::get();
         // if we have not reached the max number of subnets, then we can start a new one
@@ -63,45 +75,43 @@ impl<T: Config> Pallet<T> {
         return stake > Self::min_stake();
     }
 
-    // get the least staked network
-    pub fn min_stake() -> u64 {
-        let mut min_stake: u64 = u64::MAX;
-        for ( netuid, net_stake ) in <SubnetTotalStake<T> as IterableStorageMap<u16, u64> >::iter(){
-            if net_stake <= min_stake {
-                min_stake = net_stake;
-            }
+    // get the stake of the least staked network that can be replaced, the max balance if every network is immune
+    pub fn min_stake() -> BalanceOf<T> {
+        match Self::least_staked_netuid() {
+            Some( netuid ) => Self::get_network_stake( netuid ),
+            None => BalanceOf::<T>::max_value(),
         }
-        return min_stake;
     }
 
 
-    pub fn get_network_stake( netuid: u16 ) -> u64 {
+    pub fn get_network_stake( netuid: u16 ) -> BalanceOf<T> {
         return SubnetTotalStake::<T>::get( netuid );
     }
 
     pub fn do_add_network( 
         origin: T::RuntimeOrigin,
         name: Vec<u8>,
-        stake: u64,
+        stake: BalanceOf<T>,
     ) -> DispatchResult {
 
         let key = ensure_signed(origin)?;
+        let name: BoundedName<T> = Self::bounded_subnet_name( name )?;
         // --- 1. Ensure the network name does not already exist.
         if Self::get_number_of_subnets() > 0 {
-            ensure!( !Self::if_subnet_name_exists( name.clone() ), Error::<T>::SubnetNameAlreadyExists );
+            ensure!( !Self::if_subnet_name_exists( name.to_vec() ), Error::<T>::SubnetNameAlreadyExists );
             ensure!( Self::enough_stake_to_start_network( stake ), Error::<T>::NotEnoughStakeToStartNetwork );
         }
 
         let default_subnet: SubnetInfo<T> = Self::default_subnet();
         Self::add_network( name.clone() ,
-                            default_subnet.stake + stake, 
+                            default_subnet.stake.saturating_add( stake ), 
                             default_subnet.max_allowed_uids, 
                             default_subnet.immunity_period,
                             default_subnet.min_allowed_weights,
                             default_subnet.max_allowed_weights,
                             default_subnet.tempo,
                             &key.clone()// founder
-                            );
+                            )?;
         // --- 16. Ok and done.
         Ok(())
     }
@@ -139,6 +149,11 @@ impl<T: Config> Pallet<T> {
 
         ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::SubnetNameAlreadyExists );
         ensure!( Self::is_subnet_founder( netuid, &key ), Error::<T>::NotSubnetFounder );
+        // --- Renaming onto the name of another network would leave two netuids under one name.
+        let name: BoundedName<T> = Self::bounded_subnet_name( name )?;
+        if name.len() > 0 && name.to_vec() != Self::get_name_for_netuid( netuid ) {
+            ensure!( !Self::if_subnet_name_exists( name.to_vec() ), Error::<T>::SubnetNameAlreadyExists );
+        }
 
         Self::update_network_for_netuid( netuid, 
                                         name.clone(), 
@@ -154,7 +169,7 @@ impl<T: Config> Pallet<T> {
 
 
     pub fn update_network_for_netuid(netuid: u16,
-                    name: Vec<u8>,
+                    name: BoundedName<T>,
                     immunity_period: u16,
                     min_allowed_weights: u16,
                     max_allowed_weights: u16,
@@ -180,10 +195,11 @@ impl<T: Config> Pallet<T> {
         MaxAllowedUids::<T>::insert( netuid, max_allowed_uids );
 
         if name.len() > 0 {
-            // update the name
-            let old_name: Vec<u8> = Self::get_name_for_netuid( netuid );
-            SubnetNamespace::<T>::remove( old_name.clone() );
-            SubnetNamespace::<T>::insert( name.clone(), netuid)       
+            // update the name in both directions
+            let old_name: BoundedName<T> = SubnetNames::<T>::get( netuid );
+            SubnetNamespace::<T>::remove( old_name );
+            SubnetNamespace::<T>::insert( name.clone(), netuid );
+            SubnetNames::<T>::insert( netuid, name.clone() );
          }
 
     }
@@ -196,25 +212,24 @@ impl<T: Config> Pallet<T> {
         
     }
 
+    // Returns the hyperparameters given to newly created subnets, as set by governance.
     pub fn default_subnet() -> SubnetInfo<T> {
-        let netuid: u16 = 0;
+        let defaults: SubnetParams = SubnetDefaults::<T>::get();
         return SubnetInfo {
-            immunity_period: ImmunityPeriod::<T>::get( netuid ) ,
-            min_allowed_weights: MinAllowedWeights::<T>::get( netuid ),
-            max_allowed_weights: MaxAllowedWeights::<T>::get( netuid ),
-            max_allowed_uids:  MaxAllowedUids::<T>::get( netuid ),
-            tempo: Tempo::<T>::get( netuid ),
-            n: N::<T>::get( netuid ),
-            netuid: netuid,
-            stake: SubnetTotalStake::<T>::get( netuid ),
-            name : Self::get_name_for_netuid( netuid ),
-            emission: SubnetEmission::<T>::get( netuid ),
-            founder: Founder::<T>::get( netuid ),
-        
+            immunity_period: defaults.immunity_period,
+            min_allowed_weights: defaults.min_allowed_weights,
+            max_allowed_weights: defaults.max_allowed_weights,
+            max_allowed_uids: defaults.max_allowed_uids,
+            tempo: defaults.tempo,
+            n: 0,
+            netuid: 0,
+            stake: Zero::zero(),
+            name: Vec::new(),
+            emission: Zero::zero(),
+            founder: DefaultAccount::<T>::get(),
+            founder_share: 0,
+            mode: RegistrationMode::Open,
         };
-
-            
-        
     }
 
 
@@ -225,95 +240,61 @@ impl<T: Config> Pallet<T> {
 
 
     pub fn add_network_from_name( 
-        name: Vec<u8>,
+        name: BoundedName<T>,
         key : &T::AccountId,
-    ) -> u16 {
+    ) -> Result<u16, DispatchError> {
 
         let default_subnet  = Self::default_subnet();
 
+        // --- 1. The name must be free and the network must be allowed to replace one at the cap.
+        ensure!( !Self::if_subnet_name_exists( name.to_vec() ), Error::<T>::SubnetNameAlreadyExists );
+        ensure!( Self::enough_stake_to_start_network( default_subnet.stake ), Error::<T>::NotEnoughStakeToStartNetwork );
+
         let netuid = Self::add_network( 
                             name.clone(),
                             default_subnet.stake, 
                             default_subnet.max_allowed_uids, 
-                            default_subnet.max_allowed_weights,
                             default_subnet.immunity_period,
                             default_subnet.min_allowed_weights,
+                            default_subnet.max_allowed_weights,
                             default_subnet.tempo,
-                            &key);
+                            &key)?;
 
         // --- 16. Ok and done.
-        return netuid;
+        return Ok( netuid );
     }
 
 
     pub fn add_network_from_registration( 
-        name: Vec<u8>,
-        stake: u64,
+        name: BoundedName<T>,
+        stake: BalanceOf<T>,
         key : &T::AccountId,
-    ) -> u16 {
+    ) -> Result<u16, DispatchError> {
 
 
         let default_subnet  = Self::default_subnet();
 
         let netuid = Self::add_network( 
                             name.clone(),
-                            default_subnet.stake + stake, 
+                            default_subnet.stake.saturating_add( stake ), 
                             default_subnet.max_allowed_uids, 
                             default_subnet.immunity_period,
                             default_subnet.min_allowed_weights,
                             default_subnet.max_allowed_weights,
                             default_subnet.tempo,
-                            &key, );
+                            &key, )?;
 
         // --- 16. Ok and done.
-        return netuid;
+        return Ok( netuid );
     }
 
 
-    // Returns the total amount of stake in the staking table.
-    pub fn get_total_emission_per_block() -> u64 {
-        let total_stake: u64 = Self::get_total_stake();
-        let mut emission_per_block : u64 = 4_000_000_000;
-        let halving_total_stake_checkpoints: Vec<u64> = vec![2_000_000, 12_000_000, 22_000_000, 32_000_000].iter().map(|x| x*1_000_000_000).collect();
-        
-        for (i, having_stake) in halving_total_stake_checkpoints.iter().enumerate() {
-            let halving_factor = 2u64.pow(i as u32);
-            if total_stake < *having_stake {
-                emission_per_block = emission_per_block / halving_factor;
-                break;
-            }
+    pub fn calculate_network_emission(netuid:u16) -> BalanceOf<T> { 
 
-        }
+        let subnet_ratio: I64F64 = Self::get_subnet_emission_ratio( netuid );
 
-
-        return emission_per_block;
-    }
-
-
-
-    pub fn calculate_network_emission(netuid:u16) -> u64 { 
-
-
-        let subnet_stake: I64F64 =I64F64::from_num( Self::get_total_subnet_stake(netuid));
-
-        let total_stake_u64: u64 = Self::get_total_stake();
-        let total_stake: I64F64 = I64F64::from_num(total_stake_u64);
-
-        let mut subnet_ratio: I64F64 = I64F64::from_num(0);
-        if total_stake > I64F64::from_num(0) {
-            subnet_ratio =  subnet_stake/total_stake;
-        } else {
-            let n = TotalSubnets::<T>::get();
-            if n > 1 {
-                subnet_ratio = I64F64::from_num(1)/I64F64::from_num(n) ;
-            }
-            else { // n == 1
-                subnet_ratio = I64F64::from_num(1);
-            }
-        }
-
-        let total_emission_per_block: u64  = Self::get_total_emission_per_block();
-        let token_emission: u64 = (subnet_ratio*I64F64::from_num(total_emission_per_block)).to_num::<u64>();
+        let total_emission_per_block: BalanceOf<T>  = Self::get_total_emission_per_block();
+        let token_emission: BalanceOf<T> = balance_share( total_emission_per_block, subnet_ratio );
         
         SubnetEmission::<T>::insert( netuid, token_emission );
 
@@ -321,26 +302,32 @@ impl<T: Config> Pallet<T> {
 
     }
 
-    pub fn get_subnet_emission(netuid: u16) -> u64 {
+    pub fn get_subnet_emission(netuid: u16) -> BalanceOf<T> {
         return  Self::calculate_network_emission(netuid);
     }
     
     pub fn add_network( 
-                       name: Vec<u8>,
-                       stake: u64,
+                       name: BoundedName<T>,
+                       stake: BalanceOf<T>,
                        max_allowed_uids: u16,
                        immunity_period: u16,
                        min_allowed_weights: u16,
                        max_allowed_weights: u16,
                        tempo: u16,
                        founder: &T::AccountId, 
-                    ) -> u16 {
+                    ) -> Result<u16, DispatchError> {
 
-        // --- 1. Enfnsure that the network name does not already exist.
-        let total_networks: u16 = TotalSubnets::<T>::get();
+        // --- 1. At the cap the least staked network is removed, refunding its stake, and its netuid is retired.
+        // No netuid is allocated when every network is immune.
         let max_networks = MaxAllowedSubnets::<T>::get();
-        let netuid = total_networks;
-        
+        let mut replaced: bool = false;
+        if Self::get_number_of_subnets() >= max_networks {
+            ensure!( Self::remove_least_staked_netuid().is_some(), Error::<T>::NoReplaceableSubnet );
+            replaced = true;
+        }
+
+        // --- 2. Take a retired netuid, which is the replaced one if any, or a fresh one.
+        let netuid: u16 = Self::allocate_netuid();
 
         Tempo::<T>::insert( netuid, tempo);
         MaxAllowedUids::<T>::insert( netuid, max_allowed_uids );
@@ -348,7 +335,9 @@ impl<T: Config> Pallet<T> {
         MinAllowedWeights::<T>::insert( netuid, min_allowed_weights );
         MaxAllowedWeights::<T>::insert( netuid, max_allowed_weights );
         SubnetNamespace::<T>::insert( name.clone(), netuid );
+        SubnetNames::<T>::insert( netuid, name.clone() );
         Founder::<T>::insert( netuid, founder );
+        SubnetRegisteredAt::<T>::insert( netuid, Self::get_current_block_as_u64() );
 
         // set stat once network is created
         TotalSubnets::<T>::mutate( |n| *n += 1 );
@@ -356,50 +345,48 @@ impl<T: Config> Pallet<T> {
         
         // --- 6. Emit the new network event.
         log::info!("NetworkAdded( netuid:{:?}, name:{:?} )", netuid, name.clone());
-        Self::deposit_event( Event::NetworkAdded( netuid, name.clone()) );
+        Self::deposit_event( Event::NetworkAdded( netuid, name.to_vec()) );
+        if replaced {
+            log::info!("NetworkReplaced( netuid:{:?}, name:{:?} )", netuid, name.clone());
+            Self::deposit_event( Event::NetworkReplaced( netuid, name.to_vec() ) );
+        }
     
 
-        return netuid;
+        return Ok( netuid );
 
     }
 
 
 
-    // Initializes a new subnetwork under netuid with parameters.
+    // Converts a network name to its stored form, names longer than MaxNameLength are rejected.
+    pub fn bounded_subnet_name( name: Vec<u8> ) -> Result<BoundedName<T>, Error<T>> {
+        return name.try_into().map_err( |_| Error::<T>::SubnetNameTooLong );
+    }
+
+    // Names longer than MaxNameLength can not be stored, so they never exist.
     //
     pub fn if_subnet_name_exists(name: Vec<u8>) -> bool {
-       
-   
-        return  SubnetNamespace::<T>::contains_key(name.clone()).into();
+        return BoundedName::<T>::try_from( name ).map_or( false, |name| SubnetNamespace::<T>::contains_key( name ) );
     }
 
     pub fn subnet_name_exists(name: Vec<u8>) -> bool {
-       
-   
-        return  SubnetNamespace::<T>::contains_key(name.clone()).into();
+        return Self::if_subnet_name_exists( name );
     }
 
     pub fn if_subnet_netuid_exists(netuid: u16) -> bool {
-       
-   
-        return  SubnetNamespace::<T>::contains_key(Self::get_name_for_netuid(netuid)).into();
+        return SubnetNames::<T>::contains_key( netuid );
     }
 
 
     pub fn get_netuid_for_name( name: Vec<u8> ) -> u16 {
         
-        let netuid: u16 = SubnetNamespace::<T>::get(name.clone());
+        let netuid: u16 = BoundedName::<T>::try_from( name ).map_or( 0, |name| SubnetNamespace::<T>::get( name ) );
         return netuid;
     }
 
 
     pub fn get_name_for_netuid( netuid : u16) -> Vec<u8> {
-        for ( name, _netuid ) in <SubnetNamespace<T> as IterableStorageMap<Vec<u8>, u16>>::iter(){
-            if _netuid == netuid {
-                return name;
-            }
-        }
-        return Vec::new();
+        return SubnetNames::<T>::get( netuid ).into_inner();
     }
 
 
@@ -408,14 +395,17 @@ impl<T: Config> Pallet<T> {
     // Removes the network (netuid) and all of its parameters.
     //
 
-    pub fn remove_least_staked_netuid() -> u16 {
-        let netuid: u16 = Self::least_staked_netuid();
-        return Self::remove_network_for_netuid( netuid )
+    pub fn remove_least_staked_netuid() -> Option<u16> {
+        let netuid: u16 = Self::least_staked_netuid()?;
+        return Some( Self::remove_network_for_netuid( netuid ) )
     }
 
-    pub fn remove_network_for_netuid( netuid: u16 ) -> u16 {
-        let name = Self::get_name_for_netuid( netuid );
-        return Self::remove_network_for_name( name );
+    pub fn remove_network_for_name( name: Vec<u8>) -> u16 {
+        // --- 1. Ensure the network to be removed exists.
+        if !Self::if_subnet_name_exists( name.clone() ) {
+            return 0;
+        }
+        return Self::remove_network_for_netuid( Self::get_netuid_for_name( name ) );
     }
 
     // Returns true if the account is the founder of the network.
@@ -425,47 +415,120 @@ impl<T: Config> Pallet<T> {
     }
 
 
-    pub fn remove_network_for_name( name: Vec<u8>) -> u16 {
-        // --- 2. Ensure the network to be removed exists.
-        if !Self::if_subnet_name_exists( name.clone() ) {
+    pub fn remove_network_for_netuid( netuid: u16 ) -> u16 {
+        // --- 1. Ensure the network to be removed exists.
+        if !Self::if_subnet_exist( netuid ) {
             return 0;
         }
-        let netuid = Self::get_netuid_for_name( name.clone() );
-        SubnetNamespace::<T>::remove( name.clone() );
-        // --- 4. Erase all memory associated with the network.
+        if let Ok( name ) = SubnetNames::<T>::try_get( netuid ) {
+            SubnetNamespace::<T>::remove( name );
+        }
+
+        // --- 2. Refund the network stake to the stakers.
+        let stakers: Vec<T::AccountId> = <Stake<T> as IterableStorageDoubleMap<u16, T::AccountId, BalanceOf<T>> >::iter_prefix(netuid).map( |( key, _ )| key ).collect();
+        for key in stakers.iter() {
+            Self::remove_stake_from_storage( netuid, key );
+        }
+
+        // --- 3. Erase all memory associated with the network and retire the netuid.
+        Self::clear_subnet_storage( netuid );
+        TotalSubnets::<T>::mutate(|val| *val = val.saturating_sub( 1 ));
+        RemovedSubnets::<T>::insert( netuid, () );
+
+        // --- 4. Emit the event.
+        log::info!("NetworkRemoved( netuid:{:?} )", netuid);
+        Self::deposit_event( Event::NetworkRemoved( netuid ) );
 
+        return netuid;
+    }
+
+
+    // Erases every storage entry keyed by the netuid.
+    pub fn clear_subnet_storage( netuid: u16 ) {
         // --- 1. Remove incentive mechanism memory.
-        Uids::<T>::clear_prefix( netuid, u32::max_value(), None );
-        Keys::<T>::clear_prefix( netuid, u32::max_value(), None );
-        Weights::<T>::clear_prefix( netuid, u32::max_value(), None );
-        Emission::<T>::remove( netuid );
-        Incentive::<T>::remove( netuid );
-        Dividends::<T>::remove( netuid );
-        LastUpdate::<T>::remove( netuid );
+        SubnetNames::<T>::remove( netuid );
+        let _ = Uids::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = Keys::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = Weights::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = WeightCommits::<T>::clear_prefix( netuid, u32::max_value(), None );
+        CommitRevealWeights::<T>::remove( netuid );
+        let _ = PreviousWeights::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = WeightCopyDetections::<T>::clear_prefix( netuid, u32::max_value(), None );
+        WeightCopyThreshold::<T>::remove( netuid );
+        WeightCopyPenalty::<T>::remove( netuid );
+        RegistrationModes::<T>::remove( netuid );
+        MaxInactiveBlocks::<T>::remove( netuid );
+        let _ = RegistrationAllowlist::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = Names::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = Namespace::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = Address::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = BlockAtRegistration::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = Emission::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = Incentive::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = Dividends::<T>::clear_prefix( netuid, u32::max_value(), None );
+        let _ = LastUpdate::<T>::clear_prefix( netuid, u32::max_value(), None );
         Founder::<T>::remove( netuid );
+        FounderShare::<T>::remove( netuid );
+        SubnetVoteShare::<T>::remove( netuid );
+        Self::remove_subnet_from_votes( netuid );
+        Self::remove_pending_slashes_of_subnet( netuid );
+        SubnetRegisteredAt::<T>::remove( netuid );
+        PendingEmission::<T>::remove( netuid );
+        SubnetEmission::<T>::remove( netuid );
 
         // --- 2. Erase network parameters.
         Tempo::<T>::remove( netuid );
         MaxAllowedUids::<T>::remove( netuid );
         ImmunityPeriod::<T>::remove( netuid );
         MinAllowedWeights::<T>::remove( netuid );
+        MaxAllowedWeights::<T>::remove( netuid );
+        MaxRegistrationsPerBlock::<T>::remove( netuid );
+        RegistrationsThisBlock::<T>::remove( netuid );
         N::<T>::remove( netuid );
 
-        // --- 3. Erase network stake, and remove network from list of networks.
-        for ( key, stated_amount ) in <Stake<T> as IterableStorageDoubleMap<u16, T::AccountId, u64> >::iter_prefix(netuid){
-            Self::remove_stake_from_storage( netuid, &key );
-        }
-        // --- 4. Remove all stake.
-        Stake::<T>::remove_prefix( netuid, None );
+        // --- 3. Erase network stake.
+        let _ = Stake::<T>::clear_prefix( netuid, u32::max_value(), None );
         SubnetTotalStake::<T>::remove( netuid );
-        TotalSubnets::<T>::mutate(|val| *val -= 1);
-        // --- 4. Emit the event.
-        log::info!("NetworkRemoved( netuid:{:?} )", netuid);
-        Self::deposit_event( Event::NetworkRemoved( netuid ) );
+    }
 
-        return netuid;
-        
 
+    // Returns true when no storage entry is keyed by the netuid, so it can be handed to a new network.
+    pub fn is_subnet_storage_clear( netuid: u16 ) -> bool {
+        return !N::<T>::contains_key( netuid )
+            && !SubnetNames::<T>::contains_key( netuid )
+            && !Tempo::<T>::contains_key( netuid )
+            && !Founder::<T>::contains_key( netuid )
+            && !SubnetTotalStake::<T>::contains_key( netuid )
+            && !PendingEmission::<T>::contains_key( netuid )
+            && Emission::<T>::iter_prefix( netuid ).next().is_none()
+            && LastUpdate::<T>::iter_prefix( netuid ).next().is_none()
+            && Uids::<T>::iter_prefix( netuid ).next().is_none()
+            && Keys::<T>::iter_prefix( netuid ).next().is_none()
+            && Weights::<T>::iter_prefix( netuid ).next().is_none()
+            && Names::<T>::iter_prefix( netuid ).next().is_none()
+            && Address::<T>::iter_prefix( netuid ).next().is_none()
+            && Stake::<T>::iter_prefix( netuid ).next().is_none();
+    }
+
+
+    // Returns the netuid for a new network: the lowest retired netuid, otherwise the next unused one.
+    pub fn allocate_netuid() -> u16 {
+        let netuid: u16 = match RemovedSubnets::<T>::iter_keys().min() {
+            Some( netuid ) => {
+                RemovedSubnets::<T>::remove( netuid );
+                netuid
+            },
+            None => {
+                let netuid: u16 = NextNetuid::<T>::get();
+                NextNetuid::<T>::put( netuid.saturating_add( 1 ) );
+                netuid
+            },
+        };
+        if !Self::is_subnet_storage_clear( netuid ) {
+            log::warn!("netuid {:?} still had storage before reuse, clearing it", netuid );
+            Self::clear_subnet_storage( netuid );
+        }
+        return netuid;
     }
 
 
@@ -480,10 +543,11 @@ impl<T: Config> Pallet<T> {
             n: Self::get_subnet_n(netuid).into(),
             max_allowed_uids: Self::get_max_allowed_uids(netuid).into(),
             tempo: Self::get_tempo(netuid).into(),
-            emission: SubnetEmission::<T>::get(netuid).into(),
-            stake: SubnetTotalStake::<T>::get(netuid).into(),
+            emission: SubnetEmission::<T>::get(netuid),
+            stake: SubnetTotalStake::<T>::get(netuid),
             founder: Founder::<T>::get(netuid),
-            
+            founder_share: FounderShare::<T>::get(netuid),
+            mode: RegistrationModes::<T>::get(netuid),
         };
 	}
 
@@ -507,8 +571,9 @@ impl<T: Config> Pallet<T> {
 
     // Returns true if the uid is set on the network.
     //
+    // Uids of a network are always 0..n, so this does not need to read the uid itself.
     pub fn is_uid_exist_on_network(netuid: u16, uid: u16) -> bool {
-        return  Keys::<T>::contains_key(netuid, uid);
+        return uid < Self::get_subnet_n( netuid );
     }
 
     // Returns true if the key holds a slot on the network.
@@ -536,42 +601,37 @@ impl<T: Config> Pallet<T> {
     }
 
     pub fn get_uid_for_name ( netuid: u16, name: Vec<u8> ) -> u16  {
-        return Namespace::<T>::get(netuid, name)
+        return BoundedName::<T>::try_from( name ).map_or( 0, |name| Namespace::<T>::get( netuid, name ) );
     }
 
     pub fn get_name_for_uid ( netuid: u16, uid: u16 ) -> Vec<u8>  {
-        return Names::<T>::get(netuid, uid);
+        return Names::<T>::get(netuid, uid).into_inner();
     }
 
 
     pub fn if_module_name_exists( netuid: u16, name: Vec<u8> ) -> bool {
-        return Namespace::<T>::contains_key( netuid, name.clone() );
-        
+        return BoundedName::<T>::try_from( name ).map_or( false, |name| Namespace::<T>::contains_key( netuid, name ) );
     }
 
     // Returns the stake of the uid on network or 0 if it doesnt exist.
     //
-    pub fn get_stake_for_uid( netuid: u16, module_uid: u16) -> u64 { 
+    pub fn get_stake_for_uid( netuid: u16, module_uid: u16) -> BalanceOf<T> { 
         return Self::get_stake_for_key( netuid, &Self::get_key_for_uid( netuid, module_uid) )
     }
 
-    pub fn get_stake_for_key( netuid: u16, key: &T::AccountId) -> u64 { 
+    pub fn get_stake_for_key( netuid: u16, key: &T::AccountId) -> BalanceOf<T> { 
         if Self::is_key_registered_on_network( netuid, &key) {
             return Stake::<T>::get( netuid, key );
         } else {
-            return 0;
+            return Zero::zero();
         }
     }
     
     
-    // Return the total number of subnetworks available on the chain.
+    // Return the total number of subnetworks available on the chain, kept in TotalSubnets as networks are added and removed.
     //
     pub fn get_number_of_subnets()-> u16 {
-        let mut number_of_subnets : u16 = 0;
-        for (_, _)  in <N<T> as IterableStorageMap<u16, u16>>::iter(){
-            number_of_subnets = number_of_subnets + 1;
-        }
-        return number_of_subnets;
+        return TotalSubnets::<T>::get();
     }
 
 
@@ -593,25 +653,25 @@ impl<T: Config> Pallet<T> {
 
     
     pub fn set_last_update_for_uid( netuid:u16, uid: u16, last_update: u64 ) { 
-        let mut updated_last_update_vec = Self::get_last_update( netuid ); 
-        if (uid as usize) < updated_last_update_vec.len() { 
-            updated_last_update_vec[uid as usize] = last_update;
-            LastUpdate::<T>::insert( netuid, updated_last_update_vec );
+        if uid < Self::get_subnet_n( netuid ) { 
+            LastUpdate::<T>::insert( netuid, uid, last_update );
         }  
     }
 
-    pub fn get_emission_for_uid( netuid:u16, uid: u16) -> u64 {let vec =  Emission::<T>::get( netuid ); if (uid as usize) < vec.len() { return vec[uid as usize] } else{ return 0 } }
-    pub fn get_incentive_for_uid( netuid:u16, uid: u16) -> u16 { let vec = Incentive::<T>::get( netuid ); if (uid as usize) < vec.len() { return vec[uid as usize] } else{ return 0 } }
-    pub fn get_dividends_for_uid( netuid:u16, uid: u16) -> u16 { let vec = Dividends::<T>::get( netuid ); if (uid as usize) < vec.len() { return vec[uid as usize] } else{ return 0 } }
-    pub fn get_last_update_for_uid( netuid:u16, uid: u16) -> u64 { let vec = LastUpdate::<T>::get( netuid ); if (uid as usize) < vec.len() { return vec[uid as usize] } else{ return 0 } }
-    pub fn get_pruning_score_for_uid( netuid:u16, uid: u16) -> u16 { let vec = Emission::<T>::get( netuid ); if (uid as usize) < vec.len() { return vec[uid as usize] as u16 } else{ return 0 } }
+    pub fn get_emission_for_uid( netuid:u16, uid: u16) -> BalanceOf<T> { Emission::<T>::get( netuid, uid ) }
+    pub fn get_incentive_for_uid( netuid:u16, uid: u16) -> u16 { Incentive::<T>::get( netuid, uid ) }
+    pub fn get_dividends_for_uid( netuid:u16, uid: u16) -> u16 { Dividends::<T>::get( netuid, uid ) }
+    pub fn get_last_update_for_uid( netuid:u16, uid: u16) -> u64 { LastUpdate::<T>::get( netuid, uid ) }
+    pub fn get_pruning_score_for_uid( netuid:u16, uid: u16) -> u16 { Emission::<T>::get( netuid, uid ).saturated_into::<u64>() as u16 }
 
 
     // ============================
 	// ==== Subnetwork Getters ====
 	// ============================
     pub fn get_tempo( netuid:u16 ) -> u16{ Tempo::<T>::get( netuid ) }
-    pub fn get_pending_emission( netuid:u16 ) -> u64{ PendingEmission::<T>::get( netuid ) }
+    pub fn get_subnet_immunity_period() -> u64 { SubnetImmunityPeriod::<T>::get() }
+    pub fn set_subnet_immunity_period( subnet_immunity_period: u64 ) { SubnetImmunityPeriod::<T>::put( subnet_immunity_period ) }
+    pub fn get_pending_emission( netuid:u16 ) -> BalanceOf<T> { PendingEmission::<T>::get( netuid ) }
     pub fn get_registrations_this_block( netuid:u16 ) -> u16 { RegistrationsThisBlock::<T>::get( netuid ) }
     pub fn get_module_block_at_registration( netuid: u16, module_uid: u16 ) -> u64 { BlockAtRegistration::<T>::get( netuid, module_uid )}
 
@@ -641,7 +701,7 @@ impl<T: Config> Pallet<T> {
     pub fn set_min_allowed_weights( netuid: u16, min_allowed_weights: u16 ) { MinAllowedWeights::<T>::insert( netuid, min_allowed_weights ); }
 
     pub fn get_max_allowed_weights( netuid:u16 ) -> u16 {
-            let max_allowed_weights = MaxAllowedWeights::<T>::get( netuid ) ; 
+            let max_allowed_weights = MaxAllowedWeights::<T>::get( netuid ).min( Self::get_max_weights_per_module() ) ; 
             let n = Self::get_subnet_n(netuid);
             // if n < min_allowed_weights, then return n
             if (n < max_allowed_weights) {
@@ -652,7 +712,11 @@ impl<T: Config> Pallet<T> {
         }
     pub fn set_max_allowed_weights( netuid: u16, max_allowed_weights: u16 ) { MaxAllowedWeights::<T>::insert( netuid, max_allowed_weights ); }
 
-    pub fn get_max_allowed_uids( netuid: u16 ) -> u16  { MaxAllowedUids::<T>::get( netuid ) }
+    pub fn get_max_allowed_uids( netuid: u16 ) -> u16  { MaxAllowedUids::<T>::get( netuid ).min( Self::get_max_modules_per_subnet() ) }
+
+    // The Config bounds, saturated to u16 like the per subnet limits they cap.
+    pub fn get_max_weights_per_module() -> u16 { T::MaxWeightsPerModule::get().min( u16::MAX as u32 ) as u16 }
+    pub fn get_max_modules_per_subnet() -> u16 { T::MaxModulesPerSubnet::get().min( u16::MAX as u32 ) as u16 }
     pub fn set_max_allowed_uids(netuid: u16, max_allowed: u16) { MaxAllowedUids::<T>::insert( netuid, max_allowed ); }
     
     pub fn get_uids( netuid: u16 ) -> Vec<u16> {
@@ -666,11 +730,7 @@ impl<T: Config> Pallet<T> {
     }
 
     pub fn get_names( netuid: u16 ) -> Vec<Vec<u8>> {
-        let mut names = Vec::<Vec<u8>>::new();
-        for ( uid, name ) in < Names<T> as IterableStorageDoubleMap<u16, u16, Vec<u8>> >::iter_prefix(netuid){
-            names.push( name );
-        }
-        return names;
+        return Names::<T>::iter_prefix( netuid ).map( |( _, name )| name.into_inner() ).collect();
     }
     pub fn get_addresses( netuid: u16 ) -> Vec<T::AccountId> {
         let mut addresses = Vec::<T::AccountId>::new();
@@ -718,10 +778,17 @@ impl<T: Config> Pallet<T> {
         return true;
     }
 
-    pub fn get_emissions( netuid:u16 ) -> Vec<u64> { Emission::<T>::get( netuid ) }
-    pub fn get_incentive( netuid:u16 ) -> Vec<u16> { Incentive::<T>::get( netuid ) }
-    pub fn get_dividends( netuid:u16 ) -> Vec<u16> { Dividends::<T>::get( netuid ) }
-    pub fn get_last_update( netuid:u16 ) -> Vec<u64> { LastUpdate::<T>::get( netuid ) }
+    // Per uid metrics in uid order, entries missing from storage are left out.
+    pub fn get_emissions( netuid:u16 ) -> Vec<BalanceOf<T>> { Self::collect_uid_values( Emission::<T>::iter_prefix( netuid ) ) }
+    pub fn get_incentive( netuid:u16 ) -> Vec<u16> { Self::collect_uid_values( Incentive::<T>::iter_prefix( netuid ) ) }
+    pub fn get_dividends( netuid:u16 ) -> Vec<u16> { Self::collect_uid_values( Dividends::<T>::iter_prefix( netuid ) ) }
+    pub fn get_last_update( netuid:u16 ) -> Vec<u64> { Self::collect_uid_values( LastUpdate::<T>::iter_prefix( netuid ) ) }
+
+    pub fn collect_uid_values<V>( entries: impl Iterator<Item = (u16, V)> ) -> Vec<V> {
+        let mut entries: Vec<(u16, V)> = entries.collect();
+        entries.sort_by_key( |( uid, _ )| *uid );
+        return entries.into_iter().map( |( _, value )| value ).collect();
+    }
     pub fn get_max_registrations_per_block( netuid: u16 ) -> u16 { MaxRegistrationsPerBlock::<T>::get( netuid ) }
     pub fn set_max_registrations_per_block( netuid: u16, max_registrations_per_block: u16 ) { MaxRegistrationsPerBlock::<T>::insert( netuid, max_registrations_per_block ); }
 
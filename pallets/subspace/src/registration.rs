@@ -1,5 +1,5 @@
 use super::*;
-use frame_support::{ pallet_prelude::DispatchResult};
+use frame_support::{ pallet_prelude::{DispatchResult, DispatchError}};
 use sp_std::convert::TryInto;
 use sp_core::{H256, U256};
 use crate::system::ensure_root;
@@ -12,6 +12,9 @@ use frame_support::sp_std::vec;
 
 const LOG_TARGET: &'static str = "runtime::subspace::registration";
 
+// Most inactive modules removed from a subnet in one epoch, the rest are removed in the next epochs.
+const MAX_DEREGISTRATIONS_PER_EPOCH: u16 = 32;
+
 impl<T: Config> Pallet<T> {
 
 
@@ -20,37 +23,44 @@ impl<T: Config> Pallet<T> {
         network: Vec<u8>,
         name: Vec<u8>,
         address: Vec<u8>,
-        stake_amount: u64,
+        stake_amount: BalanceOf<T>,
+        ticket: Option<RegistrationTicket<T::Signature>>,
     ) -> DispatchResult {
 
         // --- 1. Check that the caller has signed the transaction. 
         // TODO( const ): This not be the key signature or else an exterior actor can register the key and potentially control it?
         let key = ensure_signed( origin.clone() )?;  
 
-        let stake: u64 = Self::resolve_stake_amount( &key, stake_amount);
+        // --- 1. Names and addresses are stored bounded, longer ones are rejected.
+        let network: BoundedName<T> = Self::bounded_subnet_name( network )?;
+        let name: BoundedName<T> = Self::bounded_module_name( name )?;
+        let address: BoundedAddress<T> = Self::bounded_address( address )?;
+
+        let stake: BalanceOf<T> = Self::resolve_stake_amount( &key, stake_amount);
 
 
         ensure!( Self::can_remove_balance_from_account( &key, stake ), Error::<T>::NotEnoughBalanceToStake );
 
         let mut netuid: u16 = 0;       
-        let new_network : bool = !Self::if_subnet_name_exists( network.clone() );
-        let mut module_stake : u64 = stake.clone();
+        let new_network : bool = !Self::if_subnet_name_exists( network.to_vec() );
+        let mut module_stake : BalanceOf<T> = stake.clone();
 
         // --- 2. Ensure we are not exceeding the max allowed registrations per block.
 
         if new_network {
             // --- 2. Ensure that the network name is not already registered.
-            ensure!( !Self::if_subnet_name_exists( network.clone() ), Error::<T>::NetworkAlreadyRegistered );
+            ensure!( !Self::if_subnet_name_exists( network.to_vec() ), Error::<T>::NetworkAlreadyRegistered );
             ensure!( Self::enough_stake_to_start_network( stake ), Error::<T>::NotEnoughStakeToStartNetwork );
-            netuid = Self::add_network_from_registration(network.clone(), stake, &key);
+            netuid = Self::add_network_from_registration(network.clone(), stake, &key)?;
             
         }  else {
+            netuid = Self::get_netuid_for_name( network.to_vec() );            
             ensure!( Self::get_registrations_this_block( netuid ) < Self::get_max_registrations_per_block( netuid ), Error::<T>::TooManyRegistrationsThisBlock );
             ensure!( !Self::is_key_registered(netuid, &key), Error::<T>::KeyAlreadyRegistered );
-            ensure!( !Self::if_module_name_exists( netuid, name.clone() ), Error::<T>::NameAlreadyRegistered );
+            ensure!( !Self::if_module_name_exists( netuid, name.to_vec() ), Error::<T>::NameAlreadyRegistered );
+            Self::ensure_registration_allowed( netuid, &key, ticket.as_ref() )?;
             
             RegistrationsThisBlock::<T>::mutate( netuid, |val| *val += 1 );
-            netuid = Self::get_netuid_for_name( network.clone() );            
             
         }
 
@@ -58,10 +68,11 @@ impl<T: Config> Pallet<T> {
         let n: u16 = Self::get_subnet_n( netuid );
 
         if n < Self::get_max_allowed_uids( netuid ) {
-            uid = Self::append_module( netuid, &key , name.clone(), address.clone(), module_stake.clone());
+            uid = Self::append_module( netuid, &key , name.clone(), address.clone(), module_stake.clone())?;
         } else {
             uid = Self::get_lowest_uid( netuid );
-            Self::replace_module( netuid, uid, &key , name.clone(), address.clone(), module_stake.clone());
+            Self::replace_module( netuid, uid, &key , name.clone(), address.clone());
+            Self::add_stake_on_account( netuid, &key, module_stake.clone() )?;
             log::info!("prune module");
         }
 
@@ -74,6 +85,195 @@ impl<T: Config> Pallet<T> {
     }
 
 
+    pub fn do_bulk_register(
+        origin: T::RuntimeOrigin,
+        netuid: u16,
+        modules: Vec<(T::AccountId, Vec<u8>, Vec<u8>, BalanceOf<T>)>,
+    ) -> DispatchResult {
+
+        // --- 1. Only root or the founder of the subnet can register modules in bulk.
+        // The stake of the modules is paid by the founder, root has no balance to pay it from.
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        let payer: Option<T::AccountId> = match ensure_root( origin.clone() ) {
+            Ok(()) => None,
+            Err(_) => {
+                let key = ensure_signed( origin )?;
+                ensure!( Self::is_subnet_founder( netuid, &key ), Error::<T>::NotSubnetFounder );
+                Some( key )
+            }
+        };
+
+        // --- 2. Root can only register modules without stake, it has no balance to pay the stake from.
+        if payer.is_none() {
+            ensure!( modules.iter().all( |( _, _, _, stake )| stake.is_zero() ), Error::<T>::RootCanNotPayStake );
+        }
+
+        // --- 3. The whole batch has to fit the registration limits of the subnet.
+        let count: u16 = modules.len().try_into().map_err( |_| Error::<T>::MaxAllowedUidsExceeded )?;
+        ensure!( Self::get_subnet_n( netuid ).saturating_add( count ) <= Self::get_max_allowed_uids( netuid ), Error::<T>::MaxAllowedUidsExceeded );
+        ensure!( Self::get_registrations_this_block( netuid ).saturating_add( count ) <= Self::get_max_registrations_per_block( netuid ), Error::<T>::TooManyRegistrationsThisBlock );
+
+        // --- 4. Append every module, one failing module reverts the whole batch.
+        // The keys did not sign the batch, so their own balance is never locked: the stake is
+        // first sent from the payer to the key and only that amount is locked.
+        frame_support::storage::with_storage_layer( || {
+            for ( key, name, address, stake ) in modules {
+                let name: BoundedName<T> = Self::bounded_module_name( name )?;
+                let address: BoundedAddress<T> = Self::bounded_address( address )?;
+                ensure!( !Self::is_key_registered( netuid, &key ), Error::<T>::KeyAlreadyRegistered );
+                ensure!( !Self::if_module_name_exists( netuid, name.to_vec() ), Error::<T>::NameAlreadyRegistered );
+                if !stake.is_zero() {
+                    let payer: &T::AccountId = payer.as_ref().ok_or( Error::<T>::RootCanNotPayStake )?;
+                    T::Currency::transfer( payer, &key, stake, ExistenceRequirement::KeepAlive )
+                        .map_err( |_| Error::<T>::NotEnoughBalanceToStake )?;
+                }
+                Self::append_module( netuid, &key, name, address, stake )?;
+            }
+            Ok::<(), DispatchError>(())
+        })?;
+        RegistrationsThisBlock::<T>::mutate( netuid, |val| *val = val.saturating_add( count ) );
+
+        // --- 5. Deposit the bulk event.
+        log::info!("BulkModulesRegistered( netuid:{:?} count:{:?} ) ", netuid, count );
+        Self::deposit_event( Event::BulkModulesRegistered( netuid, count ) );
+        Ok(())
+    }
+
+
+    pub fn do_set_registration_mode( origin: T::RuntimeOrigin, netuid: u16, mode: RegistrationMode ) -> DispatchResult {
+        let key = ensure_signed( origin )?;
+
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( Self::is_subnet_founder( netuid, &key ), Error::<T>::NotSubnetFounder );
+
+        RegistrationModes::<T>::insert( netuid, mode.clone() );
+
+        log::info!("RegistrationModeSet( netuid:{:?}, mode:{:?} )", netuid, mode );
+        Self::deposit_event( Event::RegistrationModeSet( netuid, mode ) );
+        Ok(())
+    }
+
+
+    pub fn do_set_allowlisted( origin: T::RuntimeOrigin, netuid: u16, key: T::AccountId, allowed: bool ) -> DispatchResult {
+        let founder = ensure_signed( origin )?;
+
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( Self::is_subnet_founder( netuid, &founder ), Error::<T>::NotSubnetFounder );
+
+        if allowed {
+            RegistrationAllowlist::<T>::insert( netuid, &key, () );
+        } else {
+            RegistrationAllowlist::<T>::remove( netuid, &key );
+        }
+
+        log::info!("AllowlistSet( netuid:{:?}, key:{:?}, allowed:{:?} )", netuid, key, allowed );
+        Self::deposit_event( Event::AllowlistSet( netuid, key, allowed ) );
+        Ok(())
+    }
+
+
+    // Marks the module of the caller as alive without touching its weights or module info.
+    pub fn do_heartbeat( origin: T::RuntimeOrigin, netuid: u16 ) -> DispatchResult {
+        let key = ensure_signed( origin )?;
+
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( Self::is_registered( netuid, &key ), Error::<T>::NotRegistered );
+
+        let uid: u16 = Self::get_uid_for_key( netuid, &key );
+        LastUpdate::<T>::insert( netuid, uid, Self::get_current_block_as_u64() );
+        Ok(())
+    }
+
+
+    pub fn do_set_max_inactive_blocks( origin: T::RuntimeOrigin, netuid: u16, max_inactive_blocks: u64 ) -> DispatchResult {
+        let key = ensure_signed( origin )?;
+
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( Self::is_subnet_founder( netuid, &key ), Error::<T>::NotSubnetFounder );
+
+        if max_inactive_blocks == 0 {
+            MaxInactiveBlocks::<T>::remove( netuid );
+        } else {
+            MaxInactiveBlocks::<T>::insert( netuid, max_inactive_blocks );
+        }
+
+        log::info!("MaxInactiveBlocksSet( netuid:{:?}, max_inactive_blocks:{:?} )", netuid, max_inactive_blocks );
+        Self::deposit_event( Event::MaxInactiveBlocksSet( netuid, max_inactive_blocks ) );
+        Ok(())
+    }
+
+
+    // Deregisters the modules which were not active for more than max_inactive_blocks, unlocking their stake,
+    // and returns how many were removed, at most MAX_DEREGISTRATIONS_PER_EPOCH. The founder is never deregistered
+    // and the subnet is kept even if no module is left.
+    pub fn deregister_inactive_modules( netuid: u16, block_number: u64 ) -> u16 {
+        let max_inactive_blocks: u64 = MaxInactiveBlocks::<T>::get( netuid );
+        if max_inactive_blocks == 0 {
+            return 0;
+        }
+
+        // --- Walk down from the last uid, deregister_module moves the last uid into the removed one
+        // and every uid above the current one has already been checked.
+        let n: u16 = Self::get_subnet_n( netuid );
+        let mut deregistered: u16 = 0;
+        for uid in ( 0..n ).rev() {
+            if deregistered >= MAX_DEREGISTRATIONS_PER_EPOCH {
+                break;
+            }
+            let key: T::AccountId = Keys::<T>::get( netuid, uid );
+            if Self::is_subnet_founder( netuid, &key ) {
+                continue;
+            }
+            let last_active: u64 = LastUpdate::<T>::get( netuid, uid ).max( BlockAtRegistration::<T>::get( netuid, uid ) );
+            if block_number.saturating_sub( last_active ) <= max_inactive_blocks {
+                continue;
+            }
+            Self::deregister_module( netuid, uid );
+            deregistered += 1;
+
+            log::info!("ModuleDeregistered( netuid:{:?}, uid:{:?}, key:{:?} )", netuid, uid, key );
+            Self::deposit_event( Event::ModuleDeregistered( netuid, uid, key ) );
+        }
+        return deregistered;
+    }
+
+
+    // Checks the registration mode of the subnet lets the key in. The founder can always register.
+    pub fn ensure_registration_allowed( netuid: u16, key: &T::AccountId, ticket: Option<&RegistrationTicket<T::Signature>> ) -> DispatchResult {
+        if Self::is_subnet_founder( netuid, key ) {
+            return Ok(());
+        }
+        match RegistrationModes::<T>::get( netuid ) {
+            RegistrationMode::Open => {},
+            RegistrationMode::Allowlist => {
+                ensure!( RegistrationAllowlist::<T>::contains_key( netuid, key ), Error::<T>::RegistrationNotAllowed );
+            },
+            RegistrationMode::Invite => {
+                let ticket = ticket.ok_or( Error::<T>::RegistrationNotAllowed )?;
+                ensure!( Self::get_current_block_as_u64() <= ticket.expires_at, Error::<T>::InvalidRegistrationTicket );
+                let payload: Vec<u8> = Self::registration_ticket_payload( netuid, key, ticket.expires_at );
+                ensure!( ticket.signature.verify( &payload[..], &Founder::<T>::get( netuid ) ), Error::<T>::InvalidRegistrationTicket );
+            },
+        }
+        Ok(())
+    }
+
+    // Returns the bytes a founder signs to invite the key onto the subnet until the block expires_at.
+    pub fn registration_ticket_payload( netuid: u16, key: &T::AccountId, expires_at: u64 ) -> Vec<u8> {
+        return ( b"subspace/registration", netuid, key, expires_at ).encode();
+    }
+
+
+    // Converts a module name to its stored form, names longer than MaxNameLength are rejected.
+    pub fn bounded_module_name( name: Vec<u8> ) -> Result<BoundedName<T>, Error<T>> {
+        return name.try_into().map_err( |_| Error::<T>::ModuleNameTooLong );
+    }
+
+    // Converts a module address to its stored form, addresses longer than MaxAddressLength are rejected.
+    pub fn bounded_address( address: Vec<u8> ) -> Result<BoundedAddress<T>, Error<T>> {
+        return address.try_into().map_err( |_| Error::<T>::AddressTooLong );
+    }
+
     pub fn vec_to_hash( vec_hash: Vec<u8> ) -> H256 {
         let de_ref_hash = &vec_hash; // b: &Vec<u8>
         let de_de_ref_hash: &[u8] = &de_ref_hash; // c: &[u8]
@@ -170,18 +370,21 @@ impl<T: Config> Pallet<T> {
     
         // if len(name) > 0, then we update the name.
         if name.len() > 0 {
-            ensure!( name.len() <= MaxNameLength::<T>::get() as usize, Error::<T>::ModuleNameTooLong );
+            let name: BoundedName<T> = Self::bounded_module_name( name )?;
             let old_name = Names::<T>::get( netuid, uid ); // Get the old name.
             Namespace::<T>::remove( netuid, old_name ); // Remove the old name from the namespace.
-            ensure!(!Self::if_module_name_exists(netuid, name.clone()) , Error::<T>::ModuleNameAlreadyExists); 
+            ensure!(!Self::if_module_name_exists(netuid, name.to_vec()) , Error::<T>::ModuleNameAlreadyExists); 
             Namespace::<T>::insert( netuid, name.clone(), uid );
-            Names::<T>::insert( netuid, uid, name.clone() );
+            Names::<T>::insert( netuid, uid, name );
         }
         // if len(address) > 0, then we update the address.
         if address.len() > 0 {
-            Address::<T>::insert( netuid, uid, address.clone() );
+            Address::<T>::insert( netuid, uid, Self::bounded_address( address )? );
         }
 
+        // --- 7. Updating the module counts as activity.
+        LastUpdate::<T>::insert( netuid, uid, current_block );
+
         // --- 8. Return is successful dispatch. 
         Ok(())
     }
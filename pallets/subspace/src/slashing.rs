@@ -0,0 +1,169 @@
+use super::*;
+use frame_support::pallet_prelude::{DispatchResult, DispatchError};
+use frame_support::weights::Weight;
+use sp_std::vec::Vec;
+
+impl<T: Config> Pallet<T> {
+
+
+    pub fn do_slash_module(
+        origin: T::RuntimeOrigin,
+        netuid: u16,
+        key: T::AccountId,
+        percent: u16,
+        destination: SlashDestination,
+    ) -> DispatchResult {
+        // --- 1. Only root or the founder of the subnet can slash its modules.
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        let scheduled_by: Option<T::AccountId> = Self::ensure_root_or_founder( origin, netuid )?;
+
+        // --- 2. The key has to be a module of the subnet and the percentage a valid share.
+        ensure!( Self::is_registered( netuid, &key ), Error::<T>::NotRegistered );
+        ensure!( percent > 0 && percent <= 100, Error::<T>::StorageValueOutOfRange );
+
+        // --- 3. The slash only applies once its deferral period is over.
+        Self::schedule_slash( netuid, &key, percent, destination, scheduled_by )?;
+        Ok(())
+    }
+
+
+    pub fn do_cancel_slash(
+        origin: T::RuntimeOrigin,
+        slash_id: u64,
+    ) -> DispatchResult {
+        // --- 1. Only root or the key which scheduled the slash can cancel it.
+        let slash = PendingSlashes::<T>::get( slash_id ).ok_or( Error::<T>::SlashDoesNotExist )?;
+        if ensure_root( origin.clone() ).is_err() {
+            let key = ensure_signed( origin )?;
+            ensure!( slash.scheduled_by == Some( key ), Error::<T>::NotSlashScheduler );
+        }
+
+        // --- 2. Drop the slash before it applies.
+
+        PendingSlashes::<T>::remove( slash_id );
+        PendingSlashesAt::<T>::remove( slash.apply_at, slash_id );
+        Self::release_pending_slash( &slash.key );
+
+        log::info!("SlashCancelled( slash_id:{:?} )", slash_id );
+        Self::deposit_event( Event::SlashCancelled( slash_id ) );
+        Ok(())
+    }
+
+
+    pub fn do_set_slash_defer_period(
+        origin: T::RuntimeOrigin,
+        defer_period: u64,
+    ) -> DispatchResult {
+        ensure_root( origin )?;
+        SlashDeferPeriod::<T>::put( defer_period );
+
+        log::info!("SlashDeferPeriodSet( defer_period:{:?} )", defer_period );
+        Self::deposit_event( Event::SlashDeferPeriodSet( defer_period ) );
+        Ok(())
+    }
+
+
+    // Returns the founder which signed the origin, or None for root.
+    fn ensure_root_or_founder( origin: T::RuntimeOrigin, netuid: u16 ) -> Result<Option<T::AccountId>, DispatchError> {
+        if ensure_root( origin.clone() ).is_ok() {
+            return Ok( None );
+        }
+        let key = ensure_signed( origin )?;
+        ensure!( Self::is_subnet_founder( netuid, &key ), Error::<T>::NotSubnetFounder );
+        Ok( Some( key ) )
+    }
+
+
+    // Schedules a slash of percent of the stake of the key on the subnet and returns its id. The amount is
+    // fixed now and the stake of the key can not be removed or transferred until the slash is applied or cancelled.
+    // Detectors inside the pallet call this directly with no scheduler, so only root can cancel their slashes.
+    // The slashes of this block were already applied, so a slash applies at the next block at the earliest.
+    pub fn schedule_slash( netuid: u16, key: &T::AccountId, percent: u16, destination: SlashDestination, scheduled_by: Option<T::AccountId> ) -> Result<u64, DispatchError> {
+        ensure!( PendingSlashes::<T>::count() < MaxPendingSlashes::<T>::get(), Error::<T>::TooManyPendingSlashes );
+        let slash_id: u64 = NextSlashId::<T>::get();
+        let apply_at: u64 = Self::get_current_block_as_u64().saturating_add( SlashDeferPeriod::<T>::get().max( 1 ) );
+        let percent: u16 = percent.min( 100 );
+        let amount: BalanceOf<T> = Self::get_share_of( Self::get_stake( netuid, key ), percent );
+
+        NextSlashId::<T>::put( slash_id.saturating_add( 1 ) );
+        PendingSlashes::<T>::insert( slash_id, PendingSlash { netuid, key: key.clone(), percent, amount, destination, apply_at, scheduled_by } );
+        PendingSlashesAt::<T>::insert( apply_at, slash_id, () );
+        PendingSlashCount::<T>::mutate( key, |count| *count = count.saturating_add( 1 ) );
+
+        log::info!("SlashScheduled( slash_id:{:?}, netuid:{:?}, key:{:?}, percent:{:?}, apply_at:{:?} )", slash_id, netuid, key, percent, apply_at );
+        Self::deposit_event( Event::SlashScheduled( slash_id, netuid, key.clone(), percent, apply_at ) );
+        return Ok( slash_id );
+    }
+
+
+    // Applies the pending slashes due at the block and returns their weight. Only the slashes indexed
+    // under the block are read, at most MaxPendingSlashes of them.
+    pub fn apply_pending_slashes( block_number: u64 ) -> Weight {
+        let due: Vec<u64> = PendingSlashesAt::<T>::drain_prefix( block_number ).map( |( slash_id, _ )| slash_id ).collect();
+        let mut applied: u64 = 0;
+        for slash_id in due.iter() {
+            if let Some( slash ) = PendingSlashes::<T>::take( slash_id ) {
+                Self::release_pending_slash( &slash.key );
+                Self::apply_slash( *slash_id, &slash, block_number );
+                applied += 1;
+            }
+        }
+        return Weight::from_ref_time( 40_000_000 * applied )
+            .saturating_add( T::DbWeight::get().reads( 1 + due.len() as u64 + 7 * applied ) )
+            .saturating_add( T::DbWeight::get().writes( due.len() as u64 + 10 * applied ) );
+    }
+
+
+    // Drops the pending slashes on the removed subnet, so a netuid handed to a new network starts without them.
+    // At most MaxPendingSlashes slashes are read.
+    pub fn remove_pending_slashes_of_subnet( netuid: u16 ) {
+        let slashes: Vec<(u64, PendingSlash<T::AccountId, BalanceOf<T>>)> = PendingSlashes::<T>::iter()
+            .filter( |( _, slash )| slash.netuid == netuid )
+            .collect();
+        for ( slash_id, slash ) in slashes.iter() {
+            PendingSlashes::<T>::remove( slash_id );
+            PendingSlashesAt::<T>::remove( slash.apply_at, slash_id );
+            Self::release_pending_slash( &slash.key );
+        }
+    }
+
+
+    // Drops one pending slash from the count of the key, its stake is free to move once none is left.
+    fn release_pending_slash( key: &T::AccountId ) {
+        PendingSlashCount::<T>::mutate_exists( key, |count| {
+            *count = count.map( |count| count.saturating_sub( 1 ) ).filter( |count| *count > 0 );
+        });
+    }
+
+    pub fn has_pending_slash( key: &T::AccountId ) -> bool { PendingSlashCount::<T>::contains_key( key ) }
+    pub fn set_max_pending_slashes( max_pending_slashes: u32 ) { MaxPendingSlashes::<T>::put( max_pending_slashes ); }
+
+
+    // Takes the amount fixed when the slash was scheduled from the key and records it in the slash history.
+    // Stake is only ever held by the key itself, so there are no delegators to share the slash.
+    pub fn apply_slash( slash_id: u64, slash: &PendingSlash<T::AccountId, BalanceOf<T>>, block_number: u64 ) {
+        // --- 1. Drop the stake and its counters, then take the unlocked balance from the key.
+        // A module deregistered meanwhile has its stake unlocked already, the balance is still slashed.
+        Self::decrease_stake_on_account( slash.netuid, &slash.key, slash.amount );
+        let ( imbalance, _ ) = T::Currency::slash( &slash.key, slash.amount );
+        let amount: BalanceOf<T> = imbalance.peek();
+
+        // --- 2. A dropped imbalance lowers the issuance, otherwise it is paid into the treasury.
+        match slash.destination {
+            SlashDestination::Burn => drop( imbalance ),
+            SlashDestination::Treasury => T::Currency::resolve_creating( &Self::get_treasury_account(), imbalance ),
+        }
+
+        SlashHistory::<T>::insert( &slash.key, slash_id, SlashRecord {
+            netuid: slash.netuid,
+            percent: slash.percent,
+            amount,
+            destination: slash.destination.clone(),
+            block: block_number,
+        });
+
+        log::info!("SlashApplied( slash_id:{:?}, netuid:{:?}, key:{:?}, amount:{:?} )", slash_id, slash.netuid, slash.key, amount );
+        Self::deposit_event( Event::SlashApplied( slash_id, slash.netuid, slash.key.clone(), amount, slash.destination.clone() ) );
+    }
+
+}
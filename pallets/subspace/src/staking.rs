@@ -1,6 +1,9 @@
 use super::*;
 use substrate_fixed::types::{I64F64, I32F32};
 
+// Stake stays in the key's account, held by a lock under this id.
+pub const STAKE_LOCK_ID: LockIdentifier = *b"subspace";
+
 impl<T: Config> Pallet<T> { 
 
 
@@ -8,7 +11,7 @@ impl<T: Config> Pallet<T> {
 	pub fn do_add_stake(
         origin: T::RuntimeOrigin, 
         netuid: u16,
-        stake_to_be_added: u64
+        stake_to_be_added: BalanceOf<T>
     ) -> dispatch::DispatchResult {
         // --- 1. We check that the transaction is signed by the caller and retrieve the T::AccountId key information.
         let key = ensure_signed( origin )?;
@@ -20,9 +23,7 @@ impl<T: Config> Pallet<T> {
         
         log::info!("do_add_stake( origin:{:?} stake_to_be_added:{:?} )", key, stake_to_be_added );
         
-        ensure!( Self::can_remove_balance_from_account( &key, stake_to_be_added ), Error::<T>::NotEnoughBalanceToStake );
-
-        Self::add_stake_on_account(netuid, &key, stake_to_be_added );
+        Self::add_stake_on_account(netuid, &key, stake_to_be_added )?;
  
         // --- 5. Emit the staking event.
         log::info!("StakeAdded( key:{:?}, stake_to_be_added:{:?} )", key, stake_to_be_added );
@@ -36,7 +37,7 @@ impl<T: Config> Pallet<T> {
     pub fn do_remove_stake(
         origin: T::RuntimeOrigin, 
         netuid: u16,
-        stake_to_be_removed: u64
+        stake_to_be_removed: BalanceOf<T>
     ) -> dispatch::DispatchResult {
 
         // --- 1. We check the transaction is signed by the caller and retrieve the T::AccountId key information.
@@ -47,13 +48,11 @@ impl<T: Config> Pallet<T> {
 		// --- 6. Ensure we don't exceed tx rate limit
 		// ensure!( !Self::exceeds_tx_rate_limit(&key), Error::<T>::TxRateLimitExceeded );
 
-        // --- 5. Ensure that we can conver this u64 to a balance.
-        ensure!( Self::has_enough_stake(netuid, &key, stake_to_be_removed ), Error::<T>::NotEnoughStaketoWithdraw );
-        let stake_to_be_added_as_currency = Self::u64_to_balance( stake_to_be_removed );
-        ensure!( stake_to_be_added_as_currency.is_some(), Error::<T>::CouldNotConvertToBalance );
+        // --- 7. A pending slash keeps the stake of the key in place until it applies.
+        ensure!( !Self::has_pending_slash( &key ), Error::<T>::StakeHasPendingSlash );
 
-        // --- 7. We remove the balance from the key.
-        Self::remove_stake_on_account(netuid,  &key, stake_to_be_removed );
+        // --- 8. We unlock the stake on the key.
+        Self::remove_stake_on_account(netuid,  &key, stake_to_be_removed )?;
 
         // --- 9. Emit the unstaking event.
         log::info!("StakeRemoved( key:{:?}, stake_to_be_removed:{:?} )", key, stake_to_be_removed );
@@ -64,21 +63,112 @@ impl<T: Config> Pallet<T> {
     }
 
 
+    pub fn do_transfer_stake(
+        origin: T::RuntimeOrigin, 
+        from_netuid: u16,
+        to_netuid: u16,
+        amount: BalanceOf<T>
+    ) -> dispatch::DispatchResult {
+
+        // --- 1. We check the transaction is signed by the caller and retrieve the T::AccountId key information.
+        let key = ensure_signed( origin )?;
+        log::info!("do_transfer_stake( origin:{:?} from_netuid:{:?} to_netuid:{:?} amount:{:?} )", key, from_netuid, to_netuid, amount );
+
+        // --- 2. Both subnets have to exist and be different.
+        ensure!( from_netuid != to_netuid, Error::<T>::SameSubnetTransfer );
+        ensure!( Self::if_subnet_netuid_exists( from_netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( Self::if_subnet_netuid_exists( to_netuid ), Error::<T>::NetworkDoesNotExist );
+
+        // --- 3. Stake only moves between modules of the key, so it has to be registered on both subnets.
+        ensure!( Self::is_registered( from_netuid, &key ), Error::<T>::NotRegistered );
+        ensure!( Self::is_registered( to_netuid, &key ), Error::<T>::NotRegistered );
+        ensure!( !Self::has_pending_slash( &key ), Error::<T>::StakeHasPendingSlash );
+
+        // --- 4. We move the stake, the lock on the balance of the key stays the same.
+        Self::transfer_stake_on_account( from_netuid, to_netuid, &key, amount )?;
+
+        // --- 5. Emit the transfer event.
+        log::info!("StakeTransferred( key:{:?}, from_netuid:{:?}, to_netuid:{:?}, amount:{:?} )", key, from_netuid, to_netuid, amount );
+        Self::deposit_event( Event::StakeTransferred( key, from_netuid, to_netuid, amount ) );
+
+        // --- 6. Done and ok.
+        Ok(())
+    }
+
+
+    pub fn do_transfer_stake_to(
+        origin: T::RuntimeOrigin, 
+        netuid: u16,
+        dest: T::AccountId,
+        amount: BalanceOf<T>
+    ) -> dispatch::DispatchResult {
+
+        // --- 1. We check the transaction is signed by the caller and retrieve the T::AccountId key information.
+        let key = ensure_signed( origin )?;
+        log::info!("do_transfer_stake_to( origin:{:?} netuid:{:?} dest:{:?} amount:{:?} )", key, netuid, dest, amount );
+
+        // --- 2. Both keys have to be modules of the subnet.
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( key != dest, Error::<T>::SameKeyTransfer );
+        ensure!( Self::is_registered( netuid, &key ), Error::<T>::NotRegistered );
+        ensure!( Self::is_registered( netuid, &dest ), Error::<T>::NotRegistered );
+        ensure!( !Self::has_pending_slash( &key ), Error::<T>::StakeHasPendingSlash );
+
+        // --- 3. We move the stake together with the locked balance backing it.
+        Self::transfer_stake_to_account( netuid, &key, &dest, amount )?;
+
+        // --- 4. Emit the transfer event.
+        log::info!("StakeTransferredTo( key:{:?}, dest:{:?}, netuid:{:?}, amount:{:?} )", key, dest, netuid, amount );
+        Self::deposit_event( Event::StakeTransferredTo( key, dest, netuid, amount ) );
+
+        // --- 5. Done and ok.
+        Ok(())
+    }
+
+
+    pub fn do_set_payout_preference(
+        origin: T::RuntimeOrigin, 
+        preference: PayoutPreference<T::AccountId>
+    ) -> dispatch::DispatchResult {
+
+        // --- 1. We check the transaction is signed by the caller and retrieve the T::AccountId key information.
+        let key = ensure_signed( origin )?;
+
+        // --- 2. Compounding is the default, so it is not stored.
+        if preference == PayoutPreference::Compound {
+            PayoutPreferences::<T>::remove( &key );
+        } else {
+            PayoutPreferences::<T>::insert( &key, preference.clone() );
+        }
+
+        // --- 3. Emit the event.
+        log::info!("PayoutPreferenceSet( key:{:?}, preference:{:?} )", key, preference );
+        Self::deposit_event( Event::PayoutPreferenceSet( key, preference ) );
+        Ok(())
+    }
+
+
     // Returns the total amount of stake in the staking table.
     //
-    pub fn get_total_subnet_stake(netuid:u16) -> u64 { 
+    pub fn get_total_subnet_stake(netuid:u16) -> BalanceOf<T> { 
         return SubnetTotalStake::<T>::get(netuid);
     }
-    pub fn get_total_stake() -> u64 { 
+    pub fn get_total_stake() -> BalanceOf<T> { 
         return TotalStake::<T>::get();
     }
 
+    // Returns the stake of the key summed over every subnet, which is the amount locked on its balance.
+    //
+    pub fn get_total_stake_for_key( key: &T::AccountId ) -> BalanceOf<T> {
+        return LockedStake::<T>::get( key );
+    }
+
 
 
 
     // Returns the stake under the cold - hot pairing in the staking table.
     //
-    pub fn get_stake(netuid:u16, key: &T::AccountId ) -> u64 { 
+    pub fn get_stake(netuid:u16, key: &T::AccountId ) -> BalanceOf<T> { 
         return Stake::<T>::get(netuid,  key );
     }
     
@@ -89,69 +179,97 @@ impl<T: Config> Pallet<T> {
 
     // Returns true if the cold-hot staking account has enough balance to fufil the decrement.
     //
-    pub fn has_enough_stake(netuid: u16, key: &T::AccountId, decrement: u64 ) -> bool {
+    pub fn has_enough_stake(netuid: u16, key: &T::AccountId, decrement: BalanceOf<T> ) -> bool {
         return Self::get_stake(netuid ,  key ) >= decrement;
     }
 
 
 
 
-    // Increases the stake on the cold - hot pairing by increment while also incrementing other counters.
+    // Locks increment more of the key's free balance as stake on the subnet.
     // This function should be called rather than set_stake under account.
     // 
-
-    pub fn add_stake_on_account(netuid: u16, key: &T::AccountId, increment: u64 ) -> bool{
-
-        if !Stake::<T>::contains_key(netuid, key) {
-            Stake::<T>::insert(netuid, key, 0);
-        }
-
-
-        
-        Self::remove_balance_from_account( key, Self::u64_to_balance( increment ).unwrap() );
-        Self::increase_stake_on_account(netuid, key, increment);
-        
-        return true;
-
+    pub fn add_stake_on_account(netuid: u16, key: &T::AccountId, increment: BalanceOf<T> ) -> dispatch::DispatchResult {
+        ensure!( Self::can_remove_balance_from_account( key, increment ), Error::<T>::NotEnoughBalanceToStake );
+        Self::increase_stake_on_account(netuid, key, increment)
     }
 
 
-    pub fn increase_stake_on_account(netuid:u16, key: &T::AccountId, increment: u64 ){
-        Stake::<T>::insert(netuid, key, Stake::<T>::get(netuid, key).saturating_add( increment ) );
-        SubnetTotalStake::<T>::insert(netuid , SubnetTotalStake::<T>::get(netuid).saturating_add( increment ) );
-        TotalStake::<T>::put(TotalStake::<T>::get().saturating_add( increment ) );
-
+    // Increases the stake of the key and the stake counters, nothing is written if any of them would overflow.
+    //
+    pub fn increase_stake_on_account(netuid:u16, key: &T::AccountId, increment: BalanceOf<T> ) -> dispatch::DispatchResult {
+        let stake = Stake::<T>::get(netuid, key).checked_add( &increment ).ok_or( Error::<T>::ArithmeticOverflow )?;
+        let subnet_stake = SubnetTotalStake::<T>::get(netuid).checked_add( &increment ).ok_or( Error::<T>::ArithmeticOverflow )?;
+        let total_stake = TotalStake::<T>::get().checked_add( &increment ).ok_or( Error::<T>::ArithmeticOverflow )?;
+        let locked_stake = LockedStake::<T>::get( key ).checked_add( &increment ).ok_or( Error::<T>::ArithmeticOverflow )?;
+
+        Stake::<T>::insert(netuid, key, stake );
+        SubnetTotalStake::<T>::insert(netuid , subnet_stake );
+        TotalStake::<T>::put( total_stake );
+        LockedStake::<T>::insert( key, locked_stake );
+        Self::update_stake_lock( key );
+        Ok(())
     }
 
 
     // Decreases the stake on the cold - hot pairing by the decrement while decreasing other counters.
+    // The decrement is capped by the stake, so the counters can not underflow.
     //
-    pub fn decrease_stake_on_account(netuid:u16, key: &T::AccountId, decrement: u64 ) {
-        // --- 8. We add the balancer to the key.  If the above fails we will not credit this key.
+    pub fn decrease_stake_on_account(netuid:u16, key: &T::AccountId, decrement: BalanceOf<T> ) {
+        let decrement = decrement.min( Stake::<T>::get( netuid, key ) );
         Stake::<T>::insert( netuid, key, Stake::<T>::get(netuid,  key).saturating_sub( decrement ) );
         TotalStake::<T>::put(TotalStake::<T>::get().saturating_sub( decrement ) );
         SubnetTotalStake::<T>::insert(netuid, SubnetTotalStake::<T>::get(netuid).saturating_sub( decrement ) );
+        LockedStake::<T>::insert( key, LockedStake::<T>::get( key ).saturating_sub( decrement ) );
+        Self::update_stake_lock( key );
     }
-    // Decreases the stake on the cold - hot pairing by the decrement while decreasing other counters.
+
+    // Sets the stake lock of the key to its stake over every subnet, dropping it once nothing is staked.
     //
-    pub fn remove_stake_on_account(netuid:u16, key: &T::AccountId, decrement: u64 ) {
+    pub fn update_stake_lock( key: &T::AccountId ) {
+        let locked = LockedStake::<T>::get( key );
+        if locked.is_zero() {
+            LockedStake::<T>::remove( key );
+            T::Currency::remove_lock( STAKE_LOCK_ID, key );
+        } else {
+            T::Currency::set_lock( STAKE_LOCK_ID, key, locked, WithdrawReasons::all() );
+        }
+    }
 
-        let stake_to_be_added_as_currency = Self::u64_to_balance( decrement );
+    // Unlocks decrement of the stake on the subnet back into the key's free balance.
+    //
+    pub fn remove_stake_on_account(netuid:u16, key: &T::AccountId, decrement: BalanceOf<T> ) -> dispatch::DispatchResult {
+        ensure!( Self::has_enough_stake(netuid, key, decrement ), Error::<T>::NotEnoughStaketoWithdraw );
+        Self::decrease_stake_on_account(netuid, key, decrement );
+        Ok(())
+    }
 
-        // --- 8. We add the balancer to the key.  If the above fails we will not credit this key.
-        Self::decrease_stake_on_account(netuid, &key, decrement );
-        Self::add_balance_to_account( &key, stake_to_be_added_as_currency.unwrap() );
+    // Moves amount of the stake of the key from one subnet to another.
+    //
+    pub fn transfer_stake_on_account(from_netuid: u16, to_netuid: u16, key: &T::AccountId, amount: BalanceOf<T> ) -> dispatch::DispatchResult {
+        ensure!( Self::has_enough_stake(from_netuid, key, amount ), Error::<T>::NotEnoughStaketoWithdraw );
+        Self::decrease_stake_on_account(from_netuid, key, amount );
+        Self::increase_stake_on_account(to_netuid, key, amount )
     }
 
-    // Decreases the stake on the cold - hot pairing by the decrement while decreasing other counters.
+    // Moves amount of the stake of the key on the subnet to dest. The stake is unlocked, the balance
+    // is sent to dest and locked there again, so the stake of the subnet does not change.
     //
-    pub fn remove_all_stake_on_account(netuid:u16, key: &T::AccountId ) {
+    pub fn transfer_stake_to_account(netuid: u16, key: &T::AccountId, dest: &T::AccountId, amount: BalanceOf<T> ) -> dispatch::DispatchResult {
+        ensure!( Self::has_enough_stake(netuid, key, amount ), Error::<T>::NotEnoughStaketoWithdraw );
+        Self::decrease_stake_on_account(netuid, key, amount );
+        T::Currency::transfer( key, dest, amount, ExistenceRequirement::KeepAlive )?;
+        Self::increase_stake_on_account(netuid, dest, amount )
+    }
 
+    // Unlocks all of the stake of the key on the subnet.
+    //
+    pub fn remove_all_stake_on_account(netuid:u16, key: &T::AccountId ) {
         let decrement = Stake::<T>::get(netuid,  &key);
-        Self::remove_stake_on_account(netuid, &key, decrement );
+        Self::decrease_stake_on_account(netuid, &key, decrement );
     }
 
-    // Decreases the stake on the cold - hot pairing by the decrement while decreasing other counters.
+    // Unlocks all of the stake of the key on the subnet and drops its entry.
     //
     pub fn remove_stake_from_storage(netuid:u16, key: &T::AccountId ) {
 
@@ -159,65 +277,56 @@ impl<T: Config> Pallet<T> {
         Stake::<T>::remove(netuid, &key);
     }
 
-	pub fn u64_to_balance( input: u64 ) -> Option<<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance> { input.try_into().ok() }
-
-    pub fn add_balance_to_account(key: &T::AccountId, amount: <<T as Config>::Currency as Currency<<T as system::Config>::AccountId>>::Balance) {
-        T::Currency::deposit_creating(&key, amount); // Infallibe
-    }
-
-    pub fn set_balance_on_account(key: &T::AccountId, amount: <<T as Config>::Currency as Currency<<T as system::Config>::AccountId>>::Balance) {
-        T::Currency::make_free_balance_be(&key, amount); 
+    // Mints new issuance into the account, used to pay out emission.
+    //
+    pub fn mint_to_account( key: &T::AccountId, amount: BalanceOf<T> ) -> dispatch::DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let minted = T::Currency::deposit_creating( key, amount );
+        ensure!( minted.peek() == amount, Error::<T>::BalanceMintError );
+        Ok(())
     }
 
-    pub fn can_remove_balance_from_account(key: &T::AccountId, amount_64: u64) -> bool {
-        let amount_as_balance = Self::u64_to_balance( amount_64 );
-        if amount_as_balance.is_none() {
-            return false;
-        }
-        let amount = amount_as_balance.unwrap();
-        let current_balance = Self::get_balance(key);
-        if amount > current_balance {
-            return false;
+    // Mints the emission of the key on the subnet and pays it out by the given preference.
+    //
+    pub fn payout_emission( netuid: u16, key: &T::AccountId, amount: BalanceOf<T>, preference: &PayoutPreference<T::AccountId> ) -> dispatch::DispatchResult {
+        match preference {
+            PayoutPreference::Compound => {
+                Self::mint_to_account( key, amount )?;
+                Self::increase_stake_on_account( netuid, key, amount )
+            },
+            PayoutPreference::Free => Self::mint_to_account( key, amount ),
+            PayoutPreference::Account( dest ) => Self::mint_to_account( dest, amount ),
         }
-        // This bit is currently untested. @todo
-        let new_potential_balance = current_balance - amount;
-        let can_withdraw = T::Currency::ensure_can_withdraw(&key, amount, WithdrawReasons::except(WithdrawReasons::TIP), new_potential_balance).is_ok();
-        can_withdraw
     }
 
-    pub fn get_balance(key: &T::AccountId) -> <<T as Config>::Currency as Currency<<T as system::Config>::AccountId>>::Balance {
-        return T::Currency::free_balance(&key);
+    // Mints amount into the key, failing instead of dropping it when the key would stay below the existential deposit.
+    pub fn add_balance_to_account(key: &T::AccountId, amount: BalanceOf<T>) -> dispatch::DispatchResult {
+        Self::mint_to_account( key, amount )
     }
 
-    pub fn balance_to_u64( input: <<T as Config>::Currency as Currency<<T as system::Config>::AccountId>>::Balance) -> u64 { input.try_into().ok().unwrap() }
-
-    pub fn get_balance_as_u64(key: &T::AccountId) -> u64 {
-        return Self::balance_to_u64( Self::get_balance(key) );
+    pub fn set_balance_on_account(key: &T::AccountId, amount: BalanceOf<T>) {
+        T::Currency::make_free_balance_be(&key, amount); 
     }
 
-    pub fn has_enough_balance(key: &T::AccountId, decrement: u64 ) -> bool {
-        return Self::get_balance_as_u64(key) >= decrement;
+    // Stake is locked on the free balance, so only the part which is not locked yet can be staked.
+    pub fn can_remove_balance_from_account(key: &T::AccountId, amount: BalanceOf<T>) -> bool {
+        return amount <= Self::get_balance( key );
     }
 
-    pub fn resolve_stake_amount(key: &T::AccountId, stake: u64 ) -> u64 {
-        let balance = Self::get_balance_as_u64(key);
-        if balance < stake {
-            return balance;
-        } else {
-            return stake;
-        }
+    // Returns the free balance of the key which is not locked as stake.
+    //
+    pub fn get_balance(key: &T::AccountId) -> BalanceOf<T> {
+        return T::Currency::free_balance(&key).saturating_sub( LockedStake::<T>::get( key ) );
     }
 
+    pub fn has_enough_balance(key: &T::AccountId, decrement: BalanceOf<T> ) -> bool {
+        return Self::get_balance(key) >= decrement;
+    }
 
-    pub fn remove_balance_from_account(key: &T::AccountId, amount: <<T as Config>::Currency as Currency<<T as system::Config>::AccountId>>::Balance) -> bool {
-        return match T::Currency::withdraw(&key, amount, WithdrawReasons::except(WithdrawReasons::TIP), ExistenceRequirement::KeepAlive) {
-            Ok(_result) => {
-                true
-            }
-            Err(_error) => {
-                false
-            }
-        };
+    pub fn resolve_stake_amount(key: &T::AccountId, stake: BalanceOf<T> ) -> BalanceOf<T> {
+        return stake.min( Self::get_balance(key) );
     }
 
-}
\ No newline at end of file
+}
@@ -4,32 +4,78 @@ use frame_support::sp_std::vec;
 use frame_support::inherent::Vec;
 use substrate_fixed::types::{I32F32, I64F64, I96F32, I110F18};
 use frame_support::storage::{IterableStorageMap, IterableStorageDoubleMap};
+use frame_support::weights::Weight;
+
+// Upper bound on the row comparisons of weight copy detection in one epoch, each costs up to
+// MaxWeightsPerModule multiplications.
+const MAX_WEIGHT_COPY_COMPARISONS: u64 = 16_384;
 
 impl<T: Config> Pallet<T> { 
 
-    pub fn block_step( ) {
+    // Runs the per block work of the pallet and returns the weight of the parts which vary with the state,
+    // on top of the fixed weight charged in on_initialize.
+    pub fn block_step( ) -> Weight {
         let block_number: u64 = Self::get_current_block_as_u64();
         log::debug!("block_step for block: {:?} ", block_number );
-        for ( netuid, tempo )  in <Tempo<T> as IterableStorageMap<u16, u16>>::iter() {
+        let mut weight: Weight = Weight::zero();
+        weight = weight.saturating_add( Self::resolve_proposals( block_number ) );
+        weight = weight.saturating_add( Self::remove_tallied_votes() );
+        weight = weight.saturating_add( Self::apply_pending_slashes( block_number ) );
+        Self::update_emission_halvings();
+        weight = weight.saturating_add( Self::update_subnet_vote_shares_at( block_number ) );
+        // --- Collected first, deregistering inactive modules can remove a subnet while the loop runs.
+        let tempos: Vec<(u16, u16)> = <Tempo<T> as IterableStorageMap<u16, u16>>::iter().collect();
+        for ( netuid, tempo ) in tempos {
             RegistrationsThisBlock::<T>::mutate(netuid,  |val| *val = 0 );
-            let new_queued_emission : u64 = Self::calculate_network_emission( netuid );
-            PendingEmission::<T>::mutate( netuid, | queued | *queued += new_queued_emission );
-            log::debug!("netuid_i: {:?} queued_emission: +{:?} ", netuid, new_queued_emission );  
-            if  (block_number + netuid as u64) % (tempo as u64) > 0 {
+
+            // --- Tempo 0 pauses the subnet, it neither accrues emission nor runs epochs.
+            if tempo == 0 {
+                SubnetEmission::<T>::insert( netuid, BalanceOf::<T>::zero() );
                 continue;
             }
-            let emission_to_drain:u64 = PendingEmission::<T>::get( netuid ).clone(); 
-            Self::epoch( netuid, emission_to_drain );
-            PendingEmission::<T>::insert( netuid, 0 );
 
+            // --- Emission of a subnet without modules is not minted, nobody could receive it.
+            if Self::get_subnet_n( netuid ) > 0 {
+                let subnet_emission : BalanceOf<T> = Self::calculate_network_emission( netuid );
+                let new_queued_emission : BalanceOf<T> = Self::distribute_emission_shares( netuid, subnet_emission );
+                PendingEmission::<T>::mutate( netuid, | queued | *queued = queued.saturating_add( new_queued_emission ) );
+                log::debug!("netuid_i: {:?} queued_emission: +{:?} ", netuid, new_queued_emission );  
+            } else {
+                SubnetEmission::<T>::insert( netuid, BalanceOf::<T>::zero() );
+            }
+
+            if Self::blocks_until_next_epoch( netuid, tempo, block_number ) > 0 {
+                continue;
+            }
+            // --- Each deregistration moves one module and remaps the weight rows of the others.
+            let n: u64 = Self::get_subnet_n( netuid ) as u64;
+            let deregistered: u16 = Self::deregister_inactive_modules( netuid, block_number );
+            weight = weight.saturating_add( T::DbWeight::get().reads_writes( 40 + 3 * n, 40 + 3 * n ).saturating_mul( deregistered as u64 ) );
+            if !Self::if_subnet_exist( netuid ) {
+                continue;
+            }
+            Self::expire_weight_commits( netuid, block_number );
+            weight = weight.saturating_add( Self::get_weight_copy_detection_weight( netuid ) );
+            let emission_to_drain: BalanceOf<T> = PendingEmission::<T>::get( netuid ); 
+            let distributed_emission: BalanceOf<T> = Self::epoch( netuid, emission_to_drain );
+            PendingEmission::<T>::insert( netuid, BalanceOf::<T>::zero() );
+
+            // --- Only the distributed amount is minted as stake, rounding dust is dropped.
+            log::debug!("EpochEmission( netuid:{:?}, pending:{:?}, distributed:{:?} )", netuid, emission_to_drain, distributed_emission );
+            Self::deposit_event( Event::EpochEmission( netuid, emission_to_drain, distributed_emission ) );
         }
+        return weight;
     }
 
 
-    pub fn epoch( netuid: u16, token_emission: u64 ) {
+    // Distributes the token emission over the modules of the subnet and returns the amount credited.
+    pub fn epoch( netuid: u16, token_emission: BalanceOf<T> ) -> BalanceOf<T> {
         // Get subnetwork size.
         let n: u16 = Self::get_subnet_n( netuid );
         log::trace!( "n: {:?}", n );
+        if n == 0 {
+            return Zero::zero();
+        }
 
         // Get current block.
         let current_block: u64 = Self::get_current_block_as_u64();
@@ -47,14 +93,12 @@ impl<T: Config> Pallet<T> {
         let mut keys: Vec<(u16, T::AccountId)> = Self::get_uid_key_tuples( netuid );
         log::trace!( "keys: {:?}", &keys );
         // Access network stake as normalized vector.
+        // Stake proportions are computed on the balances, which may not fit the fixed point range.
         let mut stake_64: Vec<I64F64> = vec![ I64F64::from_num(0.0); n as usize ];
-        let mut total_stake : I64F64 = I64F64::from_num(Self::get_total_subnet_stake( netuid ).clone());
-        if total_stake == I64F64::from_num(0.0) {
-            total_stake = I64F64::from_num(1.0);
-        }
+        let total_stake : BalanceOf<T> = Self::get_total_subnet_stake( netuid );
         for (uid_i, key) in keys.iter() {
 
-            stake_64[ *uid_i as usize ] = I64F64::from_num( Self::get_stake_for_key(netuid, key ).clone()) /  total_stake ;
+            stake_64[ *uid_i as usize ] = balance_proportion( Self::get_stake_for_key(netuid, key ), total_stake );
         }
 
         let mut stake: Vec<I32F32> = stake_64.iter().map( |x| I32F32::from_num(x.clone()) ).collect();
@@ -105,6 +149,23 @@ impl<T: Config> Pallet<T> {
         inplace_normalize( &mut dividends );
         log::trace!( "D: {:?}", &dividends );
 
+        // ====================
+        // == Weight copying ==
+        // ====================
+
+        // Modules whose weights match the previous weights of another module lose part of their dividends.
+        let copy_threshold: u16 = WeightCopyThreshold::<T>::get( netuid );
+        if copy_threshold > 0 {
+            let penalty: I32F32 = I32F32::from_num( WeightCopyPenalty::<T>::get( netuid ).min( 100 ) ) / I32F32::from_num( 100 );
+            for ( uid, source_uid, similarity ) in Self::detect_weight_copies( netuid, &weights, copy_threshold ) {
+                dividends[ uid as usize ] = dividends[ uid as usize ] * ( I32F32::from_num( 1 ) - penalty );
+                WeightCopyDetections::<T>::insert( netuid, uid, WeightCopyDetection { block: current_block, source_uid: source_uid, similarity: fixed_proportion_to_u16( similarity ) } );
+                log::debug!( "weight copy( netuid:{:?}, uid:{:?}, source_uid:{:?}, similarity:{:?} )", netuid, uid, source_uid, similarity );
+            }
+            Self::store_previous_weights( netuid );
+        }
+        log::trace!( "D (copy penalty): {:?}", &dividends );
+
         // =================================
         // == Emission==
         // =================================
@@ -122,32 +183,48 @@ impl<T: Config> Pallet<T> {
         inplace_normalize( &mut normalized_emission );
 
         
-        // Compute rao based emission scores. range: I96F32(0, token_emission)
-        let emission: Vec<I64F64> = normalized_emission.iter().map( |e: &I32F32| I64F64::from_num(*e) * I64F64::from_num(token_emission) ).collect();
-        let emission: Vec<u64> = emission.iter().map( |e: &I64F64| e.to_num::<u64>() ).collect();
+        // Compute rao based emission scores. range: (0, token_emission)
+        let emission: Vec<BalanceOf<T>> = normalized_emission.iter().map( |e: &I32F32| balance_share( token_emission, I64F64::from_num(*e) ) ).collect();
         log::trace!( "nE: {:?}", &normalized_emission );
         log::trace!( "E: {:?}", &emission );
 
         // ===================
         // == Value storage ==
         // ===================
-        Emission::<T>::insert( netuid, emission.clone() );
-        let cloned_incentive: Vec<u16> = incentive.iter().map(|xi| fixed_proportion_to_u16(*xi)).collect::<Vec<u16>>();
-        Incentive::<T>::insert( netuid, cloned_incentive );
-        let cloned_dividends: Vec<u16> = dividends.iter().map(|xi| fixed_proportion_to_u16(*xi)).collect::<Vec<u16>>();
-        Dividends::<T>::insert( netuid, cloned_dividends );
-
-        // Emission tuples ( keys, u64 emission)
-        let mut result: Vec<(T::AccountId, u64)> = vec![]; 
+        for uid in 0..n {
+            Emission::<T>::insert( netuid, uid, emission[ uid as usize ] );
+            Incentive::<T>::insert( netuid, uid, fixed_proportion_to_u16( incentive[ uid as usize ] ) );
+            Dividends::<T>::insert( netuid, uid, fixed_proportion_to_u16( dividends[ uid as usize ] ) );
+        }
+
+        // Emission tuples ( keys, emission)
+        let mut result: Vec<(T::AccountId, BalanceOf<T>)> = vec![]; 
         for ( uid_i, key ) in keys.iter() {
             result.push( ( key.clone(), emission[ *uid_i as usize ] ) );
         }
             
-        // --- 6. emmit
+        // --- 6. emmit, the rewards are minted and paid out by the payout preference of each key.
+        let mut compounded_emission: BalanceOf<T> = Zero::zero();
+        let mut free_emission: BalanceOf<T> = Zero::zero();
+        let mut forwarded_emission: BalanceOf<T> = Zero::zero();
         for (key, amount) in result.iter() {                 
-            Self::increase_stake_on_account(netuid, &key, *amount );
+            let preference: PayoutPreference<T::AccountId> = PayoutPreferences::<T>::get( key );
+            match Self::payout_emission( netuid, key, *amount, &preference ) {
+                Ok(()) => match preference {
+                    PayoutPreference::Compound => compounded_emission = compounded_emission.saturating_add( *amount ),
+                    PayoutPreference::Free => free_emission = free_emission.saturating_add( *amount ),
+                    PayoutPreference::Account( _ ) => forwarded_emission = forwarded_emission.saturating_add( *amount ),
+                },
+                Err(error) => log::warn!("could not mint emission( netuid:{:?}, key:{:?}, amount:{:?} ): {:?}", netuid, key, amount, error ),
+            }
         }    
-    
+        let distributed_emission: BalanceOf<T> = compounded_emission.saturating_add( free_emission ).saturating_add( forwarded_emission );
+        if !distributed_emission.is_zero() {
+            log::info!("EmissionMinted( netuid:{:?}, compounded:{:?}, free:{:?}, forwarded:{:?} )", netuid, compounded_emission, free_emission, forwarded_emission );
+            Self::deposit_event( Event::EmissionMinted( netuid, compounded_emission, free_emission, forwarded_emission ) );
+        }
+
+        return distributed_emission;
     }
 
 
@@ -165,7 +242,7 @@ impl<T: Config> Pallet<T> {
     pub fn get_weights_sparse( netuid:u16 ) -> Vec<Vec<(u16, I32F32)>> { 
         let n: usize = Self::get_subnet_n( netuid ) as usize; 
         let mut weights: Vec<Vec<(u16, I32F32)>> = vec![ vec![]; n ]; 
-        for ( uid_i, weights_i ) in < Weights<T> as IterableStorageDoubleMap<u16 ,u16, Vec<(u16, u16)> >>::iter_prefix( netuid ) {
+        for ( uid_i, weights_i ) in < Weights<T> as IterableStorageDoubleMap<u16 ,u16, BoundedWeights<T> >>::iter_prefix( netuid ) {
             for (uid_j, weight_ij) in weights_i.iter() { 
                 weights [ uid_i as usize ].push( ( *uid_j, u16_proportion_to_fixed( *weight_ij ) ));
             }
@@ -176,16 +253,90 @@ impl<T: Config> Pallet<T> {
     } 
 
 
+    pub fn get_previous_weights_sparse( netuid:u16 ) -> Vec<Vec<(u16, I32F32)>> { 
+        let n: usize = Self::get_subnet_n( netuid ) as usize; 
+        let mut weights: Vec<Vec<(u16, I32F32)>> = vec![ vec![]; n ]; 
+        for ( uid_i, weights_i ) in PreviousWeights::<T>::iter_prefix( netuid ) {
+            if uid_i as usize >= n { continue; }
+            for (uid_j, weight_ij) in weights_i.iter() { 
+                weights [ uid_i as usize ].push( ( *uid_j, u16_proportion_to_fixed( *weight_ij ) ));
+            }
+        }
+        mask_diag_sparse( &weights )
+    }
+
+    // Keeps the current weights of the subnet to compare the weights of the next epoch against.
+    pub fn store_previous_weights( netuid: u16 ) {
+        let _ = PreviousWeights::<T>::clear_prefix( netuid, u32::max_value(), None );
+        for ( uid, weights ) in Weights::<T>::iter_prefix( netuid ) {
+            if !weights.is_empty() {
+                PreviousWeights::<T>::insert( netuid, uid, weights );
+            }
+        }
+    }
+
+    // Weight of weight copy detection on top of the epoch. The previous weights of every module are cleared
+    // and written again, and rows are compared at most MAX_WEIGHT_COPY_COMPARISONS times.
+    pub fn get_weight_copy_detection_weight( netuid: u16 ) -> Weight {
+        if WeightCopyThreshold::<T>::get( netuid ) == 0 {
+            return Weight::zero();
+        }
+        let n: u64 = Self::get_subnet_n( netuid ) as u64;
+        let comparisons: u64 = n.saturating_mul( n ).min( MAX_WEIGHT_COPY_COMPARISONS );
+        return Weight::from_ref_time( 20_000u64.saturating_mul( T::MaxWeightsPerModule::get() as u64 ).saturating_mul( comparisons ) )
+            .saturating_add( T::DbWeight::get().reads_writes( 2 * n + 1, 2 * n ) );
+    }
+
+    // Returns ( uid, source_uid, similarity ) for every uid whose weights have a cosine similarity of at least
+    // threshold percent with the previous weights of another uid, source_uid being the closest match.
+    // Every row is compared, also when it matches its own previous row, so keeping copied weights stable
+    // does not stop them from being flagged. Rows are compared at most MAX_WEIGHT_COPY_COMPARISONS times
+    // per epoch, the remaining rows are not checked.
+    pub fn detect_weight_copies( netuid: u16, weights: &Vec<Vec<(u16, I32F32)>>, threshold: u16 ) -> Vec<(u16, u16, I32F32)> {
+        let threshold: I32F32 = I32F32::from_num( threshold ) / I32F32::from_num( 100 );
+        let mut current: Vec<Vec<(u16, I32F32)>> = weights.clone();
+        let mut previous: Vec<Vec<(u16, I32F32)>> = Self::get_previous_weights_sparse( netuid );
+        current.iter_mut().for_each( |row| row.sort_by_key( |( uid, _ )| *uid ) );
+        previous.iter_mut().for_each( |row| row.sort_by_key( |( uid, _ )| *uid ) );
+        let sources: Vec<usize> = ( 0..previous.len() ).filter( |uid_j| !previous[ *uid_j ].is_empty() ).collect();
+
+        let mut comparisons: u64 = 0;
+        let mut copies: Vec<(u16, u16, I32F32)> = vec![];
+        for ( uid_i, weights_i ) in current.iter().enumerate() {
+            if weights_i.is_empty() { continue; }
+            if comparisons.saturating_add( sources.len() as u64 ) > MAX_WEIGHT_COPY_COMPARISONS {
+                log::warn!( "weight copy detection on netuid {:?} stopped at uid {:?}", netuid, uid_i );
+                break;
+            }
+            comparisons += sources.len() as u64;
+
+            let mut closest: Option<(u16, I32F32)> = None;
+            for uid_j in sources.iter() {
+                if uid_i == *uid_j { continue; }
+                let similarity: I32F32 = sparse_cosine_similarity( weights_i, &previous[ *uid_j ] );
+                if similarity >= threshold && closest.map_or( true, |( _, closest_similarity )| similarity > closest_similarity ) {
+                    closest = Some( ( *uid_j as u16, similarity ) );
+                }
+            }
+            if let Some( ( source_uid, similarity ) ) = closest {
+                copies.push( ( uid_i as u16, source_uid, similarity ) );
+            }
+        }
+        copies
+    }
+
+
+    // Epochs run on the blocks where ( block_number + netuid ) % tempo == 0, so this is 0 on an epoch block.
     pub fn blocks_until_next_epoch( netuid: u16, tempo: u16, block_number: u64 ) -> u64 { 
-        if tempo == 0 { return 10 } // Special case: epoch = 0, the network never runs.
-        // epoch | netuid | # first epoch block
-        //   1        0               0
-        //   1        1               1
-        //   2        0               1
-        //   2        1               0
-        //   100      0              99
-        //   100      1              98
-        return tempo as u64 - ( block_number + netuid as u64 + 1 ) % ( tempo as u64 + 1 )
+        if tempo == 0 { return u64::MAX } // Special case: tempo = 0, the network is paused and never runs.
+        // tempo | netuid | block | # blocks until epoch
+        //   1        *       *          0
+        //   2        0       0          0
+        //   2        1       0          1
+        //   100      0       1         99
+        //   100      1       1         98
+        let tempo: u64 = tempo as u64;
+        return ( tempo - ( block_number + netuid as u64 ) % tempo ) % tempo
     }
 
  
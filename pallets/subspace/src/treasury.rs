@@ -0,0 +1,108 @@
+use super::*;
+use frame_support::pallet_prelude::DispatchResult;
+use frame_support::traits::Get;
+use sp_runtime::traits::AccountIdConversion;
+use sp_runtime::Percent;
+
+impl<T: Config> Pallet<T> {
+
+
+    // Account holding the treasury share of emission, derived from the pallet id.
+    pub fn get_treasury_account() -> T::AccountId {
+        return T::TreasuryPalletId::get().into_account_truncating();
+    }
+
+    pub fn get_treasury_balance() -> BalanceOf<T> {
+        return Self::get_balance( &Self::get_treasury_account() );
+    }
+
+
+    pub fn do_set_founder_share(
+        origin: T::RuntimeOrigin,
+        netuid: u16,
+        founder_share: u16,
+    ) -> DispatchResult {
+        let key = ensure_signed( origin )?;
+
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( Self::is_subnet_founder( netuid, &key ), Error::<T>::NotSubnetFounder );
+        ensure!( founder_share <= 100, Error::<T>::InvalidShare );
+
+        FounderShare::<T>::insert( netuid, founder_share );
+
+        log::info!("FounderShareSet( netuid:{:?}, founder_share:{:?} )", netuid, founder_share );
+        Self::deposit_event( Event::FounderShareSet( netuid, founder_share ) );
+        Ok(())
+    }
+
+
+    // Takes the treasury share and then the founder share out of the emission of this block for the subnet,
+    // returning what is left for the modules. Shares are always taken, a share which can not be minted yet
+    // because the account would stay below the existential deposit is kept until it can.
+    pub fn distribute_emission_shares( netuid: u16, emission: BalanceOf<T> ) -> BalanceOf<T> {
+        let mut remaining_emission: BalanceOf<T> = emission;
+
+        let treasury_emission: BalanceOf<T> = Self::get_share_of( remaining_emission, TreasuryShare::<T>::get() );
+        if !treasury_emission.is_zero() {
+            remaining_emission = remaining_emission.saturating_sub( treasury_emission );
+            let minted: BalanceOf<T> = Self::mint_emission_share( &Self::get_treasury_account(), treasury_emission );
+            if !minted.is_zero() {
+                log::debug!("TreasuryEmission( netuid:{:?}, amount:{:?} )", netuid, minted );
+                Self::deposit_event( Event::TreasuryEmission( netuid, minted ) );
+            }
+        }
+
+        let founder_emission: BalanceOf<T> = Self::get_share_of( remaining_emission, FounderShare::<T>::get( netuid ) );
+        if !founder_emission.is_zero() {
+            remaining_emission = remaining_emission.saturating_sub( founder_emission );
+            let founder: T::AccountId = Founder::<T>::get( netuid );
+            let minted: BalanceOf<T> = Self::mint_emission_share( &founder, founder_emission );
+            if !minted.is_zero() {
+                log::debug!("FounderEmission( netuid:{:?}, founder:{:?}, amount:{:?} )", netuid, founder, minted );
+                Self::deposit_event( Event::FounderEmission( netuid, founder, minted ) );
+            }
+        }
+
+        return remaining_emission;
+    }
+
+    // Mints the share together with the shares kept for the account and returns the minted amount,
+    // which is zero while they do not reach the existential deposit.
+    pub fn mint_emission_share( key: &T::AccountId, share: BalanceOf<T> ) -> BalanceOf<T> {
+        let pending: BalanceOf<T> = PendingShares::<T>::get( key );
+        let amount: BalanceOf<T> = pending.saturating_add( share );
+        match Self::mint_to_account( key, amount ) {
+            Ok(()) => {
+                PendingShares::<T>::remove( key );
+                TotalPendingShares::<T>::mutate( |total| *total = total.saturating_sub( pending ) );
+                return amount;
+            }
+            Err(error) => {
+                log::debug!("keeping emission share( key:{:?}, amount:{:?} ): {:?}", key, amount, error );
+                PendingShares::<T>::insert( key, amount );
+                TotalPendingShares::<T>::mutate( |total| *total = total.saturating_add( share ) );
+                return Zero::zero();
+            }
+        }
+    }
+
+    pub fn get_share_of( amount: BalanceOf<T>, share: u16 ) -> BalanceOf<T> {
+        return Percent::from_percent( share.min( 100 ) as u8 ).mul_floor( amount );
+    }
+
+
+    // Transfers an accepted spend out of the treasury account.
+    pub fn spend_treasury( dest: &T::AccountId, amount: BalanceOf<T> ) -> DispatchResult {
+        T::Currency::transfer( &Self::get_treasury_account(), dest, amount, ExistenceRequirement::AllowDeath )?;
+        log::info!("TreasurySpent( dest:{:?}, amount:{:?} )", dest, amount );
+        Self::deposit_event( Event::TreasurySpent( dest.clone(), amount ) );
+        Ok(())
+    }
+
+    pub fn get_founder_share( netuid: u16 ) -> u16 { FounderShare::<T>::get( netuid ) }
+    pub fn get_pending_share( key: &T::AccountId ) -> BalanceOf<T> { PendingShares::<T>::get( key ) }
+    pub fn get_total_pending_shares() -> BalanceOf<T> { TotalPendingShares::<T>::get() }
+    pub fn get_treasury_share() -> u16 { TreasuryShare::<T>::get() }
+    pub fn set_treasury_share( treasury_share: u16 ) { TreasuryShare::<T>::put( treasury_share ) }
+
+}
@@ -0,0 +1,64 @@
+use super::*;
+use crate::math::*;
+use frame_support::pallet_prelude::DispatchResult;
+
+// Vesting balance is held by its own lock, which overlaps the stake lock, so vesting balance can
+// still be staked but neither it nor the stake backed by it can leave the account before it unlocks.
+pub const VESTING_LOCK_ID: LockIdentifier = *b"subvest ";
+
+impl<T: Config> Pallet<T> {
+
+
+    pub fn do_vest(
+        origin: T::RuntimeOrigin,
+    ) -> DispatchResult {
+        // --- 1. Check the caller's signature.
+        let key = ensure_signed( origin )?;
+
+        // --- 2. Shrink the lock to what is still vesting.
+        let schedule = Vesting::<T>::get( &key ).ok_or( Error::<T>::NotVesting )?;
+        let still_locked: BalanceOf<T> = Self::get_vesting_locked( &schedule, Self::get_current_block_as_u64() );
+        Self::update_vesting_lock( &key, still_locked );
+
+        // --- 3. Emit the event.
+        log::info!("Vested( key:{:?}, still_locked:{:?} )", key, still_locked );
+        Self::deposit_event( Event::Vested( key, still_locked ) );
+        Ok(())
+    }
+
+
+    // Stores the schedule of the key and locks what has not vested yet.
+    pub fn add_vesting_schedule( key: &T::AccountId, schedule: VestingSchedule<BalanceOf<T>> ) {
+        let still_locked: BalanceOf<T> = Self::get_vesting_locked( &schedule, Self::get_current_block_as_u64() );
+        Vesting::<T>::insert( key, schedule );
+        Self::update_vesting_lock( key, still_locked );
+    }
+
+
+    // Returns the part of the schedule which is still locked at the block. Nothing unlocks before the cliff,
+    // afterwards the locked balance unlocks linearly over the duration.
+    pub fn get_vesting_locked( schedule: &VestingSchedule<BalanceOf<T>>, block_number: u64 ) -> BalanceOf<T> {
+        let cliff_end: u64 = schedule.start.saturating_add( schedule.cliff );
+        if block_number < cliff_end {
+            return schedule.locked;
+        }
+        let elapsed: u64 = block_number - cliff_end;
+        if elapsed >= schedule.duration {
+            return Zero::zero();
+        }
+        let unlocked: BalanceOf<T> = balance_share( schedule.locked, balance_proportion( elapsed, schedule.duration ) );
+        return schedule.locked.saturating_sub( unlocked );
+    }
+
+
+    // Sets the vesting lock of the key, dropping the lock and its schedule once everything has vested.
+    pub fn update_vesting_lock( key: &T::AccountId, still_locked: BalanceOf<T> ) {
+        if still_locked.is_zero() {
+            Vesting::<T>::remove( key );
+            T::Currency::remove_lock( VESTING_LOCK_ID, key );
+        } else {
+            T::Currency::set_lock( VESTING_LOCK_ID, key, still_locked, WithdrawReasons::all() );
+        }
+    }
+
+}
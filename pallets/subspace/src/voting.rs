@@ -0,0 +1,179 @@
+use super::*;
+use crate::math::*;
+use frame_system::ensure_root;
+use frame_support::pallet_prelude::DispatchResult;
+use frame_support::weights::Weight;
+use frame_support::sp_std::vec;
+use sp_std::vec::Vec;
+use substrate_fixed::types::{I32F32, I64F64};
+
+impl<T: Config> Pallet<T> {
+
+
+    pub fn do_vote_subnet_weights(
+        origin: T::RuntimeOrigin,
+        netuids: Vec<u16>,
+        weights: Vec<u16>,
+    ) -> DispatchResult {
+        // --- 1. Check the caller's signature.
+        let key = ensure_signed( origin )?;
+
+        // --- 2. Votes are weighted by the stake of the key, so it needs some.
+        ensure!( !Self::get_total_stake_for_key( &key ).is_zero(), Error::<T>::NotEnoughStakeToVote );
+
+        // --- 3. Check the vote is well formed and only points at existing subnets.
+        ensure!( Self::uids_match_values( &netuids, &weights ), Error::<T>::WeightVecNotEqualSize );
+        ensure!( !Self::has_duplicate_uids( &netuids ), Error::<T>::DuplicateUids );
+        for netuid in netuids.iter() {
+            ensure!( Self::if_subnet_exist( *netuid ), Error::<T>::NetworkDoesNotExist );
+        }
+
+        // --- 4. Store the normalized vote, an empty vote withdraws it. The shares are recomputed
+        // over every vote, so the number of keys with a vote is bounded.
+        if netuids.is_empty() {
+            SubnetVotes::<T>::remove( &key );
+        } else {
+            let normalized_weights: Vec<u16> = Self::normalize_weights( weights );
+            let vote: BoundedWeights<T> = netuids.iter().copied().zip( normalized_weights ).collect::<Vec<(u16, u16)>>()
+                .try_into().map_err( |_| Error::<T>::TooManyUids )?;
+            ensure!( SubnetVotes::<T>::contains_key( &key ) || SubnetVotes::<T>::count() < MaxSubnetVoters::<T>::get(), Error::<T>::TooManySubnetVoters );
+            SubnetVotes::<T>::insert( &key, vote );
+        }
+
+        log::info!("SubnetWeightsVoted( key:{:?}, subnets:{:?} )", key, netuids.len() );
+        Self::deposit_event( Event::SubnetWeightsVoted( key, netuids.len() as u16 ) );
+        Ok(())
+    }
+
+
+    pub fn do_set_subnet_emission_mode(
+        origin: T::RuntimeOrigin,
+        mode: SubnetEmissionMode,
+        majority: u16,
+    ) -> DispatchResult {
+        ensure_root( origin )?;
+        ensure!( majority <= 100, Error::<T>::StorageValueOutOfRange );
+        EmissionMode::<T>::put( mode.clone() );
+        SubnetVoteMajority::<T>::put( majority );
+        let _ = SubnetVoteShare::<T>::clear( u32::max_value(), None );
+
+        // --- Vote mode starts from the current votes instead of waiting for the next recomputation.
+        if mode == SubnetEmissionMode::Vote {
+            Self::update_subnet_vote_shares();
+        }
+
+        log::info!("SubnetEmissionModeSet( mode:{:?}, majority:{:?} )", mode, majority );
+        Self::deposit_event( Event::SubnetEmissionModeSet( mode, majority ) );
+        Ok(())
+    }
+
+
+    // Recomputes the shares in vote mode every SubnetVoteInterval blocks and returns the weight of it.
+    pub fn update_subnet_vote_shares_at( block_number: u64 ) -> Weight {
+        if EmissionMode::<T>::get() != SubnetEmissionMode::Vote || block_number % SubnetVoteInterval::<T>::get().max( 1 ) != 0 {
+            return T::DbWeight::get().reads( 2 );
+        }
+        return Self::update_subnet_vote_shares();
+    }
+
+
+    // Recomputes the share of every subnet from the stake weighted votes and returns the weight of it.
+    // When a majority is set each vote is first clipped to the stake weighted median of that subnet,
+    // so a single large stake can not pull emission towards a subnet the rest of the stakers do not support.
+    // The clip is not linear in the votes, so the shares are recomputed over the bounded set of votes
+    // rather than updated as single votes change.
+    pub fn update_subnet_vote_shares() -> Weight {
+        let cleared: u32 = SubnetVoteShare::<T>::clear( u32::max_value(), None ).unique;
+        let subnets: u64 = N::<T>::iter_keys().count() as u64;
+        let voters: u64 = SubnetVotes::<T>::count() as u64;
+        let weight: Weight = Weight::from_ref_time( 1_000_000 * voters * subnets )
+            .saturating_add( T::DbWeight::get().reads( 2 + subnets + 2 * voters ) )
+            .saturating_add( T::DbWeight::get().writes( cleared as u64 + subnets ) );
+
+        let columns: u16 = match N::<T>::iter_keys().max() {
+            Some( max_netuid ) => max_netuid.saturating_add( 1 ),
+            None => return weight,
+        };
+
+        // --- 1. Collect the votes of staked keys on existing subnets.
+        let mut stake_64: Vec<I64F64> = vec![];
+        let mut votes: Vec<Vec<(u16, I32F32)>> = vec![];
+        for ( key, vote ) in SubnetVotes::<T>::iter() {
+            let key_stake: BalanceOf<T> = Self::get_total_stake_for_key( &key );
+            if key_stake.is_zero() {
+                continue;
+            }
+            let row: Vec<(u16, I32F32)> = vote.iter()
+                .filter( |(netuid, _)| Self::if_subnet_exist( *netuid ) )
+                .map( |(netuid, weight)| ( *netuid, I32F32::from_num( *weight ) ) )
+                .collect();
+            stake_64.push( balance_proportion( key_stake, Self::get_total_stake() ) );
+            votes.push( row );
+        }
+        inplace_normalize_64( &mut stake_64 );
+        let stake: Vec<I32F32> = vec_fixed64_to_fixed32( stake_64 );
+        inplace_row_normalize_sparse( &mut votes );
+
+        // --- 2. Clip every vote to the stake weighted consensus of its subnet.
+        let majority: u16 = SubnetVoteMajority::<T>::get();
+        if majority > 0 {
+            let consensus: Vec<I32F32> = weighted_median_col_sparse( &stake, &votes, columns, I32F32::from_num( majority ) / I32F32::from_num( 100 ) );
+            votes = col_clip_sparse( &votes, &consensus );
+        }
+
+        // --- 3. Subnet shares are the stake weighted sum of the votes.
+        let mut shares: Vec<I32F32> = matmul_sparse( &votes, &stake, columns );
+        inplace_normalize( &mut shares );
+        for ( netuid, share ) in shares.iter().enumerate() {
+            if *share > I32F32::from_num( 0 ) {
+                SubnetVoteShare::<T>::insert( netuid as u16, fixed_proportion_to_u16( *share ) );
+            }
+        }
+        return weight;
+    }
+
+
+    // Returns the fraction of the block emission given to the subnet under the current emission mode.
+    // Vote mode falls back to the stake share while nobody has cast a vote that counts.
+    pub fn get_subnet_emission_ratio( netuid: u16 ) -> I64F64 {
+        if EmissionMode::<T>::get() == SubnetEmissionMode::Vote && SubnetVoteShare::<T>::iter().next().is_some() {
+            return I64F64::from_num( SubnetVoteShare::<T>::get( netuid ) ) / I64F64::from_num( u16::MAX );
+        }
+
+        let total_stake: BalanceOf<T> = Self::get_total_stake();
+        if !total_stake.is_zero() {
+            return balance_proportion( Self::get_total_subnet_stake( netuid ), total_stake );
+        }
+        let n: u16 = TotalSubnets::<T>::get();
+        if n > 1 {
+            return I64F64::from_num( 1 ) / I64F64::from_num( n );
+        }
+        return I64F64::from_num( 1 );
+    }
+
+    // Drops the removed subnet from every vote, a vote left empty is withdrawn, so a netuid handed to a new
+    // network starts without votes. At most MaxSubnetVoters votes are read.
+    pub fn remove_subnet_from_votes( netuid: u16 ) {
+        let voters: Vec<T::AccountId> = SubnetVotes::<T>::iter()
+            .filter( |( _, vote )| vote.iter().any( |( voted_netuid, _ )| *voted_netuid == netuid ) )
+            .map( |( key, _ )| key )
+            .collect();
+        for key in voters.iter() {
+            let mut vote: BoundedWeights<T> = SubnetVotes::<T>::get( key );
+            vote.retain( |( voted_netuid, _ )| *voted_netuid != netuid );
+            if vote.is_empty() {
+                SubnetVotes::<T>::remove( key );
+            } else {
+                SubnetVotes::<T>::insert( key, vote );
+            }
+        }
+    }
+
+
+    pub fn get_emission_mode() -> SubnetEmissionMode { EmissionMode::<T>::get() }
+    pub fn get_subnet_vote_share( netuid: u16 ) -> u16 { SubnetVoteShare::<T>::get( netuid ) }
+    pub fn get_subnet_votes( key: &T::AccountId ) -> Vec<(u16, u16)> { SubnetVotes::<T>::get( key ).into_inner() }
+    pub fn set_subnet_vote_interval( interval: u64 ) { SubnetVoteInterval::<T>::put( interval ) }
+    pub fn set_max_subnet_voters( max_voters: u32 ) { MaxSubnetVoters::<T>::put( max_voters ) }
+
+}
@@ -0,0 +1,89 @@
+
+//! Weights for pallet_subspace, in the layout of the substrate frame weight template.
+//!
+//! The values are hand written estimates, they have not been measured. Replace them with the output
+//! of the benchmarks in benchmarks.rs, run on reference hardware with:
+//!
+//! ./target/release/node-subspace benchmark pallet --chain=dev --execution=wasm --wasm-execution=compiled
+//!     --pallet=pallet_subspace --extrinsic=* --steps=50 --repeat=20 --output=pallets/subspace/src/weight_info.rs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_subspace.
+pub trait WeightInfo {
+	fn set_weights(w: u32, ) -> Weight;
+	fn register() -> Weight;
+	fn add_stake() -> Weight;
+	fn remove_stake() -> Weight;
+	fn epoch(n: u32, ) -> Weight;
+}
+
+/// Estimated weights for pallet_subspace, not yet benchmarked.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// The range of component `w` is `[1, 4095]`.
+	fn set_weights(w: u32, ) -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(Weight::from_ref_time(200_000 as u64).saturating_mul(w as u64))
+			.saturating_add(T::DbWeight::get().reads(9 as u64))
+			.saturating_add(T::DbWeight::get().writes(2 as u64))
+	}
+	fn register() -> Weight {
+		Weight::from_ref_time(91_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(27 as u64))
+			.saturating_add(T::DbWeight::get().writes(22 as u64))
+	}
+	fn add_stake() -> Weight {
+		Weight::from_ref_time(65_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(8 as u64))
+			.saturating_add(T::DbWeight::get().writes(6 as u64))
+	}
+	fn remove_stake() -> Weight {
+		Weight::from_ref_time(66_000_000 as u64)
+			.saturating_add(T::DbWeight::get().reads(8 as u64))
+			.saturating_add(T::DbWeight::get().writes(6 as u64))
+	}
+	/// The range of component `n` is `[1, 4096]`.
+	fn epoch(n: u32, ) -> Weight {
+		Weight::from_ref_time(27_000_000 as u64).saturating_mul(n as u64)
+			.saturating_add(T::DbWeight::get().reads((2 as u64).saturating_mul(n as u64)))
+			.saturating_add(T::DbWeight::get().writes((3 as u64).saturating_mul(n as u64)))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// The range of component `w` is `[1, 4095]`.
+	fn set_weights(w: u32, ) -> Weight {
+		Weight::from_ref_time(20_000_000 as u64)
+			.saturating_add(Weight::from_ref_time(200_000 as u64).saturating_mul(w as u64))
+			.saturating_add(RocksDbWeight::get().reads(9 as u64))
+			.saturating_add(RocksDbWeight::get().writes(2 as u64))
+	}
+	fn register() -> Weight {
+		Weight::from_ref_time(91_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(27 as u64))
+			.saturating_add(RocksDbWeight::get().writes(22 as u64))
+	}
+	fn add_stake() -> Weight {
+		Weight::from_ref_time(65_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(8 as u64))
+			.saturating_add(RocksDbWeight::get().writes(6 as u64))
+	}
+	fn remove_stake() -> Weight {
+		Weight::from_ref_time(66_000_000 as u64)
+			.saturating_add(RocksDbWeight::get().reads(8 as u64))
+			.saturating_add(RocksDbWeight::get().writes(6 as u64))
+	}
+	/// The range of component `n` is `[1, 4096]`.
+	fn epoch(n: u32, ) -> Weight {
+		Weight::from_ref_time(27_000_000 as u64).saturating_mul(n as u64)
+			.saturating_add(RocksDbWeight::get().reads((2 as u64).saturating_mul(n as u64)))
+			.saturating_add(RocksDbWeight::get().writes((3 as u64).saturating_mul(n as u64)))
+	}
+}
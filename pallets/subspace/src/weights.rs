@@ -1,6 +1,7 @@
 use super::*;
 use frame_support::sp_std::vec;
 use sp_std::vec::Vec;
+use sp_runtime::traits::Hash;
 
 impl<T: Config> Pallet<T> {
 
@@ -10,13 +11,43 @@ impl<T: Config> Pallet<T> {
         // --- 1. Check the caller's signature. This is the key of a registered account.
         let key = ensure_signed( origin )?;
 
-        let stake: u64 = Self::get_stake_for_key( netuid, &key );
+        // --- 2. Subnets using commit-reveal only accept revealed weights.
+        ensure!( !CommitRevealWeights::<T>::get( netuid ), Error::<T>::CommitRevealEnabled );
 
-        ensure!( stake > 0, Error::<T>::NotEnoughStaketoSetWeights );
+        Self::set_weights_for_key( netuid, &key, uids, values )
+    }
+
+
+    pub fn do_set_weights_multi( origin: T::RuntimeOrigin, subnet_weights: Vec<(u16, Vec<u16>, Vec<u16>)> ) -> dispatch::DispatchResult {
+
+        // --- 1. Check the caller's signature. This is the key of a registered account.
+        let key = ensure_signed( origin )?;
+
+        // --- 2. Every subnet is set at most once, so the order of the entries does not matter.
+        let netuids: Vec<u16> = subnet_weights.iter().map( |( netuid, _, _ )| *netuid ).collect();
+        ensure!( !Self::has_duplicate_uids( &netuids ), Error::<T>::DuplicateSubnets );
+
+        // --- 3. The weights of every subnet go through the same checks as set_weights, one failing entry reverts all of them.
+        frame_support::storage::with_storage_layer( || {
+            for ( netuid, uids, values ) in subnet_weights {
+                ensure!( !CommitRevealWeights::<T>::get( netuid ), Error::<T>::CommitRevealEnabled );
+                Self::set_weights_for_key( netuid, &key, uids, values )?;
+            }
+            Ok(())
+        })
+    }
+
+
+    // Checks and stores the weights of the module of the key on the subnet.
+    pub fn set_weights_for_key( netuid: u16, key: &T::AccountId, uids: Vec<u16>, values: Vec<u16> ) -> dispatch::DispatchResult {
+
+        let stake: BalanceOf<T> = Self::get_stake_for_key( netuid, key );
+
+        ensure!( !stake.is_zero(), Error::<T>::NotEnoughStaketoSetWeights );
         // --- 2. Check to see if this is a valid network.
         ensure!( Self::if_subnet_exist( netuid ), Error::<T>::NetworkDoesNotExist );
         // --- 5. Check to see if the key is registered to the passed network.
-        ensure!( Self::is_key_registered_on_network( netuid, &key ), Error::<T>::NotRegistered );
+        ensure!( Self::is_key_registered_on_network( netuid, key ), Error::<T>::NotRegistered );
         
 
         // --- 3. Check that the length of uid list and value list are equal for this network.
@@ -25,7 +56,7 @@ impl<T: Config> Pallet<T> {
         // --- 4. Check to see if the number of uids is within the max allowed uids for this network.
         // --- 7. Get the module uid of associated key on network netuid.
         
-        let uid : u16 =   Self::get_uid_for_key( netuid, &key );
+        let uid : u16 =   Self::get_uid_for_key( netuid, key );
 
         // --- 8. Ensure the uid is not setting weights faster than the weights_set_rate_limit.
         let current_block: u64 = Self::get_current_block_as_u64();
@@ -52,6 +83,7 @@ impl<T: Config> Pallet<T> {
         for ( uid, val ) in uids.iter().zip(normalized_values.iter()) { zipped_weights.push((*uid, *val)) }
 
         // --- 16. Set weights under netuid, uid double map entry.
+        let zipped_weights: BoundedWeights<T> = zipped_weights.try_into().map_err( |_| Error::<T>::TooManyUids )?;
         Weights::<T>::insert( netuid, uid, zipped_weights );
 
         // --- 17. Set the activity for the weights on this network.
@@ -67,6 +99,127 @@ impl<T: Config> Pallet<T> {
 
 
 
+    pub fn do_set_commit_reveal_weights( origin: T::RuntimeOrigin, netuid: u16, enabled: bool ) -> dispatch::DispatchResult {
+        let key = ensure_signed( origin )?;
+
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( Self::is_subnet_founder( netuid, &key ), Error::<T>::NotSubnetFounder );
+
+        if enabled {
+            CommitRevealWeights::<T>::insert( netuid, true );
+        } else {
+            CommitRevealWeights::<T>::remove( netuid );
+            let _ = WeightCommits::<T>::clear_prefix( netuid, u32::max_value(), None );
+        }
+
+        log::info!("CommitRevealWeightsSet( netuid:{:?}, enabled:{:?} )", netuid, enabled );
+        Self::deposit_event( Event::CommitRevealWeightsSet( netuid, enabled ) );
+        Ok(())
+    }
+
+
+    pub fn do_set_weight_copy_detection( origin: T::RuntimeOrigin, netuid: u16, threshold: u16, penalty: u16 ) -> dispatch::DispatchResult {
+        let key = ensure_signed( origin )?;
+
+        ensure!( Self::if_subnet_netuid_exists( netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( Self::is_subnet_founder( netuid, &key ), Error::<T>::NotSubnetFounder );
+        ensure!( threshold <= 100 && penalty <= 100, Error::<T>::StorageValueOutOfRange );
+
+        WeightCopyThreshold::<T>::insert( netuid, threshold );
+        WeightCopyPenalty::<T>::insert( netuid, penalty );
+        if threshold == 0 {
+            let _ = PreviousWeights::<T>::clear_prefix( netuid, u32::max_value(), None );
+        }
+
+        log::info!("WeightCopyDetectionSet( netuid:{:?}, threshold:{:?}, penalty:{:?} )", netuid, threshold, penalty );
+        Self::deposit_event( Event::WeightCopyDetectionSet( netuid, threshold, penalty ) );
+        Ok(())
+    }
+
+
+    pub fn do_commit_weights( origin: T::RuntimeOrigin, netuid: u16, commit_hash: T::Hash ) -> dispatch::DispatchResult {
+
+        // --- 1. Check the caller's signature. This is the key of a registered account.
+        let key = ensure_signed( origin )?;
+
+        // --- 2. Only modules with stake on a commit-reveal subnet can commit.
+        ensure!( Self::if_subnet_exist( netuid ), Error::<T>::NetworkDoesNotExist );
+        ensure!( CommitRevealWeights::<T>::get( netuid ), Error::<T>::CommitRevealDisabled );
+        ensure!( Self::is_key_registered_on_network( netuid, &key ), Error::<T>::NotRegistered );
+        ensure!( !Self::get_stake_for_key( netuid, &key ).is_zero(), Error::<T>::NotEnoughStaketoSetWeights );
+
+        // --- 3. A new commit replaces the pending one of the module.
+        let uid: u16 = Self::get_uid_for_key( netuid, &key );
+        WeightCommits::<T>::insert( netuid, uid, ( commit_hash, Self::get_current_block_as_u64() ) );
+
+        // --- 4. Emit the tracking event.
+        log::info!("WeightsCommitted( netuid:{:?}, uid:{:?} )", netuid, uid );
+        Self::deposit_event( Event::WeightsCommitted( netuid, uid ) );
+        Ok(())
+    }
+
+
+    pub fn do_reveal_weights( origin: T::RuntimeOrigin, netuid: u16, uids: Vec<u16>, values: Vec<u16>, salt: Vec<u8> ) -> dispatch::DispatchResult {
+
+        // --- 1. Check the caller's signature. This is the key of a registered account.
+        let key = ensure_signed( origin )?;
+        ensure!( Self::is_key_registered_on_network( netuid, &key ), Error::<T>::NotRegistered );
+        let uid: u16 = Self::get_uid_for_key( netuid, &key );
+
+        // --- 2. The commit is revealed in the epoch after the one it was made in. Weights copied from a reveal
+        // can only be revealed an epoch later, once the epoch using the original weights has run.
+        let ( commit_hash, commit_block ) = WeightCommits::<T>::get( netuid, uid ).ok_or( Error::<T>::NoWeightCommit )?;
+        let current_block: u64 = Self::get_current_block_as_u64();
+        let reveal_block: u64 = Self::get_weight_reveal_block( netuid, commit_block );
+        ensure!( current_block >= reveal_block, Error::<T>::WeightRevealTooEarly );
+        ensure!( current_block < reveal_block.saturating_add( Self::get_weight_reveal_period( netuid ) ), Error::<T>::WeightCommitExpired );
+
+        // --- 3. The revealed weights have to hash to the commit.
+        // The hash binds the key and the subnet, so a commit copied from another module can not be revealed.
+        ensure!( Self::hash_weights( &key, netuid, &uids, &values, &salt ) == commit_hash, Error::<T>::InvalidWeightReveal );
+
+        // --- 4. Set the weights as if they were set directly.
+        Self::set_weights_for_key( netuid, &key, uids, values )?;
+        WeightCommits::<T>::remove( netuid, uid );
+
+        // --- 5. Emit the tracking event.
+        log::info!("WeightsRevealed( netuid:{:?}, uid:{:?} )", netuid, uid );
+        Self::deposit_event( Event::WeightsRevealed( netuid, uid ) );
+        Ok(())
+    }
+
+
+    // Returns the hash the key commits to on the subnet before revealing its weights.
+    pub fn hash_weights( key: &T::AccountId, netuid: u16, uids: &Vec<u16>, values: &Vec<u16>, salt: &Vec<u8> ) -> T::Hash {
+        return T::Hashing::hash_of( &( key, netuid, uids, values, salt ) );
+    }
+
+    // Returns the first block in which a commit made at the block can be revealed, the next epoch block of the subnet.
+    pub fn get_weight_reveal_block( netuid: u16, commit_block: u64 ) -> u64 {
+        let next_block: u64 = commit_block.saturating_add( 1 );
+        return next_block.saturating_add( Self::blocks_until_next_epoch( netuid, Self::get_tempo( netuid ), next_block ) );
+    }
+
+    // Number of blocks from the reveal block in which a commit can be revealed, one tempo of the subnet.
+    pub fn get_weight_reveal_period( netuid: u16 ) -> u64 {
+        return Self::get_tempo( netuid ) as u64;
+    }
+
+    // Drops the commits of the subnet which can no longer be revealed.
+    pub fn expire_weight_commits( netuid: u16, current_block: u64 ) {
+        let reveal_period: u64 = Self::get_weight_reveal_period( netuid );
+        let expired: Vec<u16> = WeightCommits::<T>::iter_prefix( netuid )
+            .filter( |( _, ( _, commit_block ) )| Self::get_weight_reveal_block( netuid, *commit_block ).saturating_add( reveal_period ) <= current_block )
+            .map( |( uid, _ )| uid )
+            .collect();
+        for uid in expired {
+            WeightCommits::<T>::remove( netuid, uid );
+            log::info!("WeightCommitExpired( netuid:{:?}, uid:{:?} )", netuid, uid );
+            Self::deposit_event( Event::WeightCommitExpired( netuid, uid ) );
+        }
+    }
+
+
     // Checks for any invalid uids on this network.
     pub fn contains_invalid_uids( netuid: u16, uids: &Vec<u16> ) -> bool {
         for uid in uids {
@@ -78,12 +231,12 @@ impl<T: Config> Pallet<T> {
     }
 
     // Returns true if the passed uids have the same length of the passed values.
-    fn uids_match_values(uids: &Vec<u16>, values: &Vec<u16>) -> bool {
+    pub fn uids_match_values(uids: &Vec<u16>, values: &Vec<u16>) -> bool {
         return uids.len() == values.len();
     }
 
     // Returns true if the items contain duplicates.
-    fn has_duplicate_uids(items: &Vec<u16>) -> bool {
+    pub fn has_duplicate_uids(items: &Vec<u16>) -> bool {
         let mut parsed: Vec<u16> = Vec::new();
         for item in items {
             if parsed.contains(&item) { return true; }
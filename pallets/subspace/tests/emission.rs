@@ -0,0 +1,89 @@
+mod mock;
+use mock::*;
+use pallet_subspace::{Error, EmissionSchedule};
+use frame_support::{assert_ok, assert_noop};
+use sp_core::U256;
+
+#[test]
+fn test_stake_halving_does_not_revert_on_unstake() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        let key = U256::from(0);
+        register_module(netuid, key, 1_000_000_000);
+        let unit_emission: u64 = SubspaceModule::get_total_emission_per_block();
+
+        // cross the first checkpoint of the default schedule
+        let checkpoint_stake: u64 = 2_000_000 * 1_000_000_000;
+        assert_ok!(SubspaceModule::increase_stake_on_account(netuid, &key, checkpoint_stake));
+        step_block(1);
+        assert_eq!(SubspaceModule::get_total_emission_per_block(), unit_emission / 2);
+
+        // dropping back below the checkpoint keeps the halving
+        SubspaceModule::decrease_stake_on_account(netuid, &key, checkpoint_stake);
+        step_block(1);
+        assert_eq!(SubspaceModule::get_total_emission_per_block(), unit_emission / 2);
+    });
+}
+
+#[test]
+fn test_block_halving_schedule() {
+    new_test_ext().execute_with(|| {
+        register_module(0, U256::from(0), 1_000_000_000);
+        let unit_emission: u64 = SubspaceModule::get_total_emission_per_block();
+        assert_ok!(SubspaceModule::set_emission_schedule(
+            RuntimeOrigin::root(),
+            EmissionSchedule::BlockHalving { interval: 10 },
+            u64::MAX
+        ));
+
+        assert_eq!(SubspaceModule::get_emission_per_block_at(9), unit_emission);
+        assert_eq!(SubspaceModule::get_emission_per_block_at(10), unit_emission / 2);
+        assert_eq!(SubspaceModule::get_emission_per_block_at(25), unit_emission / 4);
+        assert_eq!(SubspaceModule::get_projected_emission(20), 10 * unit_emission + 10 * (unit_emission / 2));
+
+        run_to_block(10);
+        assert_eq!(SubspaceModule::get_total_emission_per_block(), unit_emission / 2);
+    });
+}
+
+#[test]
+fn test_max_supply_caps_emission() {
+    new_test_ext().execute_with(|| {
+        register_module(0, U256::from(0), 1_000_000_000);
+        let unit_emission: u64 = SubspaceModule::get_total_emission_per_block();
+        let max_supply: u64 = SubspaceModule::get_total_supply() + 2 * unit_emission + unit_emission / 2;
+        assert_ok!(SubspaceModule::set_emission_schedule(
+            RuntimeOrigin::root(),
+            EmissionSchedule::Constant,
+            max_supply
+        ));
+        assert_eq!(SubspaceModule::get_projected_emission(10), 2 * unit_emission + unit_emission / 2);
+
+        step_block(5);
+        assert_eq!(SubspaceModule::get_total_supply(), max_supply);
+        assert_eq!(SubspaceModule::get_total_emission_per_block(), 0);
+        assert_eq!(SubspaceModule::get_projected_emission(10), 0);
+    });
+}
+
+#[test]
+fn test_invalid_emission_schedule() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            SubspaceModule::set_emission_schedule(RuntimeOrigin::root(), EmissionSchedule::BlockHalving { interval: 0 }, u64::MAX),
+            Error::<Test>::InvalidEmissionSchedule
+        );
+        assert_noop!(
+            SubspaceModule::set_emission_schedule(
+                RuntimeOrigin::root(),
+                EmissionSchedule::StakeHalving { checkpoints: vec![10, 5].try_into().unwrap() },
+                u64::MAX
+            ),
+            Error::<Test>::InvalidEmissionSchedule
+        );
+        assert_noop!(
+            SubspaceModule::set_emission_schedule(get_origin(U256::from(0)), EmissionSchedule::Constant, u64::MAX),
+            sp_runtime::DispatchError::BadOrigin
+        );
+    });
+}
@@ -0,0 +1,196 @@
+mod mock;
+use mock::*;
+use pallet_subspace::{Error, GlobalParams, SubnetParams};
+use frame_support::{assert_ok, assert_noop, traits::Currency};
+use sp_core::U256;
+
+fn new_global_params() -> GlobalParams {
+    GlobalParams {
+        max_allowed_subnets: 50,
+        unit_emission: 2_000_000_000,
+        subnet_defaults: SubnetParams {
+            tempo: 10,
+            immunity_period: 200,
+            min_allowed_weights: 2,
+            max_allowed_weights: 100,
+            max_allowed_uids: 1000,
+        },
+        treasury_share: 0,
+    }
+}
+
+#[test]
+fn test_global_proposal_accepted() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        let key = U256::from(0);
+        register_module(netuid, key, 1_000_000_000);
+        add_balance(key, SubspaceModule::get_proposal_cost());
+        SubspaceModule::set_proposal_voting_period(10);
+
+        let params = new_global_params();
+        assert_ok!(SubspaceModule::add_global_proposal(get_origin(key), params.clone()));
+        assert_ok!(SubspaceModule::vote_proposal(get_origin(key), 0, true));
+
+        step_block(9);
+        assert_ne!(SubspaceModule::get_global_params(), params);
+        step_block(1);
+        assert_eq!(SubspaceModule::get_global_params(), params);
+        assert_eq!(SubspaceModule::get_max_allowed_subnets(), 50);
+
+        // new subnets pick up the governed defaults
+        register_module(1, U256::from(1), 1_000_000_000);
+        assert_eq!(SubspaceModule::get_tempo(1), 10);
+        assert_eq!(SubspaceModule::get_immunity_period(1), 200);
+        assert_eq!(SubspaceModule::get_max_allowed_uids(1), 1000);
+    });
+}
+
+#[test]
+fn test_global_proposal_rejected_without_quorum() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        let whale = U256::from(0);
+        let minnow = U256::from(1);
+        register_module(netuid, whale, 10_000_000_000);
+        register_module(netuid, minnow, 1_000_000_000);
+        add_balance(minnow, SubspaceModule::get_proposal_cost());
+        SubspaceModule::set_proposal_voting_period(5);
+
+        let params_before = SubspaceModule::get_global_params();
+        assert_ok!(SubspaceModule::add_global_proposal(get_origin(minnow), new_global_params()));
+        assert_ok!(SubspaceModule::vote_proposal(get_origin(minnow), 0, true));
+
+        step_block(5);
+        assert_eq!(SubspaceModule::get_global_params(), params_before);
+        assert_noop!(
+            SubspaceModule::vote_proposal(get_origin(whale), 0, true),
+            Error::<Test>::ProposalDoesNotExist
+        );
+    });
+}
+
+#[test]
+fn test_global_proposal_majority_against() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        let whale = U256::from(0);
+        let minnow = U256::from(1);
+        register_module(netuid, whale, 10_000_000_000);
+        register_module(netuid, minnow, 1_000_000_000);
+        add_balance(minnow, SubspaceModule::get_proposal_cost());
+        SubspaceModule::set_proposal_voting_period(5);
+
+        let params_before = SubspaceModule::get_global_params();
+        assert_ok!(SubspaceModule::add_global_proposal(get_origin(minnow), new_global_params()));
+        assert_ok!(SubspaceModule::vote_proposal(get_origin(minnow), 0, true));
+        assert_ok!(SubspaceModule::vote_proposal(get_origin(whale), 0, false));
+
+        step_block(5);
+        assert_eq!(SubspaceModule::get_global_params(), params_before);
+    });
+}
+
+#[test]
+fn test_global_proposal_checks() {
+    new_test_ext().execute_with(|| {
+        let key = U256::from(0);
+        let stranger = U256::from(1);
+        register_module(0, key, 1_000_000_000);
+        add_balance(key, SubspaceModule::get_proposal_cost());
+
+        assert_noop!(
+            SubspaceModule::add_global_proposal(get_origin(stranger), new_global_params()),
+            Error::<Test>::NotEnoughStakeToPropose
+        );
+
+        let mut params = new_global_params();
+        params.subnet_defaults.min_allowed_weights = 200;
+        assert_noop!(
+            SubspaceModule::add_global_proposal(get_origin(key), params),
+            Error::<Test>::InvalidGlobalParams
+        );
+
+        assert_ok!(SubspaceModule::add_global_proposal(get_origin(key), new_global_params()));
+        assert_noop!(
+            SubspaceModule::vote_proposal(get_origin(stranger), 0, true),
+            Error::<Test>::NotEnoughStakeToVote
+        );
+    });
+}
+
+#[test]
+fn test_proposals_are_bounded_and_paid() {
+    new_test_ext().execute_with(|| {
+        use pallet_subspace::{Proposals, ProposalsPerKey};
+        let key = U256::from(0);
+        let other = U256::from(1);
+        let cost: u64 = 1_000;
+        register_module(0, key, 1_000_000_000);
+        register_module(0, other, 1_000_000_000);
+        SubspaceModule::set_proposal_cost(cost);
+        SubspaceModule::set_max_proposals(3);
+        SubspaceModule::set_max_proposals_per_key(2);
+        SubspaceModule::set_proposal_voting_period(5);
+
+        // the cost is burned from the free balance, staked balance can not pay it.
+        assert_noop!(
+            SubspaceModule::add_global_proposal(get_origin(key), new_global_params()),
+            Error::<Test>::NotEnoughBalanceToPropose
+        );
+        add_balance(key, 3 * cost);
+        add_balance(other, 2 * cost);
+        let issuance: u64 = Balances::total_issuance();
+        assert_ok!(SubspaceModule::add_global_proposal(get_origin(key), new_global_params()));
+        assert_ok!(SubspaceModule::add_global_proposal(get_origin(key), new_global_params()));
+        assert_eq!(Balances::total_issuance(), issuance - 2 * cost);
+        assert_eq!(SubspaceModule::get_balance(&key), cost);
+
+        assert_noop!(
+            SubspaceModule::add_global_proposal(get_origin(key), new_global_params()),
+            Error::<Test>::TooManyProposalsForKey
+        );
+        assert_ok!(SubspaceModule::add_global_proposal(get_origin(other), new_global_params()));
+        assert_noop!(
+            SubspaceModule::add_global_proposal(get_origin(other), new_global_params()),
+            Error::<Test>::TooManyProposals
+        );
+
+        // tallying the proposals frees their places.
+        step_block(5);
+        assert_eq!(Proposals::<Test>::count(), 0);
+        assert_eq!(ProposalsPerKey::<Test>::get(key), 0);
+        assert_ok!(SubspaceModule::add_global_proposal(get_origin(key), new_global_params()));
+    });
+}
+
+#[test]
+fn test_votes_keep_a_running_tally() {
+    new_test_ext().execute_with(|| {
+        use pallet_subspace::{ProposalVotes, TalliedProposals};
+        let whale = U256::from(0);
+        let minnow = U256::from(1);
+        let whale_stake: u64 = 10_000_000_000;
+        let minnow_stake: u64 = 1_000_000_000;
+        register_module(0, whale, whale_stake);
+        register_module(0, minnow, minnow_stake);
+        add_balance(minnow, SubspaceModule::get_proposal_cost());
+        SubspaceModule::set_proposal_voting_period(5);
+
+        // a changed vote moves its stake from one side of the tally to the other.
+        assert_ok!(SubspaceModule::add_global_proposal(get_origin(minnow), new_global_params()));
+        assert_ok!(SubspaceModule::vote_proposal(get_origin(minnow), 0, true));
+        assert_ok!(SubspaceModule::vote_proposal(get_origin(whale), 0, true));
+        assert_eq!(SubspaceModule::tally_proposal(0), (whale_stake + minnow_stake, 0));
+        assert_ok!(SubspaceModule::vote_proposal(get_origin(whale), 0, false));
+        assert_eq!(SubspaceModule::tally_proposal(0), (minnow_stake, whale_stake));
+
+        // the tally is dropped with the proposal and its votes are removed after it.
+        let params_before = SubspaceModule::get_global_params();
+        step_block(5);
+        assert_eq!(SubspaceModule::get_global_params(), params_before);
+        assert_eq!(SubspaceModule::tally_proposal(0), (0, 0));
+        assert_eq!(ProposalVotes::<Test>::iter_prefix(0).count(), 0);
+        assert!(!TalliedProposals::<Test>::contains_key(0));
+    });
+}
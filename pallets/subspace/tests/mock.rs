@@ -1,6 +1,7 @@
 use frame_support::{assert_ok, parameter_types, traits::{Everything, Hooks}, weights};
 use frame_system::{limits, EnsureNever, EnsureRoot, RawOrigin};
 use frame_support::traits::{StorageMapShim, Hash};
+use frame_support::PalletId;
 use frame_system as system;
 use frame_system::Config;
 use sp_core::{H256, U256, Get};
@@ -41,6 +42,12 @@ pub type TestRuntimeCall = frame_system::Call<Test>;
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
 	pub const SS58Prefix: u8 = 42;
+	pub const TreasuryPalletId: PalletId = PalletId(*b"py/subtr");
+	pub const MaxNameLength: u32 = 32;
+	pub const MaxAddressLength: u32 = 64;
+	pub const MaxWeightsPerModule: u32 = 4096;
+	pub const MaxModulesPerSubnet: u32 = 4096;
+	pub static ExistentialDeposit: u64 = 0;
 }
 
 #[allow(dead_code)]
@@ -61,14 +68,14 @@ impl pallet_balances::Config for Test {
 	type Balance = Balance;
 	type RuntimeEvent = RuntimeEvent;
 	type DustRemoval = ();
-	type ExistentialDeposit = ();
+	type ExistentialDeposit = ExistentialDeposit;
 	type AccountStore = StorageMapShim<
 		pallet_balances::Account<Test>,
 		frame_system::Provider<Test>,
 		AccountId,
 		pallet_balances::AccountData<Balance>,
 	>;
-	type MaxLocks = ();
+	type MaxLocks = frame_support::traits::ConstU32<50>;
 	type WeightInfo = ();
 	type MaxReserves = ();
 	type ReserveIdentifier = ();
@@ -105,9 +112,32 @@ impl system::Config for Test {
 impl pallet_subspace::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type Currency = Balances;
+	type TreasuryPalletId = TreasuryPalletId;
+	type MaxNameLength = MaxNameLength;
+	type MaxAddressLength = MaxAddressLength;
+	type MaxWeightsPerModule = MaxWeightsPerModule;
+	type MaxModulesPerSubnet = MaxModulesPerSubnet;
+	type Signature = TestSignature;
+	type Signer = TestSigner;
+	type WeightInfo = ();
+}
 
+// Signature of the mock runtime, valid when it names the signer and carries the signed message.
+#[derive(codec::Encode, codec::Decode, Clone, PartialEq, Eq, Debug, scale_info::TypeInfo)]
+pub struct TestSignature(pub U256, pub Vec<u8>);
 
+pub struct TestSigner(pub U256);
 
+impl sp_runtime::traits::IdentifyAccount for TestSigner {
+	type AccountId = U256;
+	fn into_account(self) -> U256 { self.0 }
+}
+
+impl sp_runtime::traits::Verify for TestSignature {
+	type Signer = TestSigner;
+	fn verify<L: sp_runtime::traits::Lazy<[u8]>>(&self, mut msg: L, signer: &U256) -> bool {
+		self.0 == *signer && self.1 == msg.get()
+	}
 }
 
 
@@ -164,17 +194,15 @@ pub(crate) fn run_to_block(n: u64) {
 
 #[allow(dead_code)]
 pub fn add_balance( key: U256, balance: u64 ) {
-	SubspaceModule::add_balance_to_account( &key, balance );
+	SubspaceModule::add_balance_to_account( &key, balance ).unwrap();
 }
 #[allow(dead_code)]
 pub fn increase_stake( netuid: u16, key: U256, stake: u64 ) {
-	SubspaceModule::remove_balance_from_account( &key, stake );
-	SubspaceModule::increase_stake_on_account( netuid, &key, stake)
+	SubspaceModule::increase_stake_on_account( netuid, &key, stake).unwrap();
 }
 
 #[allow(dead_code)]
 pub fn decrease_stake( netuid: u16, key: U256, stake: u64 ) {
-	SubspaceModule::add_balance_to_account( &key, stake );
 	SubspaceModule::decrease_stake_on_account( netuid, &key, stake)
 }
 
@@ -297,3 +325,19 @@ pub fn add_stake_and_balance(netuid: u16, key: U256, amount: u64){
 	assert_ok!(result);
 }
 
+
+// Moves the per uid metrics of the subnet back into the vectors the chain was launched with.
+#[allow(dead_code)]
+pub fn put_legacy_metrics( netuid: u16 ) {
+	use frame_support::codec::Encode;
+	let put_vec = |item: &[u8], value: Vec<u8>| {
+		let mut key = frame_support::storage::storage_prefix(b"SubspaceModule", item).to_vec();
+		key.extend(netuid.encode());
+		let _ = frame_support::storage::unhashed::clear_prefix(&key, None, None);
+		frame_support::storage::unhashed::put_raw(&key, &value);
+	};
+	put_vec(b"Incentive", SubspaceModule::get_incentive(netuid).encode());
+	put_vec(b"Dividends", SubspaceModule::get_dividends(netuid).encode());
+	put_vec(b"Emission", SubspaceModule::get_emissions(netuid).encode());
+	put_vec(b"LastUpdate", SubspaceModule::get_last_update(netuid).encode());
+}
@@ -4,8 +4,10 @@ use pallet_subspace::{Error};
 use frame_support::weights::{GetDispatchInfo, DispatchInfo, DispatchClass, Pays};
 use frame_system::Config;
 use frame_support::{sp_std::vec};
-use frame_support::{assert_ok};
+use frame_support::{assert_ok, assert_noop};
 use sp_core::U256;
+use frame_support::traits::{Hooks, GetStorageVersion};
+use rand::{Rng, SeedableRng, rngs::StdRng};
 
 /*TO DO SAM: write test for LatuUpdate after it is set */
 
@@ -186,3 +188,318 @@ fn test_emission_ratio() {
 
 
 
+
+
+#[test]
+fn test_replace_least_staked_subnet() {
+    new_test_ext().execute_with(|| {
+        SubspaceModule::set_max_allowed_subnets(2);
+        SubspaceModule::set_subnet_immunity_period(0);
+        register_module(0, U256::from(0), 2_000_000_000);
+        register_module(1, U256::from(1), 1_000_000_000);
+        assert_eq!(SubspaceModule::get_balance(&U256::from(1)), 0);
+
+        // the new subnet takes over netuid 1 and the evicted stake is refunded
+        register_module(2, U256::from(2), 3_000_000_000);
+        assert_eq!(SubspaceModule::get_number_of_subnets(), 2);
+        assert_eq!(SubspaceModule::get_name_for_netuid(1), "test2".as_bytes().to_vec());
+        assert_eq!(SubspaceModule::get_subnet_n(1), 1);
+        assert_eq!(SubspaceModule::get_key_for_uid(1, 0), U256::from(2));
+        assert_eq!(SubspaceModule::get_balance(&U256::from(1)), 1_000_000_000);
+        assert_eq!(SubspaceModule::get_total_stake(), 5_000_000_000);
+        assert!(!SubspaceModule::is_key_registered(1, &U256::from(1)));
+    });
+}
+
+#[test]
+fn test_replace_subnet_respects_immunity() {
+    new_test_ext().execute_with(|| {
+        SubspaceModule::set_max_allowed_subnets(2);
+        SubspaceModule::set_subnet_immunity_period(100);
+        register_module(0, U256::from(0), 2_000_000_000);
+        register_module(1, U256::from(1), 1_000_000_000);
+
+        let key = U256::from(2);
+        add_balance(key, 3_000_000_000);
+        let register = || SubspaceModule::register(get_origin(key), "test2".as_bytes().to_vec(), "module2".as_bytes().to_vec(), "0.0.0.0:30333".as_bytes().to_vec(), 3_000_000_000);
+        assert_eq!(register(), Err(Error::<Test>::NotEnoughStakeToStartNetwork.into()));
+
+        // once out of immunity the subnet can be replaced, but only by more stake
+        System::set_block_number(100);
+        assert_ok!(register());
+        assert_eq!(SubspaceModule::get_number_of_subnets(), 2);
+        assert_eq!(SubspaceModule::get_name_for_netuid(1), "test2".as_bytes().to_vec());
+    });
+}
+
+#[test]
+fn test_no_netuid_is_allocated_when_nothing_can_be_replaced() {
+    new_test_ext().execute_with(|| {
+        use pallet_subspace::NextNetuid;
+        SubspaceModule::set_max_allowed_subnets(0);
+        let key = U256::from(0);
+        add_balance(key, 1_000_000_000);
+        assert_eq!(
+            SubspaceModule::register(get_origin(key), "test0".as_bytes().to_vec(), "module0".as_bytes().to_vec(), "0.0.0.0:30333".as_bytes().to_vec(), 1_000_000_000),
+            Err(Error::<Test>::NoReplaceableSubnet.into())
+        );
+        assert_eq!(NextNetuid::<Test>::get(), 0);
+        assert_eq!(SubspaceModule::get_number_of_subnets(), 0);
+
+        // the admin path fails the same way and allocates nothing either
+        assert_eq!(
+            SubspaceModule::add_network_from_name("admin".as_bytes().to_vec().try_into().unwrap(), &key),
+            Err(Error::<Test>::NoReplaceableSubnet.into())
+        );
+        assert_eq!(NextNetuid::<Test>::get(), 0);
+    });
+}
+
+#[test]
+fn test_add_network_from_name_checks() {
+    new_test_ext().execute_with(|| {
+        SubspaceModule::set_max_allowed_subnets(2);
+        SubspaceModule::set_subnet_immunity_period(0);
+        register_module(0, U256::from(0), 2_000_000_000);
+        register_module(1, U256::from(1), 1_000_000_000);
+
+        // the admin path can neither take a used name nor evict a subnet without more stake
+        assert_eq!(
+            SubspaceModule::add_network_from_name("test0".as_bytes().to_vec().try_into().unwrap(), &U256::from(2)),
+            Err(Error::<Test>::SubnetNameAlreadyExists.into())
+        );
+        assert_eq!(
+            SubspaceModule::add_network_from_name("admin".as_bytes().to_vec().try_into().unwrap(), &U256::from(2)),
+            Err(Error::<Test>::NotEnoughStakeToStartNetwork.into())
+        );
+        assert_eq!(SubspaceModule::get_number_of_subnets(), 2);
+        assert_eq!(SubspaceModule::get_key_for_uid(1, 0), U256::from(1));
+    });
+}
+
+
+#[test]
+fn test_add_remove_subnets_in_any_order() {
+    new_test_ext().execute_with(|| {
+        let stake: u64 = 1_000_000_000;
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut live: Vec<(u16, U256)> = vec![];
+        let mut next_key: u64 = 0;
+
+        for _ in 0..200 {
+            if live.is_empty() || rng.gen_bool(0.6) {
+                let key = U256::from(next_key);
+                let network: Vec<u8> = format!("subnet{}", next_key).into_bytes();
+                next_key += 1;
+                add_balance(key, stake);
+                assert_ok!(SubspaceModule::register(get_origin(key), network.clone(), "module".as_bytes().to_vec(), "0.0.0.0:30333".as_bytes().to_vec(), stake));
+
+                // a new subnet never lands on a live netuid
+                let netuid: u16 = SubspaceModule::get_netuid_for_name(network);
+                assert!(!live.iter().any(|(live_netuid, _)| *live_netuid == netuid));
+                live.push((netuid, key));
+            } else {
+                let (netuid, key) = live.remove(rng.gen_range(0..live.len()));
+                assert_ok!(SubspaceModule::remove_network(get_origin(key), netuid));
+                assert!(SubspaceModule::is_subnet_storage_clear(netuid));
+                assert_eq!(SubspaceModule::get_balance(&key), stake);
+            }
+
+            // every live subnet still holds exactly its own module and stake
+            assert_eq!(SubspaceModule::get_number_of_subnets() as usize, live.len());
+            for (netuid, key) in live.iter() {
+                assert_eq!(SubspaceModule::get_subnet_n(*netuid), 1);
+                assert_eq!(SubspaceModule::get_key_for_uid(*netuid, 0), *key);
+                assert_eq!(SubspaceModule::get_stake_for_key(*netuid, key), stake);
+                assert_eq!(SubspaceModule::get_total_subnet_stake(*netuid), stake);
+                assert!(SubspaceModule::is_subnet_founder(*netuid, key));
+                assert!(SubspaceModule::check_subnet_storage(*netuid));
+            }
+            assert_eq!(SubspaceModule::get_total_stake(), stake * live.len() as u64);
+        }
+    });
+}
+
+#[test]
+fn test_removed_netuid_is_reused() {
+    new_test_ext().execute_with(|| {
+        for netuid in 0..4 {
+            register_module(netuid, U256::from(netuid), 1_000_000_000);
+        }
+        remove_network(2, U256::from(2));
+        remove_network(1, U256::from(1));
+
+        // the lowest retired netuid is handed out first, then fresh ones
+        register_module(10, U256::from(10), 1_000_000_000);
+        assert_eq!(SubspaceModule::get_netuid_for_name("test10".as_bytes().to_vec()), 1);
+        register_module(11, U256::from(11), 1_000_000_000);
+        assert_eq!(SubspaceModule::get_netuid_for_name("test11".as_bytes().to_vec()), 2);
+        register_module(12, U256::from(12), 1_000_000_000);
+        assert_eq!(SubspaceModule::get_netuid_for_name("test12".as_bytes().to_vec()), 4);
+        assert_eq!(SubspaceModule::get_key_for_uid(3, 0), U256::from(3));
+    });
+}
+
+#[test]
+fn test_migrate_netuid_allocation() {
+    new_test_ext().execute_with(|| {
+        for netuid in [0, 1, 3] {
+            pallet_subspace::N::<Test>::insert(netuid, 0);
+        }
+        assert_eq!(SubspaceModule::on_chain_storage_version(), 0);
+
+        SubspaceModule::on_runtime_upgrade();
+        assert_eq!(SubspaceModule::on_chain_storage_version(), SubspaceModule::current_storage_version());
+        assert_eq!(pallet_subspace::NextNetuid::<Test>::get(), 4);
+        assert_eq!(pallet_subspace::TotalSubnets::<Test>::get(), 3);
+        assert_eq!(SubspaceModule::allocate_netuid(), 2);
+        assert_eq!(SubspaceModule::allocate_netuid(), 4);
+    });
+}
+
+
+#[test]
+fn test_subnet_names_follow_updates() {
+    new_test_ext().execute_with(|| {
+        let key = U256::from(0);
+        register_module(0, key, 1_000_000_000);
+        register_module(1, U256::from(1), 1_000_000_000);
+        let params = SubspaceModule::get_subnet(0);
+
+        let rename = |name: &str| SubspaceModule::update_network(get_origin(key), 0, name.as_bytes().to_vec(),
+            params.immunity_period, params.min_allowed_weights, params.max_allowed_weights,
+            params.max_allowed_uids, params.tempo, params.founder);
+        assert_eq!(rename("test1"), Err(Error::<Test>::SubnetNameAlreadyExists.into()));
+        assert_ok!(rename("renamed"));
+
+        assert_eq!(SubspaceModule::get_name_for_netuid(0), "renamed".as_bytes().to_vec());
+        assert_eq!(SubspaceModule::get_netuid_for_name("renamed".as_bytes().to_vec()), 0);
+        assert!(!SubspaceModule::if_subnet_name_exists("test0".as_bytes().to_vec()));
+        assert!(SubspaceModule::if_subnet_netuid_exists(0));
+
+        remove_network(0, key);
+        assert!(!SubspaceModule::if_subnet_netuid_exists(0));
+        assert!(!SubspaceModule::if_subnet_name_exists("renamed".as_bytes().to_vec()));
+        assert_eq!(SubspaceModule::get_name_for_netuid(1), "test1".as_bytes().to_vec());
+    });
+}
+
+#[test]
+fn test_migrate_subnet_names() {
+    new_test_ext().execute_with(|| {
+        pallet_subspace::N::<Test>::insert(0, 0);
+        let name = |name: &str| -> pallet_subspace::BoundedName<Test> { name.as_bytes().to_vec().try_into().unwrap() };
+        pallet_subspace::SubnetNamespace::<Test>::insert(name("live"), 0);
+        pallet_subspace::SubnetNamespace::<Test>::insert(name("gone"), 5);
+
+        SubspaceModule::on_runtime_upgrade();
+        assert_eq!(SubspaceModule::get_name_for_netuid(0), "live".as_bytes().to_vec());
+        assert!(!SubspaceModule::if_subnet_netuid_exists(5));
+        assert!(!SubspaceModule::if_subnet_name_exists("gone".as_bytes().to_vec()));
+    });
+}
+
+#[test]
+fn test_module_metrics_follow_uid_removal() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        for i in 0..3 {
+            register_module(netuid, U256::from(i), 1_000_000_000);
+        }
+        SubspaceModule::epoch(netuid, 1_000_000_000);
+        assert_eq!(SubspaceModule::get_emissions(netuid).len(), 3);
+        let last_emission: u64 = SubspaceModule::get_emission_for_uid(netuid, 2);
+
+        // the last uid moves into the removed uid and its old entries are dropped
+        SubspaceModule::remove_module(netuid, 0);
+        assert_eq!(SubspaceModule::get_subnet_n(netuid), 2);
+        assert_eq!(SubspaceModule::get_emissions(netuid).len(), 2);
+        assert_eq!(SubspaceModule::get_last_update(netuid).len(), 2);
+        assert_eq!(SubspaceModule::get_emission_for_uid(netuid, 2), 0);
+        assert!(SubspaceModule::check_subnet_storage(netuid));
+        assert!(!SubspaceModule::is_uid_exist_on_network(netuid, 2));
+        assert!(last_emission > 0);
+    });
+}
+
+#[test]
+fn test_migrate_module_metrics_per_uid() {
+    use frame_support::codec::Encode;
+    new_test_ext().execute_with(|| {
+        pallet_subspace::N::<Test>::insert(0, 2);
+        let put_vec = |item: &[u8], value: Vec<u64>| {
+            let mut key = frame_support::storage::storage_prefix(b"SubspaceModule", item).to_vec();
+            key.extend(0u16.encode());
+            frame_support::storage::unhashed::put(&key, &value);
+        };
+        put_vec(b"Emission", vec![10, 20]);
+        put_vec(b"LastUpdate", vec![5, 6]);
+
+        SubspaceModule::on_runtime_upgrade();
+        assert_eq!(SubspaceModule::on_chain_storage_version(), SubspaceModule::current_storage_version());
+        assert_eq!(SubspaceModule::get_emission_for_uid(0, 0), 10);
+        assert_eq!(SubspaceModule::get_emission_for_uid(0, 1), 20);
+        assert_eq!(SubspaceModule::get_last_update(0), vec![5, 6]);
+        assert_eq!(SubspaceModule::get_incentive(0), Vec::<u16>::new());
+    });
+}
+
+#[test]
+fn test_names_and_addresses_are_bounded() {
+    new_test_ext().execute_with(|| {
+        let key: U256 = U256::from(0);
+        add_balance(key, 1_000_000_000);
+        let too_long: Vec<u8> = vec![b'a'; 33];
+        assert_noop!(
+            SubspaceModule::register(get_origin(key), too_long.clone(), "module".as_bytes().to_vec(), "0.0.0.0:30333".as_bytes().to_vec(), 1_000),
+            Error::<Test>::SubnetNameTooLong
+        );
+        assert_noop!(
+            SubspaceModule::register(get_origin(key), "test0".as_bytes().to_vec(), too_long.clone(), "0.0.0.0:30333".as_bytes().to_vec(), 1_000),
+            Error::<Test>::ModuleNameTooLong
+        );
+        assert_noop!(
+            SubspaceModule::register(get_origin(key), "test0".as_bytes().to_vec(), "module".as_bytes().to_vec(), vec![b'0'; 65], 1_000),
+            Error::<Test>::AddressTooLong
+        );
+        assert!(!SubspaceModule::if_subnet_name_exists(too_long));
+    });
+}
+
+#[test]
+fn test_migrate_bounded_storage() {
+    use frame_support::codec::Encode;
+    use frame_support::traits::StorageVersion;
+    new_test_ext().execute_with(|| {
+        register_module(0, U256::from(0), 1_000_000_000);
+        register_module(0, U256::from(1), 1_000_000_000);
+        StorageVersion::new(0).put::<SubspaceModule>();
+        put_legacy_metrics(0);
+
+        let twox_64_concat = |data: Vec<u8>| -> Vec<u8> { [sp_io::hashing::twox_64(&data).to_vec(), data].concat() };
+        let key = |item: &[u8], first: Vec<u8>, second: Vec<u8>| -> Vec<u8> {
+            [frame_support::storage::storage_prefix(b"SubspaceModule", item).to_vec(), twox_64_concat(first), twox_64_concat(second)].concat()
+        };
+        let rename = |uid: u16, name: Vec<u8>| {
+            let old_name: Vec<u8> = SubspaceModule::get_name_for_uid(0, uid);
+            frame_support::storage::unhashed::kill(&key(b"Namespace", 0u16.encode(), old_name.encode()));
+            frame_support::storage::unhashed::put(&key(b"Namespace", 0u16.encode(), name.encode()), &uid);
+            frame_support::storage::unhashed::put(&key(b"Names", 0u16.encode(), uid.encode()), &name);
+        };
+
+        // legacy entries: module0 has a name that truncates onto the name of module1, module1 an over long address.
+        rename(0, vec![b'a'; 40]);
+        rename(1, vec![b'a'; 32]);
+        frame_support::storage::unhashed::put(&key(b"Address", 0u16.encode(), 1u16.encode()), &vec![b'0'; 100]);
+
+        SubspaceModule::on_runtime_upgrade();
+        assert_eq!(SubspaceModule::on_chain_storage_version(), SubspaceModule::current_storage_version());
+
+        // module0 is rejected and module1 moves into its uid.
+        assert_eq!(SubspaceModule::get_subnet_n(0), 1);
+        assert_eq!(SubspaceModule::get_key_for_uid(0, 0), U256::from(1));
+        assert_eq!(SubspaceModule::get_name_for_uid(0, 0), vec![b'a'; 32]);
+        assert_eq!(SubspaceModule::get_uid_for_name(0, vec![b'a'; 32]), 0);
+        assert!(SubspaceModule::check_subnet_storage(0));
+    });
+}
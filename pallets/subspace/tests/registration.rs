@@ -2,11 +2,11 @@
 use frame_support::traits::Currency;
 use ndarray::stack_new_axis;
 use pallet_subspace::{Error};
-use frame_support::{assert_ok};
+use frame_support::{assert_ok, assert_noop};
 use frame_system::Config;
 use sp_core::U256;
 use crate::{mock::*};
-use frame_support::sp_runtime::DispatchError;
+use frame_support::sp_runtime::{DispatchError, DispatchResult};
 use frame_support::dispatch::{GetDispatchInfo, DispatchInfo};
 use frame_support::weights::{DispatchClass, Pays};
 
@@ -114,3 +114,278 @@ fn register_same_key_twice() {
 
 
 
+
+fn bulk_modules(keys: std::ops::Range<u64>, stake: u64) -> Vec<(U256, Vec<u8>, Vec<u8>, u64)> {
+	keys.map(|i| {
+		let key = U256::from(i);
+		(key, format!("bulk{}", i).as_bytes().to_vec(), b"0.0.0.0:30333".to_vec(), stake)
+	}).collect()
+}
+
+#[test]
+fn test_root_bulk_register() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		register_module(netuid, founder, stake);
+		add_balance(founder, stake);
+		add_balance(U256::from(11), stake);
+
+		// one staked module rejects the whole batch, neither root nor the keys pay for it.
+		let mut modules = bulk_modules(10..12, 0);
+		modules[1].3 = stake;
+		assert_noop!(
+			SubspaceModule::bulk_register(RuntimeOrigin::root(), netuid, modules),
+			Error::<Test>::RootCanNotPayStake
+		);
+		assert_eq!(SubspaceModule::get_balance(&founder), stake);
+		assert_eq!(SubspaceModule::get_balance(&U256::from(11)), stake);
+
+		// without stake root registers the modules, the balance of the keys stays free.
+		assert_ok!(SubspaceModule::bulk_register(RuntimeOrigin::root(), netuid, bulk_modules(10..12, 0)));
+		assert_eq!(SubspaceModule::get_subnet_n(netuid), 3);
+		assert!(SubspaceModule::is_registered(netuid, &U256::from(11)));
+		assert_eq!(SubspaceModule::get_stake(netuid, &U256::from(11)), 0);
+		assert_eq!(SubspaceModule::get_balance(&U256::from(11)), stake);
+	});
+}
+
+#[test]
+fn test_bulk_register() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		register_module(netuid, founder, stake);
+		add_balance(founder, 5 * stake + 1);
+
+		assert_noop!(
+			SubspaceModule::bulk_register(get_origin(U256::from(10)), netuid, bulk_modules(10..13, stake)),
+			Error::<Test>::NotSubnetFounder
+		);
+		assert_ok!(SubspaceModule::bulk_register(get_origin(founder), netuid, bulk_modules(10..13, stake)));
+		assert_eq!(SubspaceModule::get_stake(netuid, &U256::from(12)), stake);
+		assert_eq!(SubspaceModule::get_balance(&founder), 2 * stake + 1);
+
+		// root has no balance to pay the stake from.
+		assert_noop!(
+			SubspaceModule::bulk_register(RuntimeOrigin::root(), netuid, bulk_modules(13..15, stake)),
+			Error::<Test>::RootCanNotPayStake
+		);
+		assert_ok!(SubspaceModule::bulk_register(RuntimeOrigin::root(), netuid, bulk_modules(13..15, 0)));
+		assert_eq!(SubspaceModule::get_subnet_n(netuid), 6);
+		assert_eq!(SubspaceModule::get_stake(netuid, &U256::from(14)), 0);
+
+		// a module which cannot be registered reverts the whole batch.
+		assert_noop!(
+			SubspaceModule::bulk_register(get_origin(founder), netuid, bulk_modules(15..17, stake).into_iter().chain(bulk_modules(10..11, stake)).collect()),
+			Error::<Test>::KeyAlreadyRegistered
+		);
+		assert_noop!(
+			SubspaceModule::bulk_register(get_origin(founder), netuid, bulk_modules(15..18, stake)),
+			Error::<Test>::NotEnoughBalanceToStake
+		);
+
+		// the batch has to fit the limits of the subnet.
+		SubspaceModule::set_max_allowed_uids(netuid, 8);
+		assert_noop!(
+			SubspaceModule::bulk_register(get_origin(founder), netuid, bulk_modules(15..18, 0)),
+			Error::<Test>::MaxAllowedUidsExceeded
+		);
+		SubspaceModule::set_max_allowed_uids(netuid, 100);
+		SubspaceModule::set_max_registrations_per_block(netuid, 6);
+		assert_noop!(
+			SubspaceModule::bulk_register(get_origin(founder), netuid, bulk_modules(15..17, 0)),
+			Error::<Test>::TooManyRegistrationsThisBlock
+		);
+	});
+}
+
+#[test]
+fn test_bulk_register_does_not_lock_balance_of_keys() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		let key = U256::from(10);
+		register_module(netuid, founder, stake);
+		add_balance(founder, stake + 1);
+		add_balance(key, 3 * stake);
+
+		// the key did not sign the batch, its own balance stays free and only the stake sent by the founder is locked.
+		assert_ok!(SubspaceModule::bulk_register(get_origin(founder), netuid, bulk_modules(10..11, stake)));
+		assert_eq!(SubspaceModule::get_stake(netuid, &key), stake);
+		assert_eq!(SubspaceModule::get_balance(&key), 3 * stake);
+		assert_eq!(SubspaceModule::get_balance(&founder), 1);
+
+		// the founder can not stake more than it holds on behalf of the key.
+		SubspaceModule::remove_module(netuid, SubspaceModule::get_uid_for_key(netuid, &key));
+		assert_noop!(
+			SubspaceModule::bulk_register(get_origin(founder), netuid, bulk_modules(10..11, stake)),
+			Error::<Test>::NotEnoughBalanceToStake
+		);
+		assert_eq!(SubspaceModule::get_balance(&key), 4 * stake);
+	});
+}
+
+fn register_on(key: U256, stake: u64, ticket: Option<pallet_subspace::RegistrationTicket<TestSignature>>) -> DispatchResult {
+	add_balance(key, stake);
+	let name: Vec<u8> = format!("module{}", key).as_bytes().to_vec();
+	let address: Vec<u8> = b"0.0.0.0:30333".to_vec();
+	match ticket {
+		Some(ticket) => SubspaceModule::register_with_ticket(get_origin(key), b"test0".to_vec(), name, address, stake, ticket),
+		None => SubspaceModule::register(get_origin(key), b"test0".to_vec(), name, address, stake),
+	}
+}
+
+#[test]
+fn test_registration_modes() {
+	new_test_ext().execute_with(|| {
+		use pallet_subspace::{RegistrationMode, RegistrationTicket};
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		register_module(netuid, founder, stake);
+
+		assert_noop!(
+			SubspaceModule::set_registration_mode(get_origin(U256::from(1)), netuid, RegistrationMode::Allowlist),
+			Error::<Test>::NotSubnetFounder
+		);
+
+		// allowlist: only listed keys get in.
+		assert_ok!(SubspaceModule::set_registration_mode(get_origin(founder), netuid, RegistrationMode::Allowlist));
+		assert_eq!(SubspaceModule::get_subnet(netuid).mode, RegistrationMode::Allowlist);
+		assert_eq!(register_on(U256::from(1), stake, None), Err(Error::<Test>::RegistrationNotAllowed.into()));
+		assert_ok!(SubspaceModule::set_allowlisted(get_origin(founder), netuid, U256::from(1), true));
+		assert_ok!(register_on(U256::from(1), stake, None));
+
+		// invite: only keys holding an unexpired ticket signed by the founder get in.
+		assert_ok!(SubspaceModule::set_registration_mode(get_origin(founder), netuid, RegistrationMode::Invite));
+		let key = U256::from(2);
+		let ticket = |signer: U256, expires_at: u64| RegistrationTicket {
+			expires_at,
+			signature: TestSignature(signer, SubspaceModule::registration_ticket_payload(netuid, &key, expires_at)),
+		};
+		assert_eq!(register_on(key, stake, None), Err(Error::<Test>::RegistrationNotAllowed.into()));
+		assert_eq!(register_on(key, stake, Some(ticket(U256::from(1), 10))), Err(Error::<Test>::InvalidRegistrationTicket.into()));
+		step_block(2);
+		assert_eq!(register_on(key, stake, Some(ticket(founder, 1))), Err(Error::<Test>::InvalidRegistrationTicket.into()));
+		assert_ok!(register_on(key, stake, Some(ticket(founder, 10))));
+		assert_eq!(SubspaceModule::get_subnet_n(netuid), 3);
+	});
+}
+
+#[test]
+fn test_inactive_modules_are_deregistered() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		for key in 0..4 {
+			register_module(netuid, U256::from(key), stake);
+		}
+		SubspaceModule::set_tempo(netuid, 1);
+
+		assert_noop!(
+			SubspaceModule::set_max_inactive_blocks(get_origin(U256::from(1)), netuid, 10),
+			Error::<Test>::NotSubnetFounder
+		);
+		assert_ok!(SubspaceModule::set_max_inactive_blocks(get_origin(founder), netuid, 10));
+
+		// key 2 keeps itself alive, keys 1 and 3 go quiet.
+		run_to_block(8);
+		assert_ok!(SubspaceModule::heartbeat(get_origin(U256::from(2)), netuid));
+		run_to_block(12);
+
+		assert_eq!(SubspaceModule::get_subnet_n(netuid), 2);
+		assert!(SubspaceModule::is_registered(netuid, &founder));
+		assert!(SubspaceModule::is_registered(netuid, &U256::from(2)));
+		for key in [1, 3] {
+			let key = U256::from(key);
+			assert!(!SubspaceModule::is_registered(netuid, &key));
+			assert_eq!(SubspaceModule::get_stake_for_key(netuid, &key), 0);
+			assert!(SubspaceModule::get_balance(&key) >= stake);
+		}
+		assert_noop!(SubspaceModule::heartbeat(get_origin(U256::from(1)), netuid), Error::<Test>::NotRegistered);
+	});
+}
+
+#[test]
+fn test_inactive_deregistrations_are_capped() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		for key in 0..40 {
+			register_module(netuid, U256::from(key), stake);
+		}
+		SubspaceModule::set_tempo(netuid, 1);
+		assert_ok!(SubspaceModule::set_max_inactive_blocks(get_origin(founder), netuid, 10));
+
+		// at most 32 modules are removed in one epoch, the rest in the next one.
+		run_to_block(11);
+		assert_eq!(SubspaceModule::get_subnet_n(netuid), 8);
+		run_to_block(12);
+		assert_eq!(SubspaceModule::get_subnet_n(netuid), 1);
+		assert!(SubspaceModule::is_registered(netuid, &founder));
+	});
+}
+
+#[test]
+fn test_deregistering_never_removes_the_subnet() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		for key in 0..3 {
+			register_module(netuid, U256::from(key), stake);
+		}
+		register_module(1, U256::from(10), stake);
+		SubspaceModule::set_tempo(netuid, 1);
+		SubspaceModule::set_tempo(1, 1);
+		assert_ok!(SubspaceModule::set_max_inactive_blocks(get_origin(founder), netuid, 10));
+
+		// the founder left its subnet, so all its modules are inactive, the emptied subnet stays.
+		SubspaceModule::remove_module(netuid, SubspaceModule::get_uid_for_key(netuid, &founder));
+		run_to_block(12);
+		assert!(SubspaceModule::if_subnet_exist(netuid));
+		assert_eq!(SubspaceModule::get_subnet_n(netuid), 0);
+		assert!(SubspaceModule::is_subnet_founder(netuid, &founder));
+		assert_eq!(SubspaceModule::get_subnet_n(1), 1);
+	});
+}
+
+#[test]
+fn test_deregistering_keeps_the_moved_module() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		let moved = U256::from(3);
+		for key in 0..4 {
+			register_module(netuid, U256::from(key), stake);
+		}
+		SubspaceModule::set_tempo(netuid, 1);
+		assert_ok!(SubspaceModule::set_max_inactive_blocks(get_origin(founder), netuid, 10));
+
+		// key 1 goes quiet, key 3 is the last uid and stays active by setting weights.
+		run_to_block(8);
+		assert_ok!(SubspaceModule::heartbeat(get_origin(U256::from(2)), netuid));
+		assert_ok!(SubspaceModule::set_weights(get_origin(founder), netuid, vec![1, 3], vec![1, 1]));
+		assert_ok!(SubspaceModule::set_weights(get_origin(moved), netuid, vec![0, 2], vec![1, 1]));
+		let registered_at: u64 = SubspaceModule::get_block_at_registration(netuid)[3];
+		run_to_block(12);
+
+		// key 3 moved into uid 1 with its state, the weights on it followed it.
+		assert_eq!(SubspaceModule::get_subnet_n(netuid), 3);
+		assert_eq!(SubspaceModule::get_uid_for_key(netuid, &moved), 1);
+		assert_eq!(SubspaceModule::get_last_update_for_uid(netuid, 1), 8);
+		assert_eq!(SubspaceModule::get_block_at_registration(netuid)[1], registered_at);
+		let weights = SubspaceModule::get_weights_sparse(netuid);
+		let targets = |uid: usize| -> Vec<u16> { weights[uid].iter().map(|(target, _)| *target).collect() };
+		assert_eq!(targets(0), vec![1]);
+		assert_eq!(targets(1), vec![0, 2]);
+		assert!(SubspaceModule::if_subnet_exist(netuid));
+	});
+}
@@ -103,3 +103,384 @@ fn test_stake() {
 }
 
 
+
+
+#[test]
+fn test_stake_is_locked_on_balance() {
+	new_test_ext().execute_with(|| {
+        let netuid : u16 = 0;
+        let key : U256 = U256::from(0);
+        let other_key : U256 = U256::from(1);
+        let balance : u64 = 10_000_000_000;
+        let stake : u64 = 4_000_000_000;
+
+        add_balance(key, balance);
+        register(netuid, key, stake);
+
+        // the stake never leaves the account, it is locked on the free balance.
+        assert_eq!(Balances::free_balance(&key), balance);
+        assert_eq!(SubspaceModule::get_balance(&key), balance - stake);
+        assert_eq!(SubspaceModule::get_total_stake_for_key(&key), stake);
+        assert!(Balances::transfer(get_origin(key), other_key, balance - stake + 1).is_err());
+        assert_ok!(Balances::transfer(get_origin(key), other_key, balance - stake));
+
+        // staking more than the unlocked balance fails without touching the stake.
+        assert_eq!(SubspaceModule::add_stake(get_origin(key), netuid, 1), Err(Error::<Test>::NotEnoughBalanceToStake.into()));
+        assert_eq!(SubspaceModule::get_stake(netuid, &key), stake);
+
+        // unstaking unlocks the balance again.
+        assert_ok!(SubspaceModule::remove_stake(get_origin(key), netuid, stake));
+        assert_eq!(Balances::free_balance(&key), stake);
+        assert_eq!(SubspaceModule::get_balance(&key), stake);
+        assert_eq!(SubspaceModule::get_total_stake_for_key(&key), 0);
+        assert_eq!(SubspaceModule::remove_stake(get_origin(key), netuid, 1), Err(Error::<Test>::NotEnoughStaketoWithdraw.into()));
+        assert_ok!(Balances::transfer(get_origin(key), other_key, stake));
+	});
+}
+
+
+#[test]
+fn test_epoch_mints_emission() {
+	new_test_ext().execute_with(|| {
+        let netuid : u16 = 0;
+        let stake : u64 = 1_000_000_000;
+        for i in 0..4 {
+            register_module(netuid, U256::from(i), stake);
+        }
+        let issuance : u64 = Balances::total_issuance();
+        let total_stake : u64 = SubspaceModule::get_total_stake();
+
+        let distributed : u64 = SubspaceModule::epoch(netuid, 1_000_000_000);
+        assert!(distributed > 0);
+
+        // the rewards are new issuance, locked as stake of the modules.
+        assert_eq!(Balances::total_issuance(), issuance + distributed);
+        assert_eq!(SubspaceModule::get_total_stake(), total_stake + distributed);
+        for i in 0..4 {
+            let key : U256 = U256::from(i);
+            assert_eq!(Balances::free_balance(&key), SubspaceModule::get_total_stake_for_key(&key));
+            assert_eq!(SubspaceModule::get_balance(&key), 0);
+        }
+	});
+}
+
+
+#[test]
+fn test_migrate_stake_to_locks() {
+    use frame_support::traits::{Hooks, GetStorageVersion, StorageVersion, LockableCurrency};
+	new_test_ext().execute_with(|| {
+        let netuid : u16 = 0;
+        let key : U256 = U256::from(0);
+        let balance : u64 = 3_000_000_000;
+        let stake : u64 = 1_000_000_000;
+        add_balance(key, balance);
+        register(netuid, key, stake);
+
+        // legacy layout: the stake was withdrawn from the account and nothing is locked.
+        StorageVersion::new(0).put::<SubspaceModule>();
+        put_legacy_metrics(netuid);
+        pallet_subspace::LockedStake::<Test>::remove(&key);
+        Balances::remove_lock(pallet_subspace::STAKE_LOCK_ID, &key);
+        Balances::make_free_balance_be(&key, balance - stake);
+        let issuance : u64 = Balances::total_issuance();
+
+        SubspaceModule::on_runtime_upgrade();
+        assert_eq!(SubspaceModule::on_chain_storage_version(), SubspaceModule::current_storage_version());
+
+        assert_eq!(Balances::free_balance(&key), balance);
+        assert_eq!(Balances::total_issuance(), issuance + stake);
+        assert_eq!(SubspaceModule::get_total_stake_for_key(&key), stake);
+        assert_eq!(SubspaceModule::get_balance(&key), balance - stake);
+
+        // running it again does not mint twice.
+        StorageVersion::new(0).put::<SubspaceModule>();
+        put_legacy_metrics(netuid);
+        SubspaceModule::on_runtime_upgrade();
+        assert_eq!(Balances::free_balance(&key), balance);
+	});
+}
+
+#[test]
+fn test_increase_stake_overflow_is_rejected() {
+	new_test_ext().execute_with(|| {
+        let netuid : u16 = 0;
+        let key : U256 = U256::from(0);
+        let stake : u64 = 1_000_000_000;
+        register_module(netuid, key, stake);
+
+        assert_eq!(
+            SubspaceModule::increase_stake_on_account(netuid, &key, u64::MAX),
+            Err(Error::<Test>::ArithmeticOverflow.into())
+        );
+        assert_eq!(SubspaceModule::get_stake(netuid, &key), stake);
+        assert_eq!(SubspaceModule::get_total_stake(), stake);
+        assert_eq!(SubspaceModule::get_total_stake_for_key(&key), stake);
+	});
+}
+
+#[test]
+fn test_transfer_stake_between_subnets() {
+	new_test_ext().execute_with(|| {
+        let key : U256 = U256::from(0);
+        let other_key : U256 = U256::from(1);
+        let stake : u64 = 1_000_000_000;
+        let amount : u64 = 400_000_000;
+        register_module(0, key, stake);
+        register_module(1, key, stake);
+        register_module(0, other_key, stake);
+        let balance : u64 = SubspaceModule::get_balance(&key);
+
+        assert_ok!(SubspaceModule::transfer_stake(get_origin(key), 0, 1, amount));
+        assert_eq!(SubspaceModule::get_stake(0, &key), stake - amount);
+        assert_eq!(SubspaceModule::get_stake(1, &key), stake + amount);
+        assert_eq!(SubspaceModule::get_total_subnet_stake(0), 2 * stake - amount);
+        assert_eq!(SubspaceModule::get_total_subnet_stake(1), stake + amount);
+        assert_eq!(SubspaceModule::get_total_stake(), 3 * stake);
+        assert_eq!(SubspaceModule::get_total_stake_for_key(&key), 2 * stake);
+        assert_eq!(SubspaceModule::get_balance(&key), balance);
+
+        assert_eq!(
+            SubspaceModule::transfer_stake(get_origin(key), 0, 1, stake),
+            Err(Error::<Test>::NotEnoughStaketoWithdraw.into())
+        );
+        assert_eq!(
+            SubspaceModule::transfer_stake(get_origin(key), 1, 1, amount),
+            Err(Error::<Test>::SameSubnetTransfer.into())
+        );
+        assert_eq!(
+            SubspaceModule::transfer_stake(get_origin(other_key), 0, 1, amount),
+            Err(Error::<Test>::NotRegistered.into())
+        );
+        assert_eq!(
+            SubspaceModule::transfer_stake(get_origin(key), 0, 7, amount),
+            Err(Error::<Test>::NetworkDoesNotExist.into())
+        );
+	});
+}
+
+#[test]
+fn test_transfer_stake_to_account() {
+	new_test_ext().execute_with(|| {
+        let netuid : u16 = 0;
+        let key : U256 = U256::from(0);
+        let dest : U256 = U256::from(1);
+        let stake : u64 = 1_000_000_000;
+        let amount : u64 = 400_000_000;
+        register_module(netuid, key, stake);
+        register_module(netuid, dest, stake);
+
+        assert_ok!(SubspaceModule::transfer_stake_to(get_origin(key), netuid, dest, amount));
+        assert_eq!(SubspaceModule::get_stake(netuid, &key), stake - amount);
+        assert_eq!(SubspaceModule::get_stake(netuid, &dest), stake + amount);
+        assert_eq!(SubspaceModule::get_total_subnet_stake(netuid), 2 * stake);
+        assert_eq!(SubspaceModule::get_total_stake_for_key(&key), stake - amount);
+        assert_eq!(SubspaceModule::get_total_stake_for_key(&dest), stake + amount);
+
+        // the locked balance moves with the stake.
+        assert_eq!(Balances::free_balance(&key), stake - amount);
+        assert_eq!(Balances::free_balance(&dest), stake + amount);
+        assert_eq!(SubspaceModule::get_balance(&key), 0);
+        assert_eq!(SubspaceModule::get_balance(&dest), 0);
+
+        assert_eq!(
+            SubspaceModule::transfer_stake_to(get_origin(key), netuid, dest, stake),
+            Err(Error::<Test>::NotEnoughStaketoWithdraw.into())
+        );
+        assert_eq!(
+            SubspaceModule::transfer_stake_to(get_origin(key), netuid, key, amount),
+            Err(Error::<Test>::SameKeyTransfer.into())
+        );
+        assert_eq!(
+            SubspaceModule::transfer_stake_to(get_origin(key), netuid, U256::from(2), amount),
+            Err(Error::<Test>::NotRegistered.into())
+        );
+	});
+}
+
+#[test]
+fn test_emission_payout_preferences() {
+	new_test_ext().execute_with(|| {
+        use pallet_subspace::PayoutPreference;
+        let netuid : u16 = 0;
+        let stake : u64 = 1_000_000_000;
+        let dest : U256 = U256::from(9);
+        for i in 0..3 {
+            register_module(netuid, U256::from(i), stake);
+        }
+        assert_ok!(SubspaceModule::set_payout_preference(get_origin(U256::from(1)), PayoutPreference::Free));
+        assert_ok!(SubspaceModule::set_payout_preference(get_origin(U256::from(2)), PayoutPreference::Account(dest)));
+        let issuance : u64 = Balances::total_issuance();
+
+        let distributed : u64 = SubspaceModule::epoch(netuid, 1_000_000_000);
+        let emission : Vec<u64> = (0..3).map(|uid| SubspaceModule::get_emission_for_uid(netuid, uid)).collect();
+        assert!(emission.iter().all(|e| *e > 0));
+        assert_eq!(distributed, emission.iter().sum::<u64>());
+        assert_eq!(Balances::total_issuance(), issuance + distributed);
+
+        // compounded into stake
+        assert_eq!(SubspaceModule::get_stake(netuid, &U256::from(0)), stake + emission[0]);
+        assert_eq!(SubspaceModule::get_balance(&U256::from(0)), 0);
+        // paid to the free balance of the key
+        assert_eq!(SubspaceModule::get_stake(netuid, &U256::from(1)), stake);
+        assert_eq!(SubspaceModule::get_balance(&U256::from(1)), emission[1]);
+        // paid to another account
+        assert_eq!(SubspaceModule::get_stake(netuid, &U256::from(2)), stake);
+        assert_eq!(SubspaceModule::get_balance(&U256::from(2)), 0);
+        assert_eq!(SubspaceModule::get_balance(&dest), emission[2]);
+
+        // going back to compounding drops the stored preference
+        assert_ok!(SubspaceModule::set_payout_preference(get_origin(U256::from(1)), PayoutPreference::Compound));
+        assert!(!pallet_subspace::PayoutPreferences::<Test>::contains_key(U256::from(1)));
+	});
+}
+
+#[test]
+fn test_slash_module() {
+	new_test_ext().execute_with(|| {
+		use pallet_subspace::{SlashDestination, SlashHistory};
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		let key = U256::from(1);
+		register_module(netuid, founder, stake);
+		register_module(netuid, key, stake);
+		SubspaceModule::set_tempo(netuid, 0);
+
+		assert_eq!(SubspaceModule::set_slash_defer_period(get_origin(founder), 5), Err(DispatchError::BadOrigin));
+		assert_ok!(SubspaceModule::set_slash_defer_period(RuntimeOrigin::root(), 5));
+		assert_eq!(
+			SubspaceModule::slash_module(get_origin(key), netuid, founder, 50, SlashDestination::Burn),
+			Err(Error::<Test>::NotSubnetFounder.into())
+		);
+		assert_eq!(
+			SubspaceModule::slash_module(get_origin(founder), netuid, key, 0, SlashDestination::Burn),
+			Err(Error::<Test>::StorageValueOutOfRange.into())
+		);
+
+		// the founder burns half of the stake, root sends a tenth of it to the treasury.
+		assert_ok!(SubspaceModule::slash_module(get_origin(founder), netuid, key, 50, SlashDestination::Burn));
+		assert_ok!(SubspaceModule::slash_module(RuntimeOrigin::root(), netuid, key, 100, SlashDestination::Burn));
+		assert_ok!(SubspaceModule::slash_module(RuntimeOrigin::root(), netuid, key, 10, SlashDestination::Treasury));
+		assert_eq!(SubspaceModule::cancel_slash(get_origin(key), 0), Err(Error::<Test>::NotSlashScheduler.into()));
+
+		// the founder can only cancel the slashes it scheduled, root can cancel any of them.
+		assert_eq!(SubspaceModule::cancel_slash(get_origin(founder), 1), Err(Error::<Test>::NotSlashScheduler.into()));
+		assert_ok!(SubspaceModule::cancel_slash(RuntimeOrigin::root(), 1));
+		assert_eq!(SubspaceModule::cancel_slash(RuntimeOrigin::root(), 1), Err(Error::<Test>::SlashDoesNotExist.into()));
+		assert_ok!(SubspaceModule::slash_module(get_origin(founder), netuid, key, 10, SlashDestination::Burn));
+		assert_ok!(SubspaceModule::cancel_slash(get_origin(founder), 3));
+
+		let issuance: u64 = Balances::total_issuance();
+		run_to_block(4);
+		assert_eq!(SubspaceModule::get_stake(netuid, &key), stake);
+
+		run_to_block(5);
+		let remaining: u64 = stake - stake / 2 - stake / 10;
+		assert_eq!(SubspaceModule::get_stake(netuid, &key), remaining);
+		assert_eq!(SubspaceModule::get_total_stake_for_key(&key), remaining);
+		assert_eq!(SubspaceModule::get_total_subnet_stake(netuid), stake + remaining);
+		assert_eq!(SubspaceModule::get_total_stake(), stake + remaining);
+		assert_eq!(SubspaceModule::get_balance(&key), 0);
+		assert_eq!(SubspaceModule::get_treasury_balance(), stake / 10);
+		assert_eq!(Balances::total_issuance(), issuance - stake / 2);
+		assert_eq!(SlashHistory::<Test>::get(key, 0).unwrap().amount, stake / 2);
+		assert_eq!(SlashHistory::<Test>::get(key, 2).unwrap().amount, stake / 10);
+		assert!(SlashHistory::<Test>::get(key, 1).is_none());
+	});
+}
+
+#[test]
+fn test_pending_slashes_are_bounded() {
+	new_test_ext().execute_with(|| {
+		use pallet_subspace::{PendingSlashes, PendingSlashesAt, SlashDestination};
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		let key = U256::from(1);
+		register_module(netuid, founder, stake);
+		register_module(netuid, key, stake);
+		SubspaceModule::set_tempo(netuid, 0);
+		SubspaceModule::set_max_pending_slashes(2);
+		assert_ok!(SubspaceModule::set_slash_defer_period(RuntimeOrigin::root(), 0));
+
+		assert_ok!(SubspaceModule::slash_module(get_origin(founder), netuid, key, 10, SlashDestination::Burn));
+		assert_ok!(SubspaceModule::slash_module(get_origin(founder), netuid, key, 10, SlashDestination::Burn));
+		assert_eq!(
+			SubspaceModule::slash_module(get_origin(founder), netuid, key, 10, SlashDestination::Burn),
+			Err(Error::<Test>::TooManyPendingSlashes.into())
+		);
+		assert_ok!(SubspaceModule::cancel_slash(get_origin(founder), 1));
+		assert!(PendingSlashesAt::<Test>::get(1, 1).is_none());
+		assert_ok!(SubspaceModule::slash_module(get_origin(founder), netuid, key, 10, SlashDestination::Burn));
+
+		// slashes without a deferral period apply at the next block and free their place.
+		run_to_block(1);
+		assert_eq!(PendingSlashes::<Test>::count(), 0);
+		assert_eq!(PendingSlashesAt::<Test>::iter_prefix(1).count(), 0);
+		assert_eq!(SubspaceModule::get_stake(netuid, &key), stake - 2 * (stake / 10));
+	});
+}
+
+#[test]
+fn test_stake_can_not_escape_a_pending_slash() {
+	new_test_ext().execute_with(|| {
+		use pallet_subspace::{SlashDestination, SlashHistory};
+		let stake: u64 = 1_000_000_000;
+		let founder = U256::from(0);
+		let key = U256::from(1);
+		register_module(0, founder, stake);
+		register_module(0, key, stake);
+		register_module(1, key, stake);
+		register_module(0, U256::from(2), stake);
+		SubspaceModule::set_tempo(0, 0);
+		SubspaceModule::set_tempo(1, 0);
+		assert_ok!(SubspaceModule::set_slash_defer_period(RuntimeOrigin::root(), 5));
+		assert_ok!(SubspaceModule::slash_module(get_origin(founder), 0, key, 50, SlashDestination::Burn));
+
+		// the stake can neither be removed nor moved during the deferral period.
+		assert_eq!(SubspaceModule::remove_stake(get_origin(key), 0, stake), Err(Error::<Test>::StakeHasPendingSlash.into()));
+		assert_eq!(SubspaceModule::remove_stake(get_origin(key), 1, stake), Err(Error::<Test>::StakeHasPendingSlash.into()));
+		assert_eq!(SubspaceModule::transfer_stake(get_origin(key), 0, 1, stake), Err(Error::<Test>::StakeHasPendingSlash.into()));
+		assert_eq!(SubspaceModule::transfer_stake_to(get_origin(key), 0, U256::from(2), stake), Err(Error::<Test>::StakeHasPendingSlash.into()));
+
+		// stake added meanwhile is not slashed, the amount was fixed when the slash was scheduled.
+		add_stake_and_balance(0, key, stake);
+		run_to_block(5);
+		assert_eq!(SubspaceModule::get_stake(0, &key), 2 * stake - stake / 2);
+		assert_eq!(SlashHistory::<Test>::get(key, 0).unwrap().amount, stake / 2);
+
+		// once the slash is applied the stake is free again.
+		assert_ok!(SubspaceModule::remove_stake(get_origin(key), 0, stake));
+		assert_ok!(SubspaceModule::transfer_stake(get_origin(key), 1, 0, stake));
+		assert_eq!(SubspaceModule::get_stake(0, &key), 2 * stake - stake / 2);
+	});
+}
+
+#[test]
+fn test_vesting_schedule() {
+	new_test_ext().execute_with(|| {
+		use frame_support::traits::ExistenceRequirement::AllowDeath;
+		use pallet_subspace::{Vesting, VestingSchedule};
+		let key = U256::from(1);
+		let other = U256::from(2);
+		add_balance(key, 1_000);
+		SubspaceModule::add_vesting_schedule(&key, VestingSchedule { locked: 1_000, start: 0, cliff: 10, duration: 100 });
+		assert!(<Balances as Currency<U256>>::transfer(&key, &other, 1, AllowDeath).is_err());
+		assert_eq!(SubspaceModule::vest(get_origin(other)), Err(Error::<Test>::NotVesting.into()));
+
+		// nothing unlocks before the cliff, then half of it after half of the duration.
+		run_to_block(10);
+		assert_ok!(SubspaceModule::vest(get_origin(key)));
+		assert!(<Balances as Currency<U256>>::transfer(&key, &other, 1, AllowDeath).is_err());
+		run_to_block(60);
+		assert_ok!(SubspaceModule::vest(get_origin(key)));
+		assert_ok!(<Balances as Currency<U256>>::transfer(&key, &other, 500, AllowDeath));
+		assert!(<Balances as Currency<U256>>::transfer(&key, &other, 1, AllowDeath).is_err());
+
+		run_to_block(110);
+		assert_ok!(SubspaceModule::vest(get_origin(key)));
+		assert!(Vesting::<Test>::get(key).is_none());
+		assert_ok!(<Balances as Currency<U256>>::transfer(&key, &other, 500, AllowDeath));
+		assert_eq!(SubspaceModule::vest(get_origin(key)), Err(Error::<Test>::NotVesting.into()));
+	});
+}
@@ -0,0 +1,93 @@
+mod mock;
+use mock::*;
+use sp_core::U256;
+
+fn epoch_emission_events(netuid: u16) -> Vec<(u64, u64)> {
+    System::events().iter().filter_map(|record| match record.event {
+        RuntimeEvent::SubspaceModule(pallet_subspace::Event::EpochEmission(event_netuid, pending, distributed)) if event_netuid == netuid => Some((pending, distributed)),
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn test_blocks_until_next_epoch() {
+    new_test_ext().execute_with(|| {
+        // tempo 0 pauses the subnet
+        assert_eq!(SubspaceModule::blocks_until_next_epoch(0, 0, 0), u64::MAX);
+        assert_eq!(SubspaceModule::blocks_until_next_epoch(3, 0, 17), u64::MAX);
+
+        // tempo 1 runs every block
+        for block in 0..10 {
+            assert_eq!(SubspaceModule::blocks_until_next_epoch(0, 1, block), 0);
+            assert_eq!(SubspaceModule::blocks_until_next_epoch(1, 1, block), 0);
+        }
+
+        assert_eq!(SubspaceModule::blocks_until_next_epoch(0, 2, 0), 0);
+        assert_eq!(SubspaceModule::blocks_until_next_epoch(1, 2, 0), 1);
+        assert_eq!(SubspaceModule::blocks_until_next_epoch(0, 100, 1), 99);
+        assert_eq!(SubspaceModule::blocks_until_next_epoch(1, 100, 1), 98);
+        assert_eq!(SubspaceModule::blocks_until_next_epoch(0, u16::MAX, u64::MAX - 1), 1);
+
+        // general case: counting down lands exactly on the epoch block
+        for netuid in 0..10 as u16 {
+            for tempo in 1..20 as u16 {
+                for block in 0..40 as u64 {
+                    let blocks = SubspaceModule::blocks_until_next_epoch(netuid, tempo, block);
+                    assert!(blocks < tempo as u64);
+                    assert_eq!((block + blocks + netuid as u64) % tempo as u64, 0);
+                }
+            }
+        }
+    });
+}
+
+#[test]
+fn test_zero_tempo_pauses_subnet() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        let key = U256::from(0);
+        register_module(netuid, key, 1_000_000_000);
+        SubspaceModule::set_tempo(netuid, 0);
+        let stake_before: u64 = SubspaceModule::get_stake_for_key(netuid, &key);
+
+        step_block(10);
+        assert_eq!(SubspaceModule::get_pending_emission(netuid), 0);
+        assert_eq!(SubspaceModule::get_stake_for_key(netuid, &key), stake_before);
+        assert!(epoch_emission_events(netuid).is_empty());
+    });
+}
+
+#[test]
+fn test_empty_subnet_does_not_accrue() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = SubspaceModule::add_network_from_name("empty".as_bytes().to_vec().try_into().unwrap(), &U256::from(0)).unwrap();
+        SubspaceModule::set_tempo(netuid, 5);
+
+        step_block(10);
+        assert_eq!(SubspaceModule::get_pending_emission(netuid), 0);
+        for (pending, distributed) in epoch_emission_events(netuid) {
+            assert_eq!(pending, 0);
+            assert_eq!(distributed, 0);
+        }
+    });
+}
+
+#[test]
+fn test_epoch_emission_reconciliation() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        register_module(netuid, U256::from(0), 1_000_000_000);
+        register_module(netuid, U256::from(1), 1_000_000_000);
+        SubspaceModule::set_tempo(netuid, 5);
+        let total_stake_before: u64 = SubspaceModule::get_total_subnet_stake(netuid);
+
+        step_block(5);
+        let events = epoch_emission_events(netuid);
+        assert_eq!(events.len(), 1);
+        let (pending, distributed) = events[0];
+        assert!(pending > 0);
+        assert!(distributed <= pending);
+        assert_eq!(SubspaceModule::get_pending_emission(netuid), 0);
+        assert_eq!(SubspaceModule::get_total_subnet_stake(netuid), total_stake_before + distributed);
+    });
+}
@@ -0,0 +1,111 @@
+mod mock;
+use mock::*;
+use pallet_subspace::Error;
+use frame_support::{assert_ok, assert_noop};
+use sp_core::U256;
+
+#[test]
+fn test_emission_shares() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        let founder = U256::from(0);
+        register_module(netuid, founder, 1_000_000_000);
+        SubspaceModule::set_tempo(netuid, 100);
+        SubspaceModule::set_treasury_share(10);
+        assert_ok!(SubspaceModule::set_founder_share(get_origin(founder), netuid, 50));
+
+        let subnet_emission: u64 = SubspaceModule::calculate_network_emission(netuid);
+        let treasury_emission: u64 = subnet_emission * 10 / 100;
+        let founder_emission: u64 = (subnet_emission - treasury_emission) * 50 / 100;
+        let founder_balance: u64 = SubspaceModule::get_balance(&founder);
+        let pending_emission: u64 = SubspaceModule::get_pending_emission(netuid);
+
+        step_block(1);
+        assert_eq!(SubspaceModule::get_treasury_balance(), treasury_emission);
+        assert_eq!(SubspaceModule::get_balance(&founder), founder_balance + founder_emission);
+        assert_eq!(
+            SubspaceModule::get_pending_emission(netuid),
+            pending_emission + subnet_emission - treasury_emission - founder_emission
+        );
+    });
+}
+
+#[test]
+fn test_set_founder_share_checks() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        let founder = U256::from(0);
+        let other = U256::from(1);
+        register_module(netuid, founder, 1_000_000_000);
+        register_module(netuid, other, 1_000_000_000);
+
+        assert_noop!(
+            SubspaceModule::set_founder_share(get_origin(other), netuid, 10),
+            Error::<Test>::NotSubnetFounder
+        );
+        assert_noop!(
+            SubspaceModule::set_founder_share(get_origin(founder), netuid, 101),
+            Error::<Test>::InvalidShare
+        );
+        assert_ok!(SubspaceModule::set_founder_share(get_origin(founder), netuid, 20));
+        assert_eq!(SubspaceModule::get_founder_share(netuid), 20);
+        assert_eq!(SubspaceModule::get_subnet(netuid).founder_share, 20);
+    });
+}
+
+#[test]
+fn test_treasury_spend_proposal() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        let key = U256::from(0);
+        let dest = U256::from(42);
+        register_module(netuid, key, 1_000_000_000);
+        add_balance(key, 2 * SubspaceModule::get_proposal_cost());
+        SubspaceModule::set_proposal_voting_period(5);
+        add_balance(SubspaceModule::get_treasury_account(), 1_000);
+
+        // the treasury can not cover the second spend, so it is rejected
+        assert_ok!(SubspaceModule::add_treasury_proposal(get_origin(key), dest, 600));
+        assert_ok!(SubspaceModule::add_treasury_proposal(get_origin(key), dest, 600));
+        assert_ok!(SubspaceModule::vote_proposal(get_origin(key), 0, true));
+        assert_ok!(SubspaceModule::vote_proposal(get_origin(key), 1, true));
+
+        step_block(5);
+        assert_eq!(SubspaceModule::get_balance(&dest), 600);
+        assert_eq!(SubspaceModule::get_treasury_balance(), 400);
+        assert_eq!(SubspaceModule::get_balance(&key), 0);
+    });
+}
+
+#[test]
+fn test_emission_shares_below_existential_deposit() {
+    new_test_ext().execute_with(|| {
+        let netuid: u16 = 0;
+        let founder = U256::from(0);
+        register_module(netuid, founder, 1_000_000_000);
+        SubspaceModule::set_tempo(netuid, 100);
+        SubspaceModule::set_treasury_share(10);
+
+        // the treasury account does not exist yet and one share is below the existential deposit.
+        let subnet_emission: u64 = SubspaceModule::calculate_network_emission(netuid);
+        let treasury_emission: u64 = subnet_emission * 10 / 100;
+        ExistentialDeposit::set(&(treasury_emission + 1));
+        let pending_emission: u64 = SubspaceModule::get_pending_emission(netuid);
+        let total_supply: u64 = SubspaceModule::get_total_supply();
+
+        // the share is still taken from the modules and kept until it can be minted, it counts in the supply.
+        step_block(1);
+        assert_eq!(SubspaceModule::get_treasury_balance(), 0);
+        assert_eq!(SubspaceModule::get_pending_share(&SubspaceModule::get_treasury_account()), treasury_emission);
+        assert_eq!(SubspaceModule::get_total_pending_shares(), treasury_emission);
+        assert_eq!(SubspaceModule::get_total_supply(), total_supply + subnet_emission);
+        assert_eq!(SubspaceModule::get_pending_emission(netuid), pending_emission + subnet_emission - treasury_emission);
+
+        step_block(1);
+        assert_eq!(SubspaceModule::get_treasury_balance(), 2 * treasury_emission);
+        assert_eq!(SubspaceModule::get_pending_share(&SubspaceModule::get_treasury_account()), 0);
+        assert_eq!(SubspaceModule::get_total_pending_shares(), 0);
+        assert_eq!(SubspaceModule::get_total_supply(), total_supply + 2 * subnet_emission);
+        assert_eq!(SubspaceModule::get_pending_emission(netuid), pending_emission + 2 * (subnet_emission - treasury_emission));
+    });
+}
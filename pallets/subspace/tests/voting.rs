@@ -0,0 +1,173 @@
+mod mock;
+use mock::*;
+use pallet_subspace::{Error, SubnetEmissionMode};
+use frame_support::{assert_ok, assert_noop};
+use frame_system::RawOrigin;
+use sp_core::U256;
+
+fn setup_subnets() -> (U256, U256, U256) {
+    let whale = U256::from(0);
+    let minnow_a = U256::from(1);
+    let minnow_b = U256::from(2);
+    register_module(0, whale, 4_000_000_000);
+    register_module(1, minnow_a, 3_000_000_000);
+    register_module(1, minnow_b, 3_000_000_000);
+    SubspaceModule::set_tempo(0, 1000);
+    SubspaceModule::set_tempo(1, 1000);
+    (whale, minnow_a, minnow_b)
+}
+
+fn assert_share_near(netuid: u16, expected: f64) {
+    let share: f64 = SubspaceModule::get_subnet_vote_share(netuid) as f64 / u16::MAX as f64;
+    assert!((share - expected).abs() < 0.01, "netuid {} share {} expected {}", netuid, share, expected);
+}
+
+#[test]
+fn test_vote_mode_follows_votes() {
+    new_test_ext().execute_with(|| {
+        let (whale, minnow_a, minnow_b) = setup_subnets();
+
+        // stake mode: emission follows where stake is parked
+        let stake_emission_0 = SubspaceModule::calculate_network_emission(0);
+        let stake_emission_1 = SubspaceModule::calculate_network_emission(1);
+        assert!(stake_emission_1 > stake_emission_0);
+
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(whale), vec![1], vec![1]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(minnow_a), vec![0], vec![1]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(minnow_b), vec![0], vec![1]));
+        assert_ok!(SubspaceModule::set_subnet_emission_mode(RawOrigin::Root.into(), SubnetEmissionMode::Vote, 0));
+
+        step_block(1);
+        assert_share_near(0, 0.6);
+        assert_share_near(1, 0.4);
+        assert!(SubspaceModule::calculate_network_emission(0) > SubspaceModule::calculate_network_emission(1));
+    });
+}
+
+#[test]
+fn test_vote_mode_consensus_clip() {
+    new_test_ext().execute_with(|| {
+        let (whale, minnow_a, minnow_b) = setup_subnets();
+
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(whale), vec![1], vec![1]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(minnow_a), vec![0], vec![1]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(minnow_b), vec![0], vec![1]));
+        assert_ok!(SubspaceModule::set_subnet_emission_mode(RawOrigin::Root.into(), SubnetEmissionMode::Vote, 51));
+
+        // the whale alone is below the majority, so its vote is clipped away
+        step_block(1);
+        assert_share_near(0, 1.0);
+        assert_eq!(SubspaceModule::get_subnet_vote_share(1), 0);
+    });
+}
+
+#[test]
+fn test_vote_mode_without_votes_uses_stake() {
+    new_test_ext().execute_with(|| {
+        setup_subnets();
+        let stake_emission_1 = SubspaceModule::calculate_network_emission(1);
+        assert_ok!(SubspaceModule::set_subnet_emission_mode(RawOrigin::Root.into(), SubnetEmissionMode::Vote, 0));
+        step_block(1);
+        assert_eq!(SubspaceModule::calculate_network_emission(1), stake_emission_1);
+    });
+}
+
+#[test]
+fn test_vote_shares_recomputed_on_interval() {
+    new_test_ext().execute_with(|| {
+        let (whale, minnow_a, minnow_b) = setup_subnets();
+        SubspaceModule::set_subnet_vote_interval(10);
+
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(whale), vec![1], vec![1]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(minnow_a), vec![0], vec![1]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(minnow_b), vec![0], vec![1]));
+        assert_ok!(SubspaceModule::set_subnet_emission_mode(RawOrigin::Root.into(), SubnetEmissionMode::Vote, 0));
+        assert_share_near(0, 0.6);
+
+        // a changed vote only counts once the shares are recomputed.
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(whale), vec![0], vec![1]));
+        step_block(9);
+        assert_share_near(0, 0.6);
+        step_block(1);
+        assert_share_near(0, 1.0);
+    });
+}
+
+#[test]
+fn test_subnet_voters_are_bounded() {
+    new_test_ext().execute_with(|| {
+        let (whale, minnow_a, minnow_b) = setup_subnets();
+        SubspaceModule::set_max_subnet_voters(2);
+
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(whale), vec![1], vec![1]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(minnow_a), vec![0], vec![1]));
+        assert_noop!(
+            SubspaceModule::vote_subnet_weights(get_origin(minnow_b), vec![0], vec![1]),
+            Error::<Test>::TooManySubnetVoters
+        );
+
+        // keys with a vote can still change it, withdrawing a vote frees its place.
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(whale), vec![0], vec![1]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(whale), vec![], vec![]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(minnow_b), vec![0], vec![1]));
+    });
+}
+
+#[test]
+fn test_vote_subnet_weights_checks() {
+    new_test_ext().execute_with(|| {
+        let (whale, _, _) = setup_subnets();
+        let unstaked = U256::from(9);
+
+        assert_noop!(
+            SubspaceModule::vote_subnet_weights(get_origin(unstaked), vec![0], vec![1]),
+            Error::<Test>::NotEnoughStakeToVote
+        );
+        assert_noop!(
+            SubspaceModule::vote_subnet_weights(get_origin(whale), vec![0, 1], vec![1]),
+            Error::<Test>::WeightVecNotEqualSize
+        );
+        assert_noop!(
+            SubspaceModule::vote_subnet_weights(get_origin(whale), vec![0, 0], vec![1, 1]),
+            Error::<Test>::DuplicateUids
+        );
+        assert_noop!(
+            SubspaceModule::vote_subnet_weights(get_origin(whale), vec![7], vec![1]),
+            Error::<Test>::NetworkDoesNotExist
+        );
+        assert_noop!(
+            SubspaceModule::set_subnet_emission_mode(get_origin(whale), SubnetEmissionMode::Vote, 0),
+            sp_runtime::DispatchError::BadOrigin
+        );
+        assert_noop!(
+            SubspaceModule::set_subnet_emission_mode(RawOrigin::Root.into(), SubnetEmissionMode::Vote, 101),
+            Error::<Test>::StorageValueOutOfRange
+        );
+    });
+}
+
+#[test]
+fn test_reused_netuid_starts_without_votes_or_slashes() {
+    new_test_ext().execute_with(|| {
+        use pallet_subspace::{PendingSlashes, SlashDestination};
+        let (whale, minnow_a, minnow_b) = setup_subnets();
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(whale), vec![0, 1], vec![1, 1]));
+        assert_ok!(SubspaceModule::vote_subnet_weights(get_origin(minnow_a), vec![1], vec![1]));
+        assert_ok!(SubspaceModule::set_slash_defer_period(RawOrigin::Root.into(), 5));
+        assert_ok!(SubspaceModule::slash_module(get_origin(minnow_a), 1, minnow_b, 50, SlashDestination::Burn));
+
+        // the removed netuid is dropped from every vote and its pending slashes are dropped.
+        SubspaceModule::remove_network_for_netuid(1);
+        register_module(1, U256::from(5), 1_000_000_000);
+        assert_eq!(SubspaceModule::get_netuid_for_name("test1".as_bytes().to_vec()), 1);
+        let whale_netuids: Vec<u16> = SubspaceModule::get_subnet_votes(&whale).iter().map(|(netuid, _)| *netuid).collect();
+        assert_eq!(whale_netuids, vec![0]);
+        assert!(SubspaceModule::get_subnet_votes(&minnow_a).is_empty());
+        assert_eq!(PendingSlashes::<Test>::count(), 0);
+        assert!(!SubspaceModule::has_pending_slash(&minnow_b));
+
+        assert_ok!(SubspaceModule::set_subnet_emission_mode(RawOrigin::Root.into(), SubnetEmissionMode::Vote, 0));
+        assert_share_near(0, 1.0);
+        assert_share_near(1, 0.0);
+    });
+}
@@ -3,7 +3,7 @@ use mock::*;
 use pallet_subspace::{Error};
 use frame_system::Config;
 use frame_support::dispatch::{GetDispatchInfo, DispatchInfo, DispatchClass, Pays};
-use frame_support::{assert_ok};
+use frame_support::{assert_ok, assert_noop};
 use sp_runtime::DispatchError;
 use substrate_fixed::types::I32F32;
 use sp_core::U256;
@@ -326,3 +326,246 @@ fn test_check_len_uids_within_allowed_not_within_network_pool() {
 		assert_eq!(expected, result, "Failed to detect incompatible uids for network");
 	});
 }
+
+/***************************
+  commit-reveal weights tests
+*****************************/
+
+#[test]
+fn test_commit_reveal_weights() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		for i in 0..3 {
+			register_module(netuid, U256::from(i), stake);
+		}
+		SubspaceModule::set_tempo(netuid, 10);
+		let key = U256::from(1);
+		let uids: Vec<u16> = vec![0, 2];
+		let weights: Vec<u16> = vec![1, 3];
+		let salt: Vec<u8> = b"salt".to_vec();
+
+		// only the founder turns commit-reveal on, after which weights cannot be set directly.
+		assert_eq!(
+			SubspaceModule::set_commit_reveal_weights(get_origin(key), netuid, true),
+			Err(Error::<Test>::NotSubnetFounder.into())
+		);
+		assert_ok!(SubspaceModule::set_commit_reveal_weights(get_origin(U256::from(0)), netuid, true));
+		assert_eq!(
+			SubspaceModule::set_weights(get_origin(key), netuid, uids.clone(), weights.clone()),
+			Err(Error::<Test>::CommitRevealEnabled.into())
+		);
+
+		let commit_hash = SubspaceModule::hash_weights(&key, netuid, &uids, &weights, &salt);
+		assert_ok!(SubspaceModule::commit_weights(get_origin(key), netuid, commit_hash));
+		assert_eq!(
+			SubspaceModule::reveal_weights(get_origin(key), netuid, uids.clone(), weights.clone(), salt.clone()),
+			Err(Error::<Test>::WeightRevealTooEarly.into())
+		);
+
+		// committed weights are not used until they are revealed in the next epoch.
+		step_block(9);
+		assert_eq!(
+			SubspaceModule::reveal_weights(get_origin(key), netuid, uids.clone(), weights.clone(), salt.clone()),
+			Err(Error::<Test>::WeightRevealTooEarly.into())
+		);
+		step_block(1);
+		assert!(SubspaceModule::get_weights_sparse(netuid)[1].is_empty());
+		assert_eq!(
+			SubspaceModule::reveal_weights(get_origin(key), netuid, uids.clone(), weights.clone(), b"other".to_vec()),
+			Err(Error::<Test>::InvalidWeightReveal.into())
+		);
+		assert_ok!(SubspaceModule::reveal_weights(get_origin(key), netuid, uids.clone(), weights.clone(), salt.clone()));
+		assert_eq!(SubspaceModule::get_weights_sparse(netuid)[1].len(), 2);
+		assert!(pallet_subspace::WeightCommits::<Test>::get(netuid, 1).is_none());
+	});
+}
+
+#[test]
+fn test_copied_weight_commit_cannot_be_revealed() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		for i in 0..3 {
+			register_module(netuid, U256::from(i), stake);
+		}
+		SubspaceModule::set_tempo(netuid, 10);
+		let key = U256::from(1);
+		let copier = U256::from(2);
+		let uids: Vec<u16> = vec![0, 2];
+		let weights: Vec<u16> = vec![1, 3];
+		let salt: Vec<u8> = b"salt".to_vec();
+		assert_ok!(SubspaceModule::set_commit_reveal_weights(get_origin(U256::from(0)), netuid, true));
+
+		// the copier commits the hash it saw on chain and reveals the weights once the key has revealed them.
+		let commit_hash = SubspaceModule::hash_weights(&key, netuid, &uids, &weights, &salt);
+		assert_ok!(SubspaceModule::commit_weights(get_origin(key), netuid, commit_hash));
+		assert_ok!(SubspaceModule::commit_weights(get_origin(copier), netuid, commit_hash));
+		step_block(10);
+		assert_ok!(SubspaceModule::reveal_weights(get_origin(key), netuid, uids.clone(), weights.clone(), salt.clone()));
+		assert_eq!(
+			SubspaceModule::reveal_weights(get_origin(copier), netuid, uids, weights, salt),
+			Err(Error::<Test>::InvalidWeightReveal.into())
+		);
+		assert!(SubspaceModule::get_weights_sparse(netuid)[2].is_empty());
+	});
+}
+
+#[test]
+fn test_weight_commit_expires() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		for i in 0..3 {
+			register_module(netuid, U256::from(i), stake);
+		}
+		SubspaceModule::set_tempo(netuid, 10);
+		let key = U256::from(1);
+		let uids: Vec<u16> = vec![0, 2];
+		let weights: Vec<u16> = vec![1, 3];
+		let salt: Vec<u8> = b"salt".to_vec();
+
+		assert_eq!(
+			SubspaceModule::commit_weights(get_origin(key), netuid, SubspaceModule::hash_weights(&key, netuid, &uids, &weights, &salt)),
+			Err(Error::<Test>::CommitRevealDisabled.into())
+		);
+		assert_ok!(SubspaceModule::set_commit_reveal_weights(get_origin(U256::from(0)), netuid, true));
+		assert_ok!(SubspaceModule::commit_weights(get_origin(key), netuid, SubspaceModule::hash_weights(&key, netuid, &uids, &weights, &salt)));
+
+		// the commit is dropped at the first epoch after its reveal period.
+		step_block(25);
+		assert!(pallet_subspace::WeightCommits::<Test>::get(netuid, 1).is_none());
+		assert_eq!(
+			SubspaceModule::reveal_weights(get_origin(key), netuid, uids, weights, salt),
+			Err(Error::<Test>::NoWeightCommit.into())
+		);
+	});
+}
+
+#[test]
+fn test_weights_copied_in_the_same_epoch_do_not_count() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		for i in 0..3 {
+			register_module(netuid, U256::from(i), stake);
+		}
+		SubspaceModule::set_tempo(netuid, 10);
+		let key = U256::from(1);
+		let copier = U256::from(2);
+		let uids: Vec<u16> = vec![0, 1];
+		let weights: Vec<u16> = vec![1, 3];
+		assert_ok!(SubspaceModule::set_commit_reveal_weights(get_origin(U256::from(0)), netuid, true));
+		assert_ok!(SubspaceModule::commit_weights(get_origin(key), netuid, SubspaceModule::hash_weights(&key, netuid, &uids, &weights, &b"salt".to_vec())));
+
+		// the copier commits the weights the key revealed at the epoch block and tries to reveal them right away.
+		step_block(10);
+		assert_ok!(SubspaceModule::reveal_weights(get_origin(key), netuid, uids.clone(), weights.clone(), b"salt".to_vec()));
+		assert_ok!(SubspaceModule::commit_weights(get_origin(copier), netuid, SubspaceModule::hash_weights(&copier, netuid, &uids, &weights, &b"copy".to_vec())));
+		step_block(1);
+		assert_eq!(
+			SubspaceModule::reveal_weights(get_origin(copier), netuid, uids.clone(), weights.clone(), b"copy".to_vec()),
+			Err(Error::<Test>::WeightRevealTooEarly.into())
+		);
+
+		// the epoch using the weights of the key runs without the copy, which only counts from the epoch after.
+		step_block(9);
+		assert_eq!(SubspaceModule::get_weights_sparse(netuid)[1].len(), 2);
+		assert!(SubspaceModule::get_weights_sparse(netuid)[2].is_empty());
+		assert_ok!(SubspaceModule::reveal_weights(get_origin(copier), netuid, uids, weights, b"copy".to_vec()));
+		assert_eq!(SubspaceModule::get_weights_sparse(netuid)[2].len(), 2);
+	});
+}
+
+/***************************
+  weight copy detection tests
+*****************************/
+
+#[test]
+fn test_weight_copy_detection() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		for i in 0..4 {
+			register_module(netuid, U256::from(i), stake);
+		}
+		assert_eq!(
+			SubspaceModule::set_weight_copy_detection(get_origin(U256::from(0)), netuid, 101, 100),
+			Err(Error::<Test>::StorageValueOutOfRange.into())
+		);
+		assert_ok!(SubspaceModule::set_weight_copy_detection(get_origin(U256::from(0)), netuid, 95, 100));
+
+		// the first epoch only records the weights of uid 0.
+		assert_ok!(SubspaceModule::set_weights(get_origin(U256::from(0)), netuid, vec![2, 3], vec![1, 3]));
+		SubspaceModule::epoch(netuid, 1_000_000_000);
+		assert!(pallet_subspace::WeightCopyDetections::<Test>::get(netuid, 0).is_none());
+
+		// uid 1 copies the weights uid 0 had, while uid 0 moves on.
+		assert_ok!(SubspaceModule::set_weights(get_origin(U256::from(1)), netuid, vec![2, 3], vec![1, 3]));
+		assert_ok!(SubspaceModule::set_weights(get_origin(U256::from(0)), netuid, vec![2, 3], vec![3, 1]));
+		SubspaceModule::epoch(netuid, 1_000_000_000);
+
+		let detection = pallet_subspace::WeightCopyDetections::<Test>::get(netuid, 1).unwrap();
+		assert_eq!(detection.source_uid, 0);
+		assert!(detection.similarity > u16::MAX / 100 * 99);
+		assert!(pallet_subspace::WeightCopyDetections::<Test>::get(netuid, 0).is_none());
+		assert_eq!(SubspaceModule::get_dividends_for_uid(netuid, 1), 0);
+		assert!(SubspaceModule::get_dividends_for_uid(netuid, 0) > 0);
+	});
+}
+
+#[test]
+fn test_stable_copied_weights_stay_flagged() {
+	new_test_ext().execute_with(|| {
+		let netuid: u16 = 0;
+		let stake: u64 = 1_000_000_000;
+		for i in 0..4 {
+			register_module(netuid, U256::from(i), stake);
+		}
+		SubspaceModule::set_tempo(netuid, 0);
+		assert_ok!(SubspaceModule::set_weight_copy_detection(get_origin(U256::from(0)), netuid, 95, 100));
+		assert_ok!(SubspaceModule::set_weights(get_origin(U256::from(0)), netuid, vec![2, 3], vec![1, 3]));
+		SubspaceModule::epoch(netuid, 1_000_000_000);
+
+		// uid 1 copies the weights of uid 0 once and keeps them, matching its own previous weights from then on.
+		assert_ok!(SubspaceModule::set_weights(get_origin(U256::from(1)), netuid, vec![2, 3], vec![1, 3]));
+		for _ in 0..3 {
+			step_block(1);
+			SubspaceModule::epoch(netuid, 1_000_000_000);
+			let detection = pallet_subspace::WeightCopyDetections::<Test>::get(netuid, 1).unwrap();
+			assert_eq!(detection.block, block_number());
+			assert_eq!(detection.source_uid, 0);
+			assert_eq!(SubspaceModule::get_dividends_for_uid(netuid, 1), 0);
+		}
+	});
+}
+
+/***************************
+  pub fn set_weights_multi() tests
+*****************************/
+
+#[test]
+fn test_set_weights_multi() {
+	new_test_ext().execute_with(|| {
+		let stake: u64 = 1_000_000_000;
+		let key = U256::from(0);
+		for netuid in 0..2 {
+			register_module(netuid, key, stake);
+			register_module(netuid, U256::from(1), stake);
+		}
+
+		assert_ok!(SubspaceModule::set_weights_multi(get_origin(key), vec![(0, vec![1], vec![1]), (1, vec![1], vec![1])]));
+		assert_eq!(SubspaceModule::get_weights_sparse(0)[0].len(), 1);
+		assert_eq!(SubspaceModule::get_weights_sparse(1)[0].len(), 1);
+
+		// one invalid entry reverts the whole batch.
+		assert_noop!(
+			SubspaceModule::set_weights_multi(get_origin(key), vec![(0, vec![1], vec![5]), (1, vec![9], vec![1])]),
+			Error::<Test>::InvalidUid
+		);
+		assert_noop!(
+			SubspaceModule::set_weights_multi(get_origin(key), vec![(0, vec![1], vec![1]), (0, vec![1], vec![2])]),
+			Error::<Test>::DuplicateSubnets
+		);
+	});
+}
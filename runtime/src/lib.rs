@@ -43,7 +43,7 @@ pub use frame_support::{
 		},
 		IdentityFee, Weight, WeightToFeeCoefficients, WeightToFeeCoefficient, WeightToFeePolynomial
 	},
-	StorageValue,
+	PalletId, StorageValue,
 };
 pub use frame_system::Call as SystemCall;
 pub use pallet_balances::Call as BalancesCall;
@@ -111,10 +111,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 110,
+	spec_version: 111,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -314,9 +314,25 @@ impl pallet_sudo::Config for Runtime {
 }
 
 
+parameter_types! {
+	pub const SubspaceTreasuryPalletId: PalletId = PalletId(*b"py/subtr");
+	pub const SubspaceMaxNameLength: u32 = 32;
+	pub const SubspaceMaxAddressLength: u32 = 64;
+	pub const SubspaceMaxWeightsPerModule: u32 = 4096;
+	pub const SubspaceMaxModulesPerSubnet: u32 = 4096;
+}
+
 impl pallet_subspace::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type Currency = Balances;
+	type TreasuryPalletId = SubspaceTreasuryPalletId;
+	type MaxNameLength = SubspaceMaxNameLength;
+	type MaxAddressLength = SubspaceMaxAddressLength;
+	type MaxWeightsPerModule = SubspaceMaxWeightsPerModule;
+	type MaxModulesPerSubnet = SubspaceMaxModulesPerSubnet;
+	type Signature = Signature;
+	type Signer = <Signature as Verify>::Signer;
+	type WeightInfo = pallet_subspace::weight_info::SubstrateWeight<Runtime>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -547,6 +563,21 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl subspace_runtime_api::SubspaceRuntimeApi<Block, Balance> for Runtime {
+		fn get_total_emission_per_block() -> Balance {
+			SubspaceModule::get_total_emission_per_block()
+		}
+		fn get_emission_per_block_at(block: u64) -> Balance {
+			SubspaceModule::get_emission_per_block_at(block)
+		}
+		fn get_projected_emission(blocks: u64) -> Balance {
+			SubspaceModule::get_projected_emission(blocks)
+		}
+		fn get_remaining_supply() -> Balance {
+			SubspaceModule::get_remaining_supply()
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (